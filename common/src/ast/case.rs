@@ -0,0 +1,86 @@
+use crate::util::indent;
+
+use super::expression::Expression;
+use super::pattern::Pattern;
+use super::visitor::Visitor;
+use super::walker::Walk;
+use super::{ASTDepth, Node};
+use std::fmt::Display;
+
+/// A `case expr of pattern -> body, ...` expression.
+/// Evaluates the body of the first arm whose pattern matches the scrutinee.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct Case {
+    pub scrutinee: Node<Expression>,
+    pub arms: Vec<CaseArm>,
+}
+
+impl Case {
+    pub fn new(scrutinee: Node<Expression>, arms: Vec<CaseArm>) -> Self {
+        Self { scrutinee, arms }
+    }
+
+    pub fn arms_iter(&self) -> std::slice::Iter<'_, CaseArm> {
+        self.arms.iter()
+    }
+}
+
+impl Display for Case {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("case ")?;
+        self.scrutinee.fmt(f)?;
+        f.write_str(" of")?;
+        for arm in self.arms.iter() {
+            f.write_str("\n")?;
+            indent(arm).fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl ASTDepth for Case {
+    fn ast_depth(&self) -> usize {
+        1 + self.scrutinee.ast_depth() + self.arms.iter().map(|arm| arm.ast_depth()).sum::<usize>()
+    }
+}
+
+impl Walk for Case {
+    fn walk<V: Visitor<()>>(&self, v: &mut V) {
+        v.case(self);
+        self.scrutinee.walk(v);
+        for arm in self.arms.iter() {
+            arm.pattern.walk(v);
+            arm.body.walk(v);
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct CaseArm {
+    pub pattern: Node<Pattern>,
+    pub body: Node<Expression>,
+}
+
+impl CaseArm {
+    pub fn new(pattern: Node<Pattern>, body: Node<Expression>) -> Self {
+        Self { pattern, body }
+    }
+}
+
+impl Display for CaseArm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.pattern.fmt(f)?;
+        f.write_str(" -> ")?;
+        match &*self.body {
+            // nested case arms would otherwise be parsed as arms of this case
+            Expression::Case(_) => f.write_fmt(format_args!("({})", self.body)),
+            _ => self.body.fmt(f),
+        }
+    }
+}
+
+impl ASTDepth for CaseArm {
+    fn ast_depth(&self) -> usize {
+        self.pattern.ast_depth() + self.body.ast_depth()
+    }
+}
@@ -1,3 +1,4 @@
+pub use super::case::{Case, CaseArm};
 pub use super::fn_call::{FnCall, FnCallArgs};
 use super::fn_def::FnDef;
 pub use super::fn_def::FnDefArgs;
@@ -6,31 +7,34 @@ pub use super::lambda::{Lambda, LambdaArgs, LambdaVariant};
 pub use super::let_expression::{LetBindings, LetExpression};
 pub use super::literal::*;
 pub use super::query::{Query, QueryBinding, QueryBindings, QueryGuards};
-use super::{ASTDepth, Identifier, VarIdentifier, AST};
+use super::{ASTDepth, Identifier, Node, VarIdentifier, AST};
 use std::cell::RefCell;
 use std::fmt::Display;
 use std::rc::Rc;
 
 #[derive(Clone, Eq, Debug)]
 pub enum Expression {
-    Commented(String, Rc<Expression>),
+    Commented(String, Node<Expression>),
     Lit(Literal),
     FnCall(FnCall),
-    OpCall(Identifier, Rc<Expression>, Rc<Expression>),
+    OpCall(Identifier, Node<Expression>, Node<Expression>),
     Var(VarIdentifier),
     ConstOrTypeRef(Identifier),
     DBTypeRef(Identifier),
     PropFnRef(Identifier),
-    EdgeProp(Rc<Expression>, Identifier),
+    FnRef(Identifier),
+    PropAccess(Node<Expression>, Identifier),
+    EdgeProp(Node<Expression>, Identifier),
     IfElse(IfElse),
+    Case(Case),
     Let(LetExpression),
     Lambda(Rc<Lambda>),
     Query(Query),
     Symbol(Identifier),
-    Quoted(Rc<Expression>),
-    QuotedAST(Rc<AST>),
-    Unquoted(Rc<Expression>),
-    UnquotedAST(Rc<AST>),
+    Quoted(Node<Expression>),
+    QuotedAST(Node<AST>),
+    Unquoted(Node<Expression>),
+    UnquotedAST(Node<AST>),
     InlineFnDef(Rc<RefCell<FnDef>>),
 }
 
@@ -49,10 +53,15 @@ impl PartialEq for Expression {
             (Expression::ConstOrTypeRef(id1), Expression::ConstOrTypeRef(id2)) => id1.eq(id2),
             (Expression::DBTypeRef(id1), Expression::DBTypeRef(id2)) => id1.eq(id2),
             (Expression::PropFnRef(id1), Expression::PropFnRef(id2)) => id1.eq(id2),
+            (Expression::FnRef(id1), Expression::FnRef(id2)) => id1.eq(id2),
+            (Expression::PropAccess(expr1, prop1), Expression::PropAccess(expr2, prop2)) => {
+                expr1.eq(expr2) && prop1.eq(prop2)
+            }
             (Expression::EdgeProp(expr1, edge1), Expression::EdgeProp(expr2, edge2)) => {
                 expr1.eq(expr2) && edge1.eq(edge2)
             }
             (Expression::IfElse(if_else1), Expression::IfElse(if_else2)) => if_else1.eq(if_else2),
+            (Expression::Case(case1), Expression::Case(case2)) => case1.eq(case2),
             (Expression::Let(l1), Expression::Let(l2)) => l1.eq(l2),
             (Expression::Lambda(l1), Expression::Lambda(l2)) => l1.eq(l2),
             (Expression::Query(q1), Expression::Query(q2)) => q1.eq(q2),
@@ -85,11 +94,17 @@ impl core::hash::Hash for Expression {
             Expression::ConstOrTypeRef(id) => id.hash(state),
             Expression::DBTypeRef(id) => id.hash(state),
             Expression::PropFnRef(id) => id.hash(state),
+            Expression::FnRef(id) => id.hash(state),
+            Expression::PropAccess(expr, prop) => {
+                expr.hash(state);
+                prop.hash(state)
+            }
             Expression::EdgeProp(expr, edge) => {
                 expr.hash(state);
                 edge.hash(state)
             }
             Expression::IfElse(if_else) => if_else.hash(state),
+            Expression::Case(case) => case.hash(state),
             Expression::Let(let_expr) => let_expr.hash(state),
             Expression::Lambda(lambda) => lambda.hash(state),
             Expression::Query(query) => query.hash(state),
@@ -114,8 +129,11 @@ impl ASTDepth for Expression {
             Expression::ConstOrTypeRef(_id) => 1,
             Expression::DBTypeRef(_id) => 1,
             Expression::PropFnRef(_id) => 1,
+            Expression::FnRef(_id) => 1,
+            Expression::PropAccess(expr, _prop) => 1 + expr.ast_depth(),
             Expression::EdgeProp(expr, _edge) => 1 + expr.ast_depth(),
             Expression::IfElse(if_else) => if_else.ast_depth(),
+            Expression::Case(case) => case.ast_depth(),
             Expression::Let(let_expr) => let_expr.ast_depth(),
             Expression::Lambda(lambda) => lambda.ast_depth(),
             Expression::Query(query) => query.ast_depth(),
@@ -159,12 +177,29 @@ impl Display for Expression {
                 f.write_str(".")?;
                 f.write_str(id)
             }
+            Expression::FnRef(id) => {
+                f.write_str("&")?;
+                f.write_str(id)
+            }
+            Expression::PropAccess(expr, prop) => {
+                // fn and op calls print their own parentheses
+                match &**expr {
+                    Expression::Var(_)
+                    | Expression::PropAccess(_, _)
+                    | Expression::FnCall(_)
+                    | Expression::OpCall(_, _, _) => expr.fmt(f)?,
+                    _ => f.write_fmt(format_args!("({expr})"))?,
+                }
+                f.write_str(".")?;
+                f.write_str(prop)
+            }
             Expression::EdgeProp(expr, edge) => {
                 expr.fmt(f)?;
                 f.write_str("#")?;
                 edge.fmt(f)
             }
             Expression::IfElse(if_else) => if_else.fmt(f),
+            Expression::Case(case) => case.fmt(f),
             Expression::Let(let_expr) => let_expr.fmt(f),
             Expression::Lambda(lambda) => lambda.fmt(f),
             Expression::Query(query) => query.fmt(f),
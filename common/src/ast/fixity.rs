@@ -0,0 +1,109 @@
+use std::fmt::Display;
+
+use super::{ASTDepth, Identifier};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum Associativity {
+    Left,
+    Right,
+    None,
+}
+
+/// Precedence and associativity of an infix operator.
+/// Operators with a higher precedence bind more tightly, ranging from 0 to 9.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Fixity {
+    associativity: Associativity,
+    precedence: u8,
+}
+
+impl Fixity {
+    /// Fixity of any operator without a declared or builtin fixity.
+    pub const DEFAULT: Fixity = Fixity::left(9);
+
+    pub const fn new(associativity: Associativity, precedence: u8) -> Fixity {
+        Fixity {
+            associativity,
+            precedence,
+        }
+    }
+
+    pub const fn left(precedence: u8) -> Fixity {
+        Fixity::new(Associativity::Left, precedence)
+    }
+
+    pub const fn right(precedence: u8) -> Fixity {
+        Fixity::new(Associativity::Right, precedence)
+    }
+
+    pub const fn non_assoc(precedence: u8) -> Fixity {
+        Fixity::new(Associativity::None, precedence)
+    }
+
+    pub fn associativity(&self) -> Associativity {
+        self.associativity
+    }
+
+    pub fn precedence(&self) -> u8 {
+        self.precedence
+    }
+
+    /// Returns the fixity of the operators defined in the Std library.
+    pub fn builtin(op: &str) -> Option<Fixity> {
+        match op {
+            "|>" => Some(Fixity::left(0)),
+            "||" => Some(Fixity::right(2)),
+            "&&" => Some(Fixity::right(3)),
+            "==" | "!=" | "<" | ">" | "<=" | ">=" => Some(Fixity::non_assoc(4)),
+            "++" => Some(Fixity::right(5)),
+            "+" | "-" => Some(Fixity::left(6)),
+            "*" | "/" | "%" => Some(Fixity::left(7)),
+            "^" => Some(Fixity::right(8)),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Fixity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.associativity {
+            Associativity::Left => f.write_str("infixl ")?,
+            Associativity::Right => f.write_str("infixr ")?,
+            Associativity::None => f.write_str("infix ")?,
+        }
+        self.precedence.fmt(f)
+    }
+}
+
+/// A fixity declaration for an operator, e.g. `infixl 6 <+>`.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct FixityDef {
+    id: Identifier,
+    fixity: Fixity,
+}
+
+impl FixityDef {
+    pub fn new(id: Identifier, fixity: Fixity) -> FixityDef {
+        FixityDef { id, fixity }
+    }
+
+    pub fn id(&self) -> &Identifier {
+        &self.id
+    }
+
+    pub fn fixity(&self) -> Fixity {
+        self.fixity
+    }
+}
+
+impl Display for FixityDef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{} {}", self.fixity, self.id))
+    }
+}
+
+impl ASTDepth for FixityDef {
+    fn ast_depth(&self) -> usize {
+        1
+    }
+}
@@ -1,7 +1,6 @@
 use super::expression::Expression;
-use super::{ASTDepth, Identifier};
+use super::{ASTDepth, Identifier, Node};
 use std::fmt::Display;
-use std::rc::Rc;
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct FnCall {
@@ -33,11 +32,11 @@ impl Display for FnCall {
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct FnCallArgs<T: Clone = Expression> {
-    args: Vec<Rc<T>>,
+    args: Vec<Node<T>>,
 }
 
 impl<T: Clone> FnCallArgs<T> {
-    pub fn new<Args: IntoIterator<Item = Rc<T>>>(args: Args) -> Self {
+    pub fn new<Args: IntoIterator<Item = Node<T>>>(args: Args) -> Self {
         FnCallArgs {
             args: args.into_iter().collect(),
         }
@@ -45,7 +44,7 @@ impl<T: Clone> FnCallArgs<T> {
 
     pub fn from_owned(args: Vec<T>) -> Self {
         FnCallArgs {
-            args: args.iter().map(|a| Rc::new(a.clone())).collect(),
+            args: args.iter().map(|a| Node::new(a.clone())).collect(),
         }
     }
 
@@ -53,7 +52,7 @@ impl<T: Clone> FnCallArgs<T> {
         FnCallArgs { args: Vec::new() }
     }
 
-    pub fn prepend_arg(&mut self, arg: Rc<T>) {
+    pub fn prepend_arg(&mut self, arg: Node<T>) {
         self.args.insert(0, arg);
     }
 
@@ -65,7 +64,7 @@ impl<T: Clone> FnCallArgs<T> {
         self.len() == 0
     }
 
-    pub fn iter(&self) -> std::slice::Iter<Rc<T>> {
+    pub fn iter(&self) -> std::slice::Iter<Node<T>> {
         self.args.iter()
     }
 }
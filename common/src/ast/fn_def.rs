@@ -1,5 +1,5 @@
 use super::pattern::Pattern;
-use super::{expression::Expression, walker::Walk, ASTDepth, Identifier};
+use super::{expression::Expression, walker::Walk, ASTDepth, Identifier, Node};
 use crate::{native_fn::NativeFn, util::indent};
 use std::cell::RefCell;
 use std::hash::{Hash, Hasher};
@@ -10,7 +10,7 @@ pub struct FnDef {
     pub is_inline: bool,
     pub id: Identifier,
     variants: FnDefVariants,
-    is_tail_recursive: bool,
+    module_id: Option<Identifier>,
 }
 
 impl PartialEq for FnDef {
@@ -18,7 +18,6 @@ impl PartialEq for FnDef {
         self.is_inline.eq(&other.is_inline)
             && self.id.eq(&other.id)
             && self.variants.eq(&other.variants)
-            && self.is_tail_recursive.eq(&other.is_tail_recursive)
     }
 }
 
@@ -27,7 +26,6 @@ impl Hash for FnDef {
         Hash::hash(&self.id, h);
         Hash::hash(&self.is_inline, h);
         Hash::hash(&self.variants, h);
-        Hash::hash(&self.is_tail_recursive, h);
     }
 }
 
@@ -38,12 +36,11 @@ impl FnDef {
         body: Rc<FnDefBody>,
     ) -> Rc<RefCell<FnDef>> {
         let id = id.into();
-        let is_tail_recursive = body.is_tail_recursive(&id);
         Rc::new(RefCell::new(FnDef {
             is_inline: false,
             id,
-            variants: FnDefVariants::new([FnDefVariant(args, body)]),
-            is_tail_recursive,
+            variants: FnDefVariants::new([FnDefVariant(args, None, body)]),
+            module_id: None,
         }))
     }
 
@@ -53,12 +50,11 @@ impl FnDef {
         body: Rc<FnDefBody>,
     ) -> Rc<RefCell<FnDef>> {
         let id = id.into();
-        let is_tail_recursive = body.is_tail_recursive(&id);
         Rc::new(RefCell::new(FnDef {
             is_inline: true,
             id,
-            variants: FnDefVariants::new([FnDefVariant(args, body)]),
-            is_tail_recursive,
+            variants: FnDefVariants::new([FnDefVariant(args, None, body)]),
+            module_id: None,
         }))
     }
 
@@ -68,12 +64,11 @@ impl FnDef {
     ) -> Rc<RefCell<FnDef>> {
         let id = id.into();
         let variants = variants.into();
-        let is_tail_recursive = variants.iter().any(|v| v.is_tail_recursive(&id));
         Rc::new(RefCell::new(FnDef {
             is_inline: false,
             id,
             variants,
-            is_tail_recursive,
+            module_id: None,
         }))
     }
 
@@ -83,38 +78,62 @@ impl FnDef {
     ) -> Rc<RefCell<FnDef>> {
         let id = id.into();
         let variants = variants.into();
-        let is_tail_recursive = variants.iter().any(|v| v.is_tail_recursive(&id));
         Rc::new(RefCell::new(FnDef {
             is_inline: true,
             id,
             variants,
-            is_tail_recursive,
+            module_id: None,
         }))
     }
 
     pub fn add_variant(&mut self, args: FnDefArgs, body: Rc<FnDefBody>) {
-        self.is_tail_recursive = self.is_tail_recursive || body.is_tail_recursive(&self.id);
-        self.variants.add(args, body);
+        self.push_variant(FnDefVariant(args, None, body));
+    }
+
+    pub fn push_variant(&mut self, variant: FnDefVariant) {
+        self.variants.add(variant);
     }
 
     pub fn id(&self) -> &Identifier {
         &self.id
     }
 
+    /// The id of the module this function was defined in, if any.
+    /// Inline (`let`) functions don't belong to a module.
+    pub fn module_id(&self) -> Option<&Identifier> {
+        self.module_id.as_ref()
+    }
+
+    pub fn set_module_id(&mut self, module_id: Identifier) {
+        self.module_id = Some(module_id);
+    }
+
     pub fn required_args(&self) -> usize {
         self.variants
             .iter()
-            .map(|FnDefVariant(args, _)| args.required_args())
+            .map(|FnDefVariant(args, _, _)| args.required_args())
             .min()
             .unwrap_or_default()
     }
 
-    pub fn get_variant(&self, index: usize) -> Option<&FnDefVariant> {
-        self.variants.get_variant(index)
+    /// The most arguments any of the variants accepts.
+    pub fn max_args(&self) -> usize {
+        self.variants
+            .iter()
+            .map(|FnDefVariant(args, _, _)| args.len())
+            .max()
+            .unwrap_or_default()
     }
 
-    pub fn is_tail_recursive(&self) -> bool {
-        self.is_tail_recursive
+    /// Whether any of the variants is implemented natively in Rust.
+    pub fn is_native(&self) -> bool {
+        self.variants
+            .iter()
+            .any(|FnDefVariant(_, _, body)| matches!(**body, FnDefBody::NativeFn(_)))
+    }
+
+    pub fn get_variant(&self, index: usize) -> Option<&FnDefVariant> {
+        self.variants.get_variant(index)
     }
 
     pub fn variants_iter(&self) -> std::slice::Iter<FnDefVariant> {
@@ -124,7 +143,7 @@ impl FnDef {
 
 impl Display for FnDef {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for FnDefVariant(args, body) in self.variants.iter() {
+        for FnDefVariant(args, guard, body) in self.variants.iter() {
             if !self.is_inline {
                 f.write_str("let ")?;
             }
@@ -132,6 +151,9 @@ impl Display for FnDef {
             self.id.fmt(f)?;
             f.write_str(" ")?;
             args.fmt(f)?;
+            if let Some(guard) = guard {
+                f.write_fmt(format_args!(" when {guard}"))?;
+            }
             f.write_str(" =\n")?;
 
             match &**body {
@@ -150,30 +172,26 @@ impl ASTDepth for FnDef {
     }
 }
 
+/// A single variant of a function: its argument patterns,
+/// an optional `when` guard and the function body.
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub struct FnDefVariant(pub FnDefArgs, pub Rc<FnDefBody>);
-
-impl FnDefVariant {
-    pub fn is_tail_recursive(&self, id: &Identifier) -> bool {
-        match &*self.1 {
-            FnDefBody::RogatoFn(body) => match &**body {
-                Expression::FnCall(fn_call) => fn_call.id == *id,
-                _ => false,
-            },
-            _ => false,
-        }
-    }
-}
+pub struct FnDefVariant(
+    pub FnDefArgs,
+    pub Option<Node<Expression>>,
+    pub Rc<FnDefBody>,
+);
 
 impl From<(FnDefArgs, Rc<FnDefBody>)> for FnDefVariant {
     fn from(variant: (FnDefArgs, Rc<FnDefBody>)) -> Self {
-        FnDefVariant(variant.0, variant.1)
+        FnDefVariant(variant.0, None, variant.1)
     }
 }
 
 impl ASTDepth for FnDefVariant {
     fn ast_depth(&self) -> usize {
-        self.0.ast_depth() + self.1.ast_depth()
+        self.0.ast_depth()
+            + self.1.as_ref().map(|guard| guard.ast_depth()).unwrap_or(0)
+            + self.2.ast_depth()
     }
 }
 
@@ -193,8 +211,8 @@ impl FnDefVariants {
         self.variants.iter()
     }
 
-    pub fn add(&mut self, args: FnDefArgs, body: Rc<FnDefBody>) {
-        self.variants.push(FnDefVariant(args, body))
+    pub fn add(&mut self, variant: FnDefVariant) {
+        self.variants.push(variant)
     }
 
     pub fn get_variant(&self, index: usize) -> Option<&FnDefVariant> {
@@ -216,7 +234,7 @@ impl ASTDepth for FnDefVariants {
     fn ast_depth(&self) -> usize {
         self.variants
             .iter()
-            .map(|v| v.0.len() + v.1.ast_depth())
+            .map(|v| v.0.len() + v.2.ast_depth())
             .sum::<usize>()
     }
 }
@@ -225,6 +243,7 @@ impl Hash for FnDefVariants {
     fn hash<H: Hasher>(&self, h: &mut H) {
         for v in self.variants.iter() {
             Hash::hash(&v.0, h);
+            Hash::hash(&v.1, h);
         }
     }
 }
@@ -237,11 +256,11 @@ impl PartialEq for FnDefVariants {
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct FnDefArgs {
-    args: Vec<Rc<Pattern>>,
+    args: Vec<Node<Pattern>>,
 }
 
 impl FnDefArgs {
-    pub fn new(args: Vec<Rc<Pattern>>) -> Self {
+    pub fn new(args: Vec<Node<Pattern>>) -> Self {
         FnDefArgs { args }
     }
 
@@ -254,6 +273,12 @@ impl FnDefArgs {
         self.len() - optional_args
     }
 
+    /// Whether a call with the given number of arguments can be matched against these patterns,
+    /// i.e. it supplies all required arguments and no more than there are patterns.
+    pub fn accepts(&self, arg_count: usize) -> bool {
+        self.required_args() <= arg_count && arg_count <= self.len()
+    }
+
     pub fn is_optional_arg(p: &Pattern) -> bool {
         match p {
             Pattern::Var(v) => v.as_str().starts_with('?'),
@@ -269,7 +294,7 @@ impl FnDefArgs {
         self.len() == 0
     }
 
-    pub fn iter(&self) -> std::slice::Iter<Rc<Pattern>> {
+    pub fn iter(&self) -> std::slice::Iter<Node<Pattern>> {
         self.args.iter()
     }
 }
@@ -297,7 +322,7 @@ impl ASTDepth for FnDefArgs {
 #[derive(Clone)]
 pub enum FnDefBody {
     NativeFn(NativeFn),
-    RogatoFn(Rc<Expression>),
+    RogatoFn(Node<Expression>),
 }
 
 impl FnDefBody {
@@ -305,23 +330,9 @@ impl FnDefBody {
         FnDefBody::NativeFn(f)
     }
 
-    pub fn rogato(expr: Rc<Expression>) -> FnDefBody {
+    pub fn rogato(expr: Node<Expression>) -> FnDefBody {
         FnDefBody::RogatoFn(expr)
     }
-
-    pub fn is_tail_recursive(&self, id: &Identifier) -> bool {
-        match self {
-            Self::RogatoFn(body) => match &**body {
-                Expression::FnCall(fn_call) => fn_call.id == *id,
-                Expression::Let(let_expr) => match &*let_expr.body {
-                    Expression::FnCall(fn_call) => fn_call.id == *id,
-                    _ => false,
-                },
-                _ => false,
-            },
-            _ => false,
-        }
-    }
 }
 
 impl PartialEq for FnDefBody {
@@ -4,64 +4,76 @@ use crate::ast::expression::{
     FnCall, FnCallArgs, Lambda, LambdaArgs, LambdaVariant, LetBindings, LetExpression, Query,
     QueryBinding, QueryBindings, QueryGuards, StructProps, TupleItems,
 };
-use crate::ast::fn_def::FnDef;
+use crate::ast::fixity::{Fixity, FixityDef};
+use crate::ast::fn_def::{FnDef, FnDefVariant, FnDefVariants};
+use crate::ast::macro_def::MacroDef;
 use crate::ast::module_def::{ModuleDef, ModuleExports};
 use crate::ast::type_expression::TypeDef;
+use crate::ast::use_def::{UseDef, UseImport, UseImports};
 use crate::ast::{
     expression::{
         Expression::{self, *},
         FnDefArgs,
         Literal::{self, *},
     },
-    AST,
+    Node, AST,
 };
 use crate::ast::{type_expression::TypeExpression, Program};
 use std::rc::Rc;
 
-use super::expression::{IfElse, MapKVPair};
+use super::expression::StringPart;
+use super::expression::{Case, CaseArm, IfElse, MapKVPair};
 use super::fn_def::FnDefBody;
 use super::pattern::Pattern;
-use super::type_expression::StructTypeProperties;
+use super::type_expression::{StructTypeProperties, UnionTypeVariants};
 use super::{Identifier, VarIdentifier};
 
-pub fn program<Nodes: IntoIterator<Item = Rc<AST>>>(nodes: Nodes) -> Program {
+pub fn program<Nodes: IntoIterator<Item = Node<AST>>>(nodes: Nodes) -> Program {
     Program::from_iter(nodes)
 }
 
-pub fn lit(lit: Literal) -> Rc<Expression> {
-    Rc::new(Lit(lit))
+pub fn lit(lit: Literal) -> Node<Expression> {
+    Node::new(Lit(lit))
 }
 
-pub fn number_lit<Num>(val: Num) -> Rc<Expression>
+pub fn number_lit<Num>(val: Num) -> Node<Expression>
 where
     Decimal: From<Num>,
 {
     lit(Number(Decimal::from(val)))
 }
-pub fn bool_lit(val: bool) -> Rc<Expression> {
+pub fn bool_lit(val: bool) -> Node<Expression> {
     lit(Bool(val))
 }
 
-pub fn string_lit(val: &str) -> Rc<Expression> {
+pub fn string_lit(val: &str) -> Node<Expression> {
     lit(String(val.to_string()))
 }
 
-pub fn tuple_lit<Iter: IntoIterator<Item = Rc<Expression>>>(vals: Iter) -> Rc<Expression> {
+pub fn interpolated_string_lit<Parts: IntoIterator<Item = StringPart>>(
+    parts: Parts,
+) -> Node<Expression> {
+    lit(InterpolatedString(
+        crate::ast::expression::InterpolatedString::new(parts.into_iter().collect()),
+    ))
+}
+
+pub fn tuple_lit<Iter: IntoIterator<Item = Node<Expression>>>(vals: Iter) -> Node<Expression> {
     lit(Tuple(TupleItems::from_iter(vals)))
 }
 
-pub fn list_lit<Iter: IntoIterator<Item = Rc<Expression>>>(vals: Iter) -> Rc<Expression> {
+pub fn list_lit<Iter: IntoIterator<Item = Node<Expression>>>(vals: Iter) -> Node<Expression> {
     lit(List(TupleItems::from_iter(vals)))
 }
 
-pub fn list_cons(first: Rc<Expression>, rest: Rc<Expression>) -> Rc<Expression> {
+pub fn list_cons(first: Node<Expression>, rest: Node<Expression>) -> Node<Expression> {
     lit(ListCons(first, rest))
 }
 
-pub fn struct_lit<S: Into<Identifier>, Props: IntoIterator<Item = (S, Rc<Expression>)>>(
+pub fn struct_lit<S: Into<Identifier>, Props: IntoIterator<Item = (S, Node<Expression>)>>(
     id: S,
     raw_props: Props,
-) -> Rc<Expression> {
+) -> Node<Expression> {
     let mut props = Vec::new();
     for (id, expr) in raw_props.into_iter() {
         props.push((id.into(), expr))
@@ -69,86 +81,134 @@ pub fn struct_lit<S: Into<Identifier>, Props: IntoIterator<Item = (S, Rc<Express
     lit(Struct(id.into(), Rc::new(StructProps::from(props))))
 }
 
-pub fn map_lit<Iter: IntoIterator<Item = (Rc<Expression>, Rc<Expression>)>>(
+pub fn map_lit<Iter: IntoIterator<Item = (Node<Expression>, Node<Expression>)>>(
     items: Iter,
-) -> Rc<Expression> {
-    let kv_pairs: Vec<Rc<MapKVPair<Expression>>> = items
+) -> Node<Expression> {
+    let kv_pairs: Vec<Node<MapKVPair<Expression>>> = items
         .into_iter()
-        .map(|kvp| Rc::new(MapKVPair::new(kvp.0, kvp.1)))
+        .map(|kvp| Node::new(MapKVPair::new(kvp.0, kvp.1)))
         .collect();
     lit(Map(TupleItems::from_iter(kv_pairs)))
 }
 
-pub fn map_cons_lit<Iter: IntoIterator<Item = (Rc<Expression>, Rc<Expression>)>>(
+pub fn struct_update_lit<S: Into<Identifier>, Props: IntoIterator<Item = (S, Node<Expression>)>>(
+    base: Node<Expression>,
+    raw_props: Props,
+) -> Node<Expression> {
+    let mut props = Vec::new();
+    for (id, expr) in raw_props.into_iter() {
+        props.push((id.into(), expr))
+    }
+    lit(StructUpdate(base, Rc::new(StructProps::from(props))))
+}
+
+pub fn map_cons_lit<Iter: IntoIterator<Item = (Node<Expression>, Node<Expression>)>>(
     items: Iter,
-    rest: Rc<Expression>,
-) -> Rc<Expression> {
-    let kv_pairs: Vec<Rc<MapKVPair<Expression>>> = items
+    rest: Node<Expression>,
+) -> Node<Expression> {
+    let kv_pairs: Vec<Node<MapKVPair<Expression>>> = items
         .into_iter()
-        .map(|kvp| Rc::new(MapKVPair::new(kvp.0, kvp.1)))
+        .map(|kvp| Node::new(MapKVPair::new(kvp.0, kvp.1)))
         .collect();
     lit(MapCons(TupleItems::from_iter(kv_pairs), rest))
 }
 
-pub fn var(id: &str) -> Rc<Expression> {
-    Rc::new(Var(id.into()))
+pub fn var(id: &str) -> Node<Expression> {
+    Node::new(Var(id.into()))
+}
+
+pub fn const_or_type_ref(id: &str) -> Node<Expression> {
+    Node::new(ConstOrTypeRef(id.into()))
 }
 
-pub fn const_or_type_ref(id: &str) -> Rc<Expression> {
-    Rc::new(ConstOrTypeRef(id.into()))
+pub fn db_type_ref(id: &str) -> Node<Expression> {
+    Node::new(DBTypeRef(id.into()))
 }
 
-pub fn db_type_ref(id: &str) -> Rc<Expression> {
-    Rc::new(DBTypeRef(id.into()))
+pub fn prop_fn_ref(id: &str) -> Node<Expression> {
+    Node::new(PropFnRef(id.into()))
 }
 
-pub fn prop_fn_ref(id: &str) -> Rc<Expression> {
-    Rc::new(PropFnRef(id.into()))
+pub fn fn_ref(id: &str) -> Node<Expression> {
+    Node::new(Expression::FnRef(id.into()))
 }
 
-pub fn fn_def<P: Into<Rc<Pattern>>, Args: IntoIterator<Item = P>>(
+pub fn prop_access(expr: Node<Expression>, prop: &str) -> Node<Expression> {
+    Node::new(Expression::PropAccess(expr, prop.into()))
+}
+
+pub fn fn_def<P: Into<Node<Pattern>>, Args: IntoIterator<Item = P>>(
     id: &str,
     args: Args,
-    body: Rc<Expression>,
-) -> Rc<AST> {
-    Rc::new(AST::FnDef(FnDef::new(
+    body: Node<Expression>,
+) -> Node<AST> {
+    Node::new(AST::FnDef(FnDef::new(
         id,
         fn_def_args(args),
         Rc::new(FnDefBody::rogato(body)),
     )))
 }
 
-pub fn fn_def_args<P: Into<Rc<Pattern>>, Args: IntoIterator<Item = P>>(args: Args) -> FnDefArgs {
+pub fn guarded_fn_def<P: Into<Node<Pattern>>, Args: IntoIterator<Item = P>>(
+    id: &str,
+    args: Args,
+    guard: Node<Expression>,
+    body: Node<Expression>,
+) -> Node<AST> {
+    Node::new(AST::FnDef(FnDef::new_with_variants(
+        id,
+        FnDefVariants::new([FnDefVariant(
+            fn_def_args(args),
+            Some(guard),
+            Rc::new(FnDefBody::rogato(body)),
+        )]),
+    )))
+}
+
+pub fn fn_def_args<P: Into<Node<Pattern>>, Args: IntoIterator<Item = P>>(args: Args) -> FnDefArgs {
     FnDefArgs::new(Vec::from_iter(args.into_iter().map(|a| a.into())))
 }
 
 pub fn if_else(
-    cond: Rc<Expression>,
-    then_expr: Rc<Expression>,
-    else_expr: Rc<Expression>,
-) -> Rc<Expression> {
-    Rc::new(Expression::IfElse(IfElse::new(cond, then_expr, else_expr)))
+    cond: Node<Expression>,
+    then_expr: Node<Expression>,
+    else_expr: Node<Expression>,
+) -> Node<Expression> {
+    Node::new(Expression::IfElse(IfElse::new(cond, then_expr, else_expr)))
+}
+
+pub fn case_of<Arms: IntoIterator<Item = (Node<Pattern>, Node<Expression>)>>(
+    scrutinee: Node<Expression>,
+    arms: Arms,
+) -> Node<Expression> {
+    Node::new(Expression::Case(Case::new(
+        scrutinee,
+        arms.into_iter()
+            .map(|(pattern, body)| CaseArm::new(pattern, body))
+            .collect(),
+    )))
 }
+
 pub fn let_expr<
     VarName: Into<VarIdentifier>,
-    Bindings: IntoIterator<Item = (VarName, Rc<Expression>)>,
+    Bindings: IntoIterator<Item = (VarName, Node<Expression>)>,
 >(
     bindings: Bindings,
-    body: Rc<Expression>,
-) -> Rc<Expression> {
-    let bindings: Vec<(VarIdentifier, Rc<Expression>)> = bindings
+    body: Node<Expression>,
+) -> Node<Expression> {
+    let bindings: Vec<(VarIdentifier, Node<Expression>)> = bindings
         .into_iter()
         .map(|(name, expr)| (name.into(), expr))
         .collect();
 
-    Rc::new(Let(LetExpression::new(LetBindings::new(bindings), body)))
+    Node::new(Let(LetExpression::new(LetBindings::new(bindings), body)))
 }
 
 pub fn module_def<Exports: IntoIterator<Item = &'static str>>(
     id: &str,
     exports: Exports,
-) -> Rc<AST> {
-    Rc::new(AST::ModuleDef(ModuleDef::new(
+) -> Node<AST> {
+    Node::new(AST::ModuleDef(ModuleDef::new(
         id.into(),
         module_def_exports(exports),
     )))
@@ -160,73 +220,116 @@ pub fn module_def_exports<Exports: IntoIterator<Item = &'static str>>(
     ModuleExports::new(Vec::from_iter(exports.into_iter().map(|e| e.into())))
 }
 
-pub fn call_args<Args: IntoIterator<Item = Rc<Expression>>>(args: Args) -> FnCallArgs {
+pub fn use_def(id: &str, alias: Option<&str>, imports: UseImports) -> Node<AST> {
+    Node::new(AST::Use(UseDef::new(
+        id.into(),
+        alias.map(Identifier::from),
+        imports,
+    )))
+}
+
+pub fn use_imports<Imports: IntoIterator<Item = UseImport>>(imports: Imports) -> UseImports {
+    UseImports::Specific(Vec::from_iter(imports))
+}
+
+pub fn import_fn(id: &str, alias: Option<&str>) -> UseImport {
+    UseImport::Func(id.into(), alias.map(Identifier::from))
+}
+
+pub fn import_type(id: &str, alias: Option<&str>) -> UseImport {
+    UseImport::Type(id.into(), alias.map(Identifier::from))
+}
+
+pub fn call_args<Args: IntoIterator<Item = Node<Expression>>>(args: Args) -> FnCallArgs {
     FnCallArgs::new(args)
 }
 
-pub fn fn_call<Args: IntoIterator<Item = Rc<Expression>>>(id: &str, args: Args) -> Rc<Expression> {
-    Rc::new(Expression::FnCall(FnCall::new(id.into(), call_args(args))))
+pub fn fn_call<Args: IntoIterator<Item = Node<Expression>>>(
+    id: &str,
+    args: Args,
+) -> Node<Expression> {
+    Node::new(Expression::FnCall(FnCall::new(id.into(), call_args(args))))
+}
+
+pub fn op_call(id: &str, left: Node<Expression>, right: Node<Expression>) -> Node<Expression> {
+    Node::new(Expression::OpCall(id.into(), left, right))
 }
 
-pub fn op_call(id: &str, left: Rc<Expression>, right: Rc<Expression>) -> Rc<Expression> {
-    Rc::new(Expression::OpCall(id.into(), left, right))
+pub fn root_comment(comment: &str) -> Node<AST> {
+    Node::new(AST::RootComment(comment.to_string()))
 }
 
-pub fn root_comment(comment: &str) -> Rc<AST> {
-    Rc::new(AST::RootComment(comment.to_string()))
+pub fn commented(comment: &str, exp: Node<Expression>) -> Node<Expression> {
+    Node::new(Expression::Commented(comment.to_string(), exp))
 }
 
-pub fn commented(comment: &str, exp: Rc<Expression>) -> Rc<Expression> {
-    Rc::new(Expression::Commented(comment.to_string(), exp))
+pub fn type_def(id: &str, type_expr: Node<TypeExpression>) -> Node<AST> {
+    Node::new(AST::TypeDef(TypeDef::new(id.into(), type_expr)))
 }
 
-pub fn type_def(id: &str, type_expr: Rc<TypeExpression>) -> Rc<AST> {
-    Rc::new(AST::TypeDef(TypeDef::new(id.into(), type_expr)))
+pub fn fixity_def(id: &str, fixity: Fixity) -> Node<AST> {
+    Node::new(AST::FixityDef(FixityDef::new(id.into(), fixity)))
 }
 
-pub fn db_type_def(id: &str, type_expr: Rc<TypeExpression>) -> Rc<AST> {
-    Rc::new(AST::TypeDef(TypeDef::new(id.into(), type_expr)))
+pub fn macro_def<P: Into<Node<Pattern>>, Args: IntoIterator<Item = P>>(
+    id: &str,
+    args: Args,
+    body: Node<Expression>,
+) -> Node<AST> {
+    Node::new(AST::MacroDef(MacroDef::new(id, fn_def_args(args), body)))
 }
 
-pub fn tuple_type<Items: IntoIterator<Item = Rc<TypeExpression>>>(
+pub fn db_type_def(id: &str, type_expr: Node<TypeExpression>) -> Node<AST> {
+    Node::new(AST::TypeDef(TypeDef::new(id.into(), type_expr)))
+}
+
+pub fn tuple_type<Items: IntoIterator<Item = Node<TypeExpression>>>(
     items: Items,
-) -> Rc<TypeExpression> {
-    Rc::new(TypeExpression::TupleType(TupleItems::from_iter(items)))
+) -> Node<TypeExpression> {
+    Node::new(TypeExpression::TupleType(TupleItems::from_iter(items)))
 }
 
-pub fn list_type(type_expr: Rc<TypeExpression>) -> Rc<TypeExpression> {
-    Rc::new(TypeExpression::ListType(type_expr))
+pub fn list_type(type_expr: Node<TypeExpression>) -> Node<TypeExpression> {
+    Node::new(TypeExpression::ListType(type_expr))
 }
 
-pub fn struct_type<Iter: IntoIterator<Item = (&'static str, Rc<TypeExpression>)>>(
+pub fn struct_type<Iter: IntoIterator<Item = (&'static str, Node<TypeExpression>)>>(
     props: Iter,
-) -> Rc<TypeExpression> {
-    Rc::new(TypeExpression::StructType(StructTypeProperties::new(
+) -> Node<TypeExpression> {
+    Node::new(TypeExpression::StructType(StructTypeProperties::new(
         props.into_iter().map(|(id, expr)| (id.into(), expr)),
     )))
 }
 
-pub fn int_type() -> Rc<TypeExpression> {
-    Rc::new(TypeExpression::NumberType)
+pub fn union_type<Iter: IntoIterator<Item = (&'static str, Vec<Node<TypeExpression>>)>>(
+    variants: Iter,
+) -> Node<TypeExpression> {
+    Node::new(TypeExpression::Union(UnionTypeVariants::new(
+        variants.into_iter().map(|(id, params)| (id.into(), params)),
+    )))
+}
+
+pub fn int_type() -> Node<TypeExpression> {
+    Node::new(TypeExpression::NumberType)
 }
 
-pub fn string_type() -> Rc<TypeExpression> {
-    Rc::new(TypeExpression::StringType)
+pub fn string_type() -> Node<TypeExpression> {
+    Node::new(TypeExpression::StringType)
 }
 
-pub fn type_ref(id: &str) -> Rc<TypeExpression> {
-    Rc::new(TypeExpression::TypeRef(id.into()))
+pub fn type_ref(id: &str) -> Node<TypeExpression> {
+    Node::new(TypeExpression::TypeRef(id.into()))
 }
 
 pub fn query<
     BindIds: Into<Vec<&'static str>>,
-    Binds: IntoIterator<Item = (BindIds, Rc<Expression>, bool)>,
-    Guards: IntoIterator<Item = Rc<Expression>>,
+    Binds: IntoIterator<Item = (BindIds, Node<Expression>, bool)>,
+    Guards: IntoIterator<Item = Node<Expression>>,
 >(
     bindings: Binds,
     guards: Guards,
-    production: Rc<Expression>,
-) -> Rc<Expression> {
+    production: Node<Expression>,
+) -> Node<Expression> {
     let query_bindings = bindings
         .into_iter()
         .map(|(ids, expr, is_negated)| {
@@ -238,159 +341,205 @@ pub fn query<
             }
         })
         .collect();
-    Rc::new(Expression::Query(Query::new(
+    Node::new(Expression::Query(Query::new(
         QueryBindings::new(query_bindings),
         QueryGuards::new(guards),
         production,
     )))
 }
 
-pub fn edge_prop(expr: Rc<Expression>, edge: &str) -> Rc<Expression> {
-    Rc::new(Expression::EdgeProp(expr, edge.into()))
+pub fn edge_prop(expr: Node<Expression>, edge: &str) -> Node<Expression> {
+    Node::new(Expression::EdgeProp(expr, edge.into()))
 }
 
 pub fn lambda<Args: IntoIterator<Item = &'static str>>(
     args: Args,
-    body: Rc<Expression>,
-) -> Rc<Expression> {
-    let args = args.into_iter().map(|a| Rc::new(a.into())).collect();
-    Rc::new(Expression::Lambda(Rc::new(Lambda::new(vec![Rc::new(
+    body: Node<Expression>,
+) -> Node<Expression> {
+    let args = args.into_iter().map(|a| Node::new(a.into())).collect();
+    Node::new(Expression::Lambda(Rc::new(Lambda::new(vec![Rc::new(
         LambdaVariant::new(LambdaArgs::new(args), body),
     )]))))
 }
 
-pub fn lambda_<Args: IntoIterator<Item = Rc<Pattern>>>(
+pub fn lambda_<Args: IntoIterator<Item = Node<Pattern>>>(
     args: Args,
-    body: Rc<Expression>,
+    body: Node<Expression>,
 ) -> Rc<LambdaVariant> {
     let args = args.into_iter().collect();
     Rc::new(LambdaVariant::new(LambdaArgs::new(args), body))
 }
 
 pub fn lambda_p<
-    Args: IntoIterator<Item = Rc<Pattern>>,
-    Variants: IntoIterator<Item = (Args, Rc<Expression>)>,
+    Args: IntoIterator<Item = Node<Pattern>>,
+    Variants: IntoIterator<Item = (Args, Node<Expression>)>,
 >(
     variants: Variants,
-) -> Rc<Expression> {
+) -> Node<Expression> {
     let variants = variants
         .into_iter()
         .map(|(args, body)| {
-            let args: LambdaArgs<Rc<Pattern>> = LambdaArgs::new(args.into_iter().collect());
-            Rc::new(LambdaVariant::new(args, Rc::clone(&body)))
+            let args: LambdaArgs<Node<Pattern>> = LambdaArgs::new(args.into_iter().collect());
+            Rc::new(LambdaVariant::new(args, Node::clone(&body)))
         })
         .collect();
-    Rc::new(Expression::Lambda(Rc::new(Lambda::new(variants))))
+    Node::new(Expression::Lambda(Rc::new(Lambda::new(variants))))
 }
 
-pub fn symbol(id: &str) -> Rc<Expression> {
-    Rc::new(Expression::Symbol(id.into()))
+pub fn guarded_lambda_p<
+    Args: IntoIterator<Item = Node<Pattern>>,
+    Variants: IntoIterator<Item = (Args, Option<Node<Expression>>, Node<Expression>)>,
+>(
+    variants: Variants,
+) -> Node<Expression> {
+    let variants = variants
+        .into_iter()
+        .map(|(args, guard, body)| {
+            let args: LambdaArgs<Node<Pattern>> = LambdaArgs::new(args.into_iter().collect());
+            Rc::new(LambdaVariant::guarded(args, guard, body))
+        })
+        .collect();
+    Node::new(Expression::Lambda(Rc::new(Lambda::new(variants))))
 }
 
-pub fn quoted(expr: Rc<Expression>) -> Rc<Expression> {
-    Rc::new(Expression::Quoted(expr))
+pub fn symbol(id: &str) -> Node<Expression> {
+    Node::new(Expression::Symbol(id.into()))
 }
 
-pub fn quoted_ast(ast: Rc<AST>) -> Rc<Expression> {
-    Rc::new(Expression::QuotedAST(ast))
+pub fn quoted(expr: Node<Expression>) -> Node<Expression> {
+    Node::new(Expression::Quoted(expr))
 }
 
-pub fn unquoted(expr: Rc<Expression>) -> Rc<Expression> {
-    Rc::new(Expression::Unquoted(expr))
+pub fn quoted_ast(ast: Node<AST>) -> Node<Expression> {
+    Node::new(Expression::QuotedAST(ast))
 }
 
-pub fn unquoted_ast(ast: Rc<AST>) -> Rc<Expression> {
-    Rc::new(Expression::UnquotedAST(ast))
+pub fn unquoted(expr: Node<Expression>) -> Node<Expression> {
+    Node::new(Expression::Unquoted(expr))
 }
 
-pub fn inline_fn_def<Args: IntoIterator<Item = Rc<Pattern>>>(
+pub fn unquoted_ast(ast: Node<AST>) -> Node<Expression> {
+    Node::new(Expression::UnquotedAST(ast))
+}
+
+pub fn inline_fn_def<Args: IntoIterator<Item = Node<Pattern>>>(
     id: &str,
     args: Args,
-    body: Rc<Expression>,
-) -> Rc<Expression> {
-    Rc::new(Expression::InlineFnDef(FnDef::new_inline(
+    body: Node<Expression>,
+) -> Node<Expression> {
+    Node::new(Expression::InlineFnDef(FnDef::new_inline(
         id,
         fn_def_args(args),
         Rc::new(FnDefBody::rogato(body)),
     )))
 }
 
-pub fn any_p() -> Rc<Pattern> {
-    Rc::new(Pattern::Any)
+pub fn any_p() -> Node<Pattern> {
+    Node::new(Pattern::Any)
 }
 
-pub fn list_cons_p(head_pattern: Rc<Pattern>, tail_pattern: Rc<Pattern>) -> Rc<Pattern> {
-    Rc::new(Pattern::ListCons(head_pattern, tail_pattern))
+pub fn list_cons_p(head_pattern: Node<Pattern>, tail_pattern: Node<Pattern>) -> Node<Pattern> {
+    Node::new(Pattern::ListCons(head_pattern, tail_pattern))
 }
 
-pub fn empty_list_p() -> Rc<Pattern> {
-    Rc::new(Pattern::EmptyList)
+pub fn empty_list_p() -> Node<Pattern> {
+    Node::new(Pattern::EmptyList)
 }
 
-pub fn list_lit_p<P: Into<Vec<Rc<Pattern>>>>(patterns: P) -> Rc<Pattern> {
-    Rc::new(Pattern::List(TupleItems::from(patterns.into())))
+pub fn list_lit_p<P: Into<Vec<Node<Pattern>>>>(patterns: P) -> Node<Pattern> {
+    Node::new(Pattern::List(TupleItems::from(patterns.into())))
 }
 
-pub fn tuple_lit_p<P: Into<Vec<Rc<Pattern>>>>(patterns: P) -> Rc<Pattern> {
+pub fn tuple_lit_p<P: Into<Vec<Node<Pattern>>>>(patterns: P) -> Node<Pattern> {
     let patterns = patterns.into();
-    Rc::new(Pattern::Tuple(patterns.len(), TupleItems::from(patterns)))
+    Node::new(Pattern::Tuple(patterns.len(), TupleItems::from(patterns)))
 }
 
-pub fn map_lit_p<P: Into<Vec<(Rc<Pattern>, Rc<Pattern>)>>>(kv_pairs: P) -> Rc<Pattern> {
+pub fn map_lit_p<P: Into<Vec<(Node<Pattern>, Node<Pattern>)>>>(kv_pairs: P) -> Node<Pattern> {
     let patterns = kv_pairs
         .into()
         .iter()
-        .map(|(key, val)| Rc::new(MapKVPair::new(Rc::clone(key), Rc::clone(val))))
+        .map(|(key, val)| Node::new(MapKVPair::new(Node::clone(key), Node::clone(val))))
         .collect();
-    Rc::new(Pattern::Map(TupleItems::from(patterns)))
+    Node::new(Pattern::Map(TupleItems::from(patterns)))
 }
 
-pub fn map_cons_lit_p<P: Into<Vec<(Rc<Pattern>, Rc<Pattern>)>>>(
+pub fn map_cons_lit_p<P: Into<Vec<(Node<Pattern>, Node<Pattern>)>>>(
     kv_pairs: P,
-    tail_pattern: Rc<Pattern>,
-) -> Rc<Pattern> {
+    tail_pattern: Node<Pattern>,
+) -> Node<Pattern> {
     let patterns = kv_pairs
         .into()
         .iter()
-        .map(|(key, val)| Rc::new(MapKVPair::new(Rc::clone(key), Rc::clone(val))))
+        .map(|(key, val)| Node::new(MapKVPair::new(Node::clone(key), Node::clone(val))))
         .collect();
-    Rc::new(Pattern::MapCons(TupleItems::from(patterns), tail_pattern))
+    Node::new(Pattern::MapCons(TupleItems::from(patterns), tail_pattern))
+}
+
+pub fn var_p(id: &str) -> Node<Pattern> {
+    Node::new(Pattern::Var(id.into()))
 }
 
-pub fn var_p(id: &str) -> Rc<Pattern> {
-    Rc::new(Pattern::Var(id.into()))
+pub fn number_p<N: Into<Decimal>>(n: N) -> Node<Pattern> {
+    Node::new(Pattern::Number(n.into()))
 }
 
-pub fn number_p<N: Into<Decimal>>(n: N) -> Rc<Pattern> {
-    Rc::new(Pattern::Number(n.into()))
+pub fn bool_p(b: bool) -> Node<Pattern> {
+    Node::new(Pattern::Bool(b))
+}
+
+pub fn string_p<S: ToString>(s: S) -> Node<Pattern> {
+    Node::new(Pattern::String(s.to_string()))
+}
+
+pub fn symbol_p<S: Into<Identifier>>(s: S) -> Node<Pattern> {
+    Node::new(Pattern::Symbol(s.into()))
+}
+
+pub fn constructor_p<P: Into<Vec<Node<Pattern>>>>(id: &str, params: P) -> Node<Pattern> {
+    Node::new(Pattern::Constructor(
+        id.into(),
+        TupleItems::from(params.into()),
+    ))
 }
 
-pub fn bool_p(b: bool) -> Rc<Pattern> {
-    Rc::new(Pattern::Bool(b))
+pub fn struct_p<Props: IntoIterator<Item = (&'static str, Node<Pattern>)>>(
+    id: Option<&str>,
+    props: Props,
+) -> Node<Pattern> {
+    Node::new(Pattern::Struct(
+        id.map(Identifier::from),
+        props
+            .into_iter()
+            .map(|(prop, pattern)| (prop.into(), pattern))
+            .collect(),
+    ))
 }
 
-pub fn string_p<S: ToString>(s: S) -> Rc<Pattern> {
-    Rc::new(Pattern::String(s.to_string()))
+pub fn as_p(id: &str, pattern: Node<Pattern>) -> Node<Pattern> {
+    Node::new(Pattern::As(id.into(), pattern))
 }
 
-pub fn symbol_p<S: Into<Identifier>>(s: S) -> Rc<Pattern> {
-    Rc::new(Pattern::Symbol(s.into()))
+pub fn or_p<Alternatives: IntoIterator<Item = Node<Pattern>>>(
+    alternatives: Alternatives,
+) -> Node<Pattern> {
+    Node::new(Pattern::Or(TupleItems::from_iter(alternatives)))
 }
 
-pub fn p<P: Into<Vec<Pattern>>>(vec: P) -> Vec<Rc<Pattern>> {
+pub fn p<P: Into<Vec<Pattern>>>(vec: P) -> Vec<Node<Pattern>> {
     let vec = vec.into();
     let mut patterns = Vec::with_capacity(vec.len());
     for pat in vec {
-        patterns.push(Rc::new(pat))
+        patterns.push(Node::new(pat))
     }
     patterns
 }
 
-pub fn vars(ids: &[&str]) -> Vec<Rc<Pattern>> {
+pub fn vars(ids: &[&str]) -> Vec<Node<Pattern>> {
     let mut vec = Vec::with_capacity(ids.len());
     for id in ids.iter() {
         let id: VarIdentifier = id.into();
-        vec.push(Rc::new(Pattern::Var(id)))
+        vec.push(Node::new(Pattern::Var(id)))
     }
     vec
 }
@@ -3,22 +3,21 @@ use crate::util::indent;
 use super::expression::Expression;
 use super::visitor::Visitor;
 use super::walker::Walk;
-use super::ASTDepth;
+use super::{ASTDepth, Node};
 use std::fmt::Display;
-use std::rc::Rc;
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct IfElse {
-    pub condition: Rc<Expression>,
-    pub then_expr: Rc<Expression>,
-    pub else_expr: Rc<Expression>,
+    pub condition: Node<Expression>,
+    pub then_expr: Node<Expression>,
+    pub else_expr: Node<Expression>,
 }
 
 impl IfElse {
     pub fn new(
-        condition: Rc<Expression>,
-        then_expr: Rc<Expression>,
-        else_expr: Rc<Expression>,
+        condition: Node<Expression>,
+        then_expr: Node<Expression>,
+        else_expr: Node<Expression>,
     ) -> Self {
         Self {
             condition,
@@ -1,26 +1,42 @@
 use std::{
+    any::Any,
     fmt::Display,
     hash::{Hash, Hasher},
     rc::Rc,
 };
 
-use crate::{util::indent, val::ValueRef};
+use crate::{error::BoxedError, util::indent, val::ValueRef};
 
 use super::{
-    expression::Expression, pattern::Pattern, walker::Walk, ASTDepth, Identifier, VarIdentifier,
+    expression::Expression, pattern::Pattern, walker::Walk, ASTDepth, Identifier, Node,
+    VarIdentifier,
 };
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct LambdaVariant {
-    pub args: LambdaArgs<Rc<Pattern>>,
-    pub body: Rc<Expression>,
+    pub args: LambdaArgs<Node<Pattern>>,
+    pub guard: Option<Node<Expression>>,
+    pub body: Node<Expression>,
 }
 
 impl LambdaVariant {
-    pub fn new(args: LambdaArgs<Rc<Pattern>>, body: Rc<Expression>) -> LambdaVariant {
-        LambdaVariant { args, body }
+    pub fn new(args: LambdaArgs<Node<Pattern>>, body: Node<Expression>) -> LambdaVariant {
+        LambdaVariant {
+            args,
+            guard: None,
+            body,
+        }
+    }
+
+    pub fn guarded(
+        args: LambdaArgs<Node<Pattern>>,
+        guard: Option<Node<Expression>>,
+        body: Node<Expression>,
+    ) -> LambdaVariant {
+        LambdaVariant { args, guard, body }
     }
-    pub fn get_arg(&self, i: usize) -> Option<&Rc<Pattern>> {
+
+    pub fn get_arg(&self, i: usize) -> Option<&Node<Pattern>> {
         self.args.get(i)
     }
 
@@ -33,6 +49,10 @@ impl Display for LambdaVariant {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.args.fmt(f)?;
 
+        if let Some(guard) = &self.guard {
+            f.write_fmt(format_args!(" when {guard}"))?;
+        }
+
         if self.ast_depth() > 5 {
             f.write_str(" ->\n")?;
             indent(&self.body).fmt(f)
@@ -45,7 +65,13 @@ impl Display for LambdaVariant {
 
 impl ASTDepth for LambdaVariant {
     fn ast_depth(&self) -> usize {
-        1 + self.args.len() + self.body.ast_depth()
+        1 + self.args.len()
+            + self
+                .guard
+                .as_ref()
+                .map(|guard| guard.ast_depth())
+                .unwrap_or(0)
+            + self.body.ast_depth()
     }
 }
 
@@ -55,6 +81,9 @@ impl Walk for LambdaVariant {
         for arg in self.args.iter() {
             arg.walk(v);
         }
+        if let Some(guard) = &self.guard {
+            guard.walk(v);
+        }
         self.body.walk(v);
     }
 }
@@ -134,6 +163,13 @@ impl Lambda {
         }
         max
     }
+
+    pub fn min_arg_count(&self) -> usize {
+        self.variants_iter()
+            .map(|v| v.arg_count())
+            .min()
+            .unwrap_or_default()
+    }
 }
 
 impl ASTDepth for Lambda {
@@ -151,6 +187,9 @@ impl Display for Lambda {
                 f.write_str(",\n")?;
             }
             indent(&lambda.args).fmt(f)?;
+            if let Some(guard) = &lambda.guard {
+                f.write_fmt(format_args!(" when {guard}"))?;
+            }
 
             if self.ast_depth() > 8 {
                 f.write_str(" ->\n")?;
@@ -181,8 +220,8 @@ pub enum LambdaClosureEvalError {
     #[error("Unknown error in LambdaClosure {0}: {1}")]
     Unknown(Identifier, String),
 
-    #[error("Evaluation failed for LambdaClosure with: {0}")]
-    EvaluationFailed(Rc<LambdaVariant>, String),
+    #[error("Evaluation failed for LambdaClosure")]
+    EvaluationFailed(Rc<LambdaVariant>, #[source] BoxedError),
 
     #[error("Lambda arity mismatch: Expected: {0} but got: {1}")]
     LambdaArityMismatch(usize, usize),
@@ -191,13 +230,13 @@ pub enum LambdaClosureEvalError {
     LambdaVariantArgumentsMismatch(Rc<LambdaVariant>, Vec<ValueRef>),
 
     #[error("LambdaVariant argument pattern matching failed: {0} / {1} / {2}")]
-    LambdaVariantArgumentMismatch(Rc<LambdaVariant>, Rc<Pattern>, ValueRef),
+    LambdaVariantArgumentMismatch(Rc<LambdaVariant>, Node<Pattern>, ValueRef),
 
     #[error("Lambda arguments did not match argument patterns: {0} / {1:?}")]
     LambdaArgumentsMismatch(Lambda, Vec<ValueRef>),
 
     #[error("Lambda argument pattern matching failed: {0} / {1} / {2}")]
-    LambdaArgumentMismatch(Lambda, Rc<Pattern>, ValueRef),
+    LambdaArgumentMismatch(Lambda, Node<Pattern>, ValueRef),
 }
 
 pub trait LambdaClosureContext {
@@ -206,6 +245,10 @@ pub trait LambdaClosureContext {
     fn define_var(&mut self, id: &VarIdentifier, val: ValueRef);
     fn with_child_env(&self) -> Box<dyn LambdaClosureContext>;
 
+    /// Allows recovering the concrete context, e.g. to evaluate the lambda's body
+    /// without going through `evaluate_lambda_call`.
+    fn as_any(&self) -> &dyn Any;
+
     fn evaluate_lambda_call(
         &mut self,
         lambda: &Lambda,
@@ -1,17 +1,19 @@
-use std::{fmt::Display, rc::Rc};
+use std::fmt::Display;
 
 use crate::util::indent;
 
-use super::{expression::Expression, visitor::Visitor, walker::Walk, ASTDepth, VarIdentifier};
+use super::{
+    expression::Expression, visitor::Visitor, walker::Walk, ASTDepth, Node, VarIdentifier,
+};
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct LetExpression {
     pub bindings: LetBindings,
-    pub body: Rc<Expression>,
+    pub body: Node<Expression>,
 }
 
 impl LetExpression {
-    pub fn new(bindings: LetBindings, body: Rc<Expression>) -> LetExpression {
+    pub fn new(bindings: LetBindings, body: Node<Expression>) -> LetExpression {
         LetExpression { bindings, body }
     }
 }
@@ -43,11 +45,11 @@ impl Walk for LetExpression {
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct LetBindings {
-    bindings: Vec<(VarIdentifier, Rc<Expression>)>,
+    bindings: Vec<(VarIdentifier, Node<Expression>)>,
 }
 
 impl LetBindings {
-    pub fn new(bindings: Vec<(VarIdentifier, Rc<Expression>)>) -> LetBindings {
+    pub fn new(bindings: Vec<(VarIdentifier, Node<Expression>)>) -> LetBindings {
         LetBindings { bindings }
     }
 
@@ -55,12 +57,12 @@ impl LetBindings {
         LetBindings {
             bindings: bindings
                 .iter()
-                .map(|(id, expr)| (id.clone(), Rc::new(expr.clone())))
+                .map(|(id, expr)| (id.clone(), Node::new(expr.clone())))
                 .collect(),
         }
     }
 
-    pub fn iter(&self) -> std::slice::Iter<(VarIdentifier, Rc<Expression>)> {
+    pub fn iter(&self) -> std::slice::Iter<(VarIdentifier, Node<Expression>)> {
         self.bindings.iter()
     }
 }
@@ -1,7 +1,10 @@
 use rust_decimal::Decimal;
 
-use super::{expression::Expression, ASTDepth, Identifier};
-use crate::util::indent;
+use super::{
+    expression::{Expression, FnCall, FnCallArgs},
+    ASTDepth, Identifier, Node,
+};
+use crate::util::{escape_string, indent};
 use std::{fmt::Display, rc::Rc};
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
@@ -9,12 +12,14 @@ pub enum Literal {
     Bool(bool),
     Number(Decimal),
     String(String),
+    InterpolatedString(InterpolatedString),
     Tuple(TupleItems<Expression>),
     List(TupleItems<Expression>),
-    ListCons(Rc<Expression>, Rc<Expression>),
+    ListCons(Node<Expression>, Node<Expression>),
     Struct(Identifier, Rc<StructProps>),
+    StructUpdate(Node<Expression>, Rc<StructProps>),
     Map(TupleItems<MapKVPair<Expression>>),
-    MapCons(TupleItems<MapKVPair<Expression>>, Rc<Expression>),
+    MapCons(TupleItems<MapKVPair<Expression>>, Node<Expression>),
 }
 
 impl Display for Literal {
@@ -22,7 +27,8 @@ impl Display for Literal {
         match self {
             Literal::Bool(b) => b.fmt(f),
             Literal::Number(num) => f.write_fmt(format_args!("{num}")),
-            Literal::String(string) => f.write_fmt(format_args!("\"{string}\"")),
+            Literal::String(string) => f.write_fmt(format_args!("\"{}\"", escape_string(string))),
+            Literal::InterpolatedString(string) => string.fmt(f),
             Literal::Tuple(items) => {
                 if items.ast_depth() > 6 {
                     let items_str = format!("{items}");
@@ -63,6 +69,9 @@ impl Display for Literal {
                 }
             }
             Literal::Struct(id, props) => f.write_fmt(format_args!("{id}{{ {props} }}")),
+            Literal::StructUpdate(base, props) => {
+                f.write_fmt(format_args!("{{ {base} | {props} }}"))
+            }
             Literal::Map(kv_pairs) => {
                 f.write_str("{ ")?;
                 kv_pairs.fmt(f)?;
@@ -85,6 +94,7 @@ impl ASTDepth for Literal {
             Literal::Bool(_) => 1,
             Literal::Number(_) => 1,
             Literal::String(_) => 1,
+            Literal::InterpolatedString(string) => string.ast_depth(),
             Literal::Tuple(items) => 1 + items.iter().map(|i| i.ast_depth()).sum::<usize>(),
             Literal::List(items) => 1 + items.iter().map(|i| i.ast_depth()).sum::<usize>(),
             Literal::ListCons(first, rest) => first.ast_depth() + rest.ast_depth(),
@@ -94,28 +104,109 @@ impl ASTDepth for Literal {
                     .map(|(_name, val)| val.ast_depth())
                     .sum::<usize>()
             }
+            Literal::StructUpdate(base, props) => {
+                1 + base.ast_depth()
+                    + props
+                        .iter()
+                        .map(|(_name, val)| val.ast_depth())
+                        .sum::<usize>()
+            }
             Literal::Map(kv_pairs) => 1 + kv_pairs.ast_depth(),
             Literal::MapCons(kv_pairs, rest) => 1 + kv_pairs.ast_depth() + rest.ast_depth(),
         }
     }
 }
 
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum StringPart {
+    Lit(String),
+    Expr(Node<Expression>),
+}
+
+/// A string literal with embedded `${expr}` expressions, e.g. `"Hello ${name}"`.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct InterpolatedString {
+    parts: Vec<StringPart>,
+}
+
+impl InterpolatedString {
+    pub fn new(parts: Vec<StringPart>) -> Self {
+        InterpolatedString { parts }
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, StringPart> {
+        self.parts.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.parts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// Returns the equivalent expression concatenating all parts with `++`,
+    /// converting embedded expressions with `Std.toString`.
+    pub fn desugar(&self) -> Expression {
+        let mut parts = self.parts.iter().map(|part| match part {
+            StringPart::Lit(string) => Expression::Lit(Literal::String(string.clone())),
+            StringPart::Expr(expr) => Expression::FnCall(FnCall::new(
+                "Std.toString".into(),
+                FnCallArgs::new([Node::clone(expr)]),
+            )),
+        });
+        let first = parts
+            .next()
+            .unwrap_or_else(|| Expression::Lit(Literal::String(String::new())));
+        parts.fold(first, |left, right| {
+            Expression::OpCall("++".into(), Node::new(left), Node::new(right))
+        })
+    }
+}
+
+impl Display for InterpolatedString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("\"")?;
+        for part in self.parts.iter() {
+            match part {
+                StringPart::Lit(string) => f.write_str(&escape_string(string))?,
+                StringPart::Expr(expr) => f.write_fmt(format_args!("${{{expr}}}"))?,
+            }
+        }
+        f.write_str("\"")
+    }
+}
+
+impl ASTDepth for InterpolatedString {
+    fn ast_depth(&self) -> usize {
+        1 + self
+            .parts
+            .iter()
+            .map(|part| match part {
+                StringPart::Lit(_) => 1,
+                StringPart::Expr(expr) => expr.ast_depth(),
+            })
+            .sum::<usize>()
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct TupleItems<I> {
-    items: Vec<Rc<I>>,
+    items: Vec<Node<I>>,
 }
 
 impl<I: Display> TupleItems<I> {
     pub fn new(first: I, rest: Vec<I>) -> Self {
         let mut items = Vec::with_capacity(rest.len() + 1);
-        items.push(Rc::new(first));
+        items.push(Node::new(first));
         for item in rest {
-            items.push(Rc::new(item))
+            items.push(Node::new(item))
         }
         Self::from(items)
     }
 
-    pub fn from(items: Vec<Rc<I>>) -> Self {
+    pub fn from(items: Vec<Node<I>>) -> Self {
         TupleItems { items }
     }
 
@@ -127,13 +218,13 @@ impl<I: Display> TupleItems<I> {
         self.len() == 0
     }
 
-    pub fn iter(&self) -> std::slice::Iter<Rc<I>> {
+    pub fn iter(&self) -> std::slice::Iter<Node<I>> {
         self.items.iter()
     }
 }
 
-impl<I: Display> FromIterator<Rc<I>> for TupleItems<I> {
-    fn from_iter<T: IntoIterator<Item = Rc<I>>>(iter: T) -> Self {
+impl<I: Display> FromIterator<Node<I>> for TupleItems<I> {
+    fn from_iter<T: IntoIterator<Item = Node<I>>>(iter: T) -> Self {
         TupleItems::from(iter.into_iter().collect())
     }
 }
@@ -167,13 +258,13 @@ impl<I: ASTDepth> ASTDepth for TupleItems<I> {
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct StructProps {
-    props: Vec<(Identifier, Rc<Expression>)>,
+    props: Vec<(Identifier, Node<Expression>)>,
 }
 
 impl StructProps {
     pub fn new(
-        first: (Identifier, Rc<Expression>),
-        rest: Vec<(Identifier, Rc<Expression>)>,
+        first: (Identifier, Node<Expression>),
+        rest: Vec<(Identifier, Node<Expression>)>,
     ) -> Self {
         let mut boxed_props = Vec::with_capacity(1 + rest.len());
         let (f_id, f_expr) = first;
@@ -184,7 +275,7 @@ impl StructProps {
         Self::from(boxed_props)
     }
 
-    pub fn from(props: Vec<(Identifier, Rc<Expression>)>) -> Self {
+    pub fn from(props: Vec<(Identifier, Node<Expression>)>) -> Self {
         StructProps { props }
     }
 
@@ -196,7 +287,7 @@ impl StructProps {
         self.len() == 0
     }
 
-    pub fn iter(&self) -> std::slice::Iter<(Identifier, Rc<Expression>)> {
+    pub fn iter(&self) -> std::slice::Iter<(Identifier, Node<Expression>)> {
         self.props.iter()
     }
 }
@@ -217,17 +308,17 @@ impl Display for StructProps {
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct MapKVPair<T: Display + ASTDepth> {
-    pub key: Rc<T>,
-    pub value: Rc<T>,
+    pub key: Node<T>,
+    pub value: Node<T>,
 }
 
 impl<T: Display + ASTDepth> MapKVPair<T> {
-    pub fn new(key: Rc<T>, value: Rc<T>) -> Self {
+    pub fn new(key: Node<T>, value: Node<T>) -> Self {
         Self { key, value }
     }
 
-    pub fn pair(&self) -> (Rc<T>, Rc<T>) {
-        (Rc::clone(&self.key), Rc::clone(&self.value))
+    pub fn pair(&self) -> (Node<T>, Node<T>) {
+        (Node::clone(&self.key), Node::clone(&self.value))
     }
 }
 
@@ -245,8 +336,8 @@ impl<T: Display + ASTDepth> ASTDepth for MapKVPair<T> {
     }
 }
 
-impl<T: Display + ASTDepth> From<(Rc<T>, Rc<T>)> for MapKVPair<T> {
-    fn from(pair: (Rc<T>, Rc<T>)) -> Self {
+impl<T: Display + ASTDepth> From<(Node<T>, Node<T>)> for MapKVPair<T> {
+    fn from(pair: (Node<T>, Node<T>)) -> Self {
         MapKVPair {
             key: pair.0,
             value: pair.1,
@@ -257,8 +348,8 @@ impl<T: Display + ASTDepth> From<(Rc<T>, Rc<T>)> for MapKVPair<T> {
 impl<T: Display + ASTDepth> From<(T, T)> for MapKVPair<T> {
     fn from(pair: (T, T)) -> Self {
         MapKVPair {
-            key: Rc::new(pair.0),
-            value: Rc::new(pair.1),
+            key: Node::new(pair.0),
+            value: Node::new(pair.1),
         }
     }
 }
@@ -0,0 +1,48 @@
+use super::{expression::Expression, fn_def::FnDefArgs, ASTDepth, Identifier, Node};
+use crate::util::indent;
+use std::fmt::Display;
+
+/// A macro definition, e.g. `macro unless cond body = ^(if ~cond then ^none else ~body)`.
+/// Macros are called with their arguments as quoted code and return the quoted code
+/// that replaces the call before the program gets evaluated.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct MacroDef {
+    id: Identifier,
+    args: FnDefArgs,
+    body: Node<Expression>,
+}
+
+impl MacroDef {
+    pub fn new<ID: Into<Identifier>>(id: ID, args: FnDefArgs, body: Node<Expression>) -> MacroDef {
+        MacroDef {
+            id: id.into(),
+            args,
+            body,
+        }
+    }
+
+    pub fn id(&self) -> &Identifier {
+        &self.id
+    }
+
+    pub fn args(&self) -> &FnDefArgs {
+        &self.args
+    }
+
+    pub fn body(&self) -> &Node<Expression> {
+        &self.body
+    }
+}
+
+impl Display for MacroDef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("macro {} {} =\n", self.id, self.args))?;
+        indent(&self.body).fmt(f)
+    }
+}
+
+impl ASTDepth for MacroDef {
+    fn ast_depth(&self) -> usize {
+        1 + self.args.ast_depth() + self.body.ast_depth()
+    }
+}
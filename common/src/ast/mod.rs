@@ -3,10 +3,13 @@ use std::rc::Rc;
 use std::{cell::RefCell, fmt::Display};
 
 use self::{
-    expression::Expression, fn_def::FnDef, module_def::ModuleDef, type_expression::TypeDef,
+    expression::Expression, fixity::FixityDef, fn_def::FnDef, macro_def::MacroDef,
+    module_def::ModuleDef, type_expression::TypeDef, use_def::UseDef,
 };
 
+pub mod case;
 pub mod expression;
+pub mod fixity;
 pub mod fn_call;
 pub mod fn_def;
 pub mod helpers;
@@ -15,16 +18,23 @@ pub mod if_else;
 pub mod lambda;
 pub mod let_expression;
 pub mod literal;
+pub mod macro_def;
 pub mod module_def;
+pub mod node;
 pub mod pattern;
 pub mod program;
 pub mod query;
+pub mod rewriter;
+pub mod span;
 pub mod type_expression;
+pub mod use_def;
 pub mod visitor;
 pub mod walker;
 
 pub use identifier::{FnIdentifier, Identifier, ModIdentifier, OpIdentifier, VarIdentifier};
+pub use node::Node;
 pub use program::Program;
+pub use span::Span;
 
 pub type ASTId = usize;
 
@@ -43,12 +53,12 @@ impl NodeFactory {
         self.current_id
     }
 
-    pub fn ast_node(&mut self, ast: Rc<AST>) -> ASTNode {
+    pub fn ast_node(&mut self, ast: Node<AST>) -> ASTNode {
         let id = self.next_id();
         ASTNode { id, ast }
     }
 
-    pub fn expr_node(&mut self, expr: Rc<Expression>) -> ExprNode {
+    pub fn expr_node(&mut self, expr: Node<Expression>) -> ExprNode {
         let id = self.next_id();
         ExprNode { id, expr }
     }
@@ -63,13 +73,13 @@ impl Default for NodeFactory {
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct ASTNode {
     id: ASTId,
-    ast: Rc<AST>,
+    ast: Node<AST>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct ExprNode {
     id: ASTId,
-    expr: Rc<Expression>,
+    expr: Node<Expression>,
 }
 
 #[derive(Clone, Eq, Debug)]
@@ -77,8 +87,10 @@ pub enum AST {
     RootComment(String),
     FnDef(Rc<RefCell<FnDef>>),
     ModuleDef(ModuleDef),
-    Use(Identifier, Vec<Identifier>),
+    Use(UseDef),
     TypeDef(TypeDef),
+    FixityDef(FixityDef),
+    MacroDef(MacroDef),
 }
 
 pub trait ASTDepth {
@@ -100,20 +112,10 @@ impl Display for AST {
             }
             AST::FnDef(fn_def) => fn_def.borrow().fmt(f),
             AST::ModuleDef(mod_def) => mod_def.fmt(f),
-            AST::Use(id, imports) => {
-                f.write_str("use ")?;
-                id.fmt(f)?;
-                let mut is_first = true;
-                for imp in imports.iter() {
-                    if !is_first {
-                        f.write_str(", ")?;
-                    }
-                    imp.fmt(f)?;
-                    is_first = false;
-                }
-                Ok(())
-            }
+            AST::Use(use_def) => use_def.fmt(f),
             AST::TypeDef(type_def) => type_def.fmt(f),
+            AST::FixityDef(fixity_def) => fixity_def.fmt(f),
+            AST::MacroDef(macro_def) => macro_def.fmt(f),
         }
     }
 }
@@ -124,8 +126,10 @@ impl ASTDepth for AST {
             AST::RootComment(_) => 1,
             AST::FnDef(fn_def) => fn_def.borrow().ast_depth(),
             AST::ModuleDef(mod_def) => mod_def.ast_depth(),
-            AST::Use(_, imports) => 1 + imports.len(),
+            AST::Use(use_def) => use_def.ast_depth(),
             AST::TypeDef(type_def) => type_def.ast_depth(),
+            AST::FixityDef(fixity_def) => fixity_def.ast_depth(),
+            AST::MacroDef(macro_def) => macro_def.ast_depth(),
         }
     }
 }
@@ -142,11 +146,10 @@ impl Hash for AST {
             AST::RootComment(c) => c.hash(state),
             AST::FnDef(fn_def) => fn_def.borrow().hash(state),
             AST::ModuleDef(mod_def) => mod_def.hash(state),
-            AST::Use(id, imports) => {
-                id.hash(state);
-                imports.hash(state)
-            }
+            AST::Use(use_def) => use_def.hash(state),
             AST::TypeDef(type_def) => type_def.hash(state),
+            AST::FixityDef(fixity_def) => fixity_def.hash(state),
+            AST::MacroDef(macro_def) => macro_def.hash(state),
         }
     }
 }
@@ -157,10 +160,12 @@ impl PartialEq for AST {
             (AST::RootComment(c1), AST::RootComment(c2)) => c1.eq(c2),
             (AST::FnDef(fn_def1), AST::FnDef(fn_def2)) => fn_def1.eq(fn_def2),
             (AST::ModuleDef(mod_def1), AST::ModuleDef(mod_def2)) => mod_def1.eq(mod_def2),
-            (AST::Use(id1, imports1), AST::Use(id2, imports2)) => {
-                id1.eq(id2) && imports1.eq(imports2)
-            }
+            (AST::Use(use_def1), AST::Use(use_def2)) => use_def1.eq(use_def2),
             (AST::TypeDef(type_def1), AST::TypeDef(type_def2)) => type_def1.eq(type_def2),
+            (AST::FixityDef(fixity_def1), AST::FixityDef(fixity_def2)) => {
+                fixity_def1.eq(fixity_def2)
+            }
+            (AST::MacroDef(macro_def1), AST::MacroDef(macro_def2)) => macro_def1.eq(macro_def2),
             (_, _) => false,
         }
     }
@@ -0,0 +1,109 @@
+use super::{ASTDepth, Span};
+use std::{
+    fmt::{Debug, Display},
+    hash::Hash,
+    ops::Deref,
+    rc::Rc,
+};
+
+/// A shared AST node along with the location it was parsed from, if any.
+/// Derefs to the node itself and compares and hashes like it, regardless of its span.
+pub struct Node<T> {
+    node: Rc<T>,
+    span: Option<Span>,
+}
+
+impl<T> Node<T> {
+    pub fn new(node: T) -> Node<T> {
+        Node {
+            node: Rc::new(node),
+            span: None,
+        }
+    }
+
+    /// The location of this node within its source code, if it was parsed from one.
+    pub fn span(&self) -> Option<&Span> {
+        self.span.as_ref()
+    }
+
+    /// Attaches the given span, unless the node already points to a more specific location,
+    /// e.g. an expression within parentheses keeps the span without them.
+    pub fn with_span(mut self, span: Option<&Span>) -> Node<T> {
+        if self.span.is_none() {
+            self.span = span.cloned();
+        }
+        self
+    }
+
+    pub fn ptr_eq(a: &Node<T>, b: &Node<T>) -> bool {
+        Rc::ptr_eq(&a.node, &b.node)
+    }
+}
+
+impl<T: Clone> Node<T> {
+    /// Returns the inner node, cloning it if it's shared with other nodes.
+    pub fn unwrap_or_clone(node: Node<T>) -> T {
+        Rc::unwrap_or_clone(node.node)
+    }
+}
+
+impl<T> Clone for Node<T> {
+    fn clone(&self) -> Self {
+        Node {
+            node: Rc::clone(&self.node),
+            span: self.span.clone(),
+        }
+    }
+}
+
+impl<T> Deref for Node<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.node
+    }
+}
+
+impl<T> From<T> for Node<T> {
+    fn from(node: T) -> Self {
+        Node::new(node)
+    }
+}
+
+impl<T> From<Rc<T>> for Node<T> {
+    fn from(node: Rc<T>) -> Self {
+        Node { node, span: None }
+    }
+}
+
+impl<T: PartialEq> PartialEq for Node<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.node.eq(&other.node)
+    }
+}
+
+impl<T: Eq> Eq for Node<T> {}
+
+impl<T: Hash> Hash for Node<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.node.hash(state)
+    }
+}
+
+impl<T: Debug> Debug for Node<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.node.fmt(f)
+    }
+}
+
+impl<T: Display> Display for Node<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.node.fmt(f)
+    }
+}
+
+impl<T: ASTDepth> ASTDepth for Node<T> {
+    fn ast_depth(&self) -> usize {
+        self.node.ast_depth()
+    }
+}
@@ -4,24 +4,97 @@ use super::{
     expression::{MapKVPair, TupleItems},
     visitor::Visitor,
     walker::Walk,
-    ASTDepth, Identifier, VarIdentifier,
+    ASTDepth, Identifier, Node, VarIdentifier,
 };
-use std::{fmt::Display, rc::Rc};
+use crate::util::escape_string;
+use std::{collections::HashSet, fmt::Display};
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub enum Pattern {
     Any,
     EmptyList,
-    ListCons(Rc<Pattern>, Rc<Pattern>),
+    ListCons(Node<Pattern>, Node<Pattern>),
     List(TupleItems<Pattern>),
     Tuple(usize, TupleItems<Pattern>),
+    /// Matches maps with exactly the given key-value pairs, e.g. `{k: v}`.
+    /// On objects and structs, variable or string keys name properties instead,
+    /// so `p@{name: n}` matches like `p@_{name: n}`.
     Map(TupleItems<MapKVPair<Pattern>>),
-    MapCons(TupleItems<MapKVPair<Pattern>>, Rc<Pattern>),
+    MapCons(TupleItems<MapKVPair<Pattern>>, Node<Pattern>),
     Var(VarIdentifier),
     Bool(bool),
     Number(Decimal),
     String(String),
     Symbol(Identifier),
+    Constructor(Identifier, TupleItems<Pattern>),
+    /// Matches structs with at least the given properties, e.g. `Person{name: n}`.
+    /// Without a struct id, e.g. `_{name: n}`, objects and structs of any type match as well.
+    Struct(Option<Identifier>, Vec<(Identifier, Node<Pattern>)>),
+    /// Binds the whole value while matching it against the inner pattern, e.g. `p@Person{name: n}`.
+    As(VarIdentifier, Node<Pattern>),
+    /// Matches if any of the alternatives match, e.g. `(1 | 2)`.
+    /// All alternatives need to bind the same variables.
+    Or(TupleItems<Pattern>),
+}
+
+impl Pattern {
+    /// The variables bound by this pattern when it matches.
+    pub fn var_ids(&self) -> HashSet<VarIdentifier> {
+        let mut ids = HashSet::new();
+        self.collect_var_ids(&mut ids);
+        ids
+    }
+
+    fn collect_var_ids(&self, ids: &mut HashSet<VarIdentifier>) {
+        match self {
+            Pattern::Var(id) => {
+                ids.insert(id.clone());
+            }
+            Pattern::As(id, pattern) => {
+                ids.insert(id.clone());
+                pattern.collect_var_ids(ids)
+            }
+            Pattern::ListCons(head, tail) => {
+                head.collect_var_ids(ids);
+                tail.collect_var_ids(ids)
+            }
+            Pattern::List(items) | Pattern::Tuple(_, items) | Pattern::Constructor(_, items) => {
+                for item in items.iter() {
+                    item.collect_var_ids(ids)
+                }
+            }
+            Pattern::Map(kv_pairs) => {
+                for kv_pair in kv_pairs.iter() {
+                    kv_pair.key.collect_var_ids(ids);
+                    kv_pair.value.collect_var_ids(ids)
+                }
+            }
+            Pattern::MapCons(kv_pairs, rest) => {
+                for kv_pair in kv_pairs.iter() {
+                    kv_pair.key.collect_var_ids(ids);
+                    kv_pair.value.collect_var_ids(ids)
+                }
+                rest.collect_var_ids(ids)
+            }
+            Pattern::Struct(_, props) => {
+                for (_, pattern) in props.iter() {
+                    pattern.collect_var_ids(ids)
+                }
+            }
+            // alternatives all bind the same variables, see `Pattern::Or`
+            Pattern::Or(alternatives) => {
+                if let Some(first) = alternatives.iter().next() {
+                    first.collect_var_ids(ids)
+                }
+            }
+            Pattern::Any
+            | Pattern::EmptyList
+            | Pattern::Bool(_)
+            | Pattern::Number(_)
+            | Pattern::String(_)
+            | Pattern::Symbol(_) => {}
+        }
+    }
 }
 
 impl ASTDepth for Pattern {
@@ -39,6 +112,10 @@ impl ASTDepth for Pattern {
             Self::Number(_) => 1,
             Self::String(_) => 1,
             Self::Symbol(_) => 1,
+            Self::Constructor(_, params) => 1 + params.ast_depth(),
+            Self::Struct(_, props) => 1 + props.iter().map(|(_, p)| p.ast_depth()).sum::<usize>(),
+            Self::As(_, pattern) => 1 + pattern.ast_depth(),
+            Self::Or(alternatives) => 1 + alternatives.ast_depth(),
         }
     }
 }
@@ -80,11 +157,52 @@ impl Display for Pattern {
             Self::Var(id) => id.fmt(f),
             Self::Bool(b) => b.fmt(f),
             Self::Number(d) => d.fmt(f),
-            Self::String(s) => s.fmt(f),
+            Self::String(s) => f.write_fmt(format_args!("\"{}\"", escape_string(s))),
             Self::Symbol(s) => {
                 f.write_str("^")?;
                 s.fmt(f)
             }
+            Self::Constructor(id, params) => {
+                if params.is_empty() {
+                    return id.fmt(f);
+                }
+                f.write_str("(")?;
+                id.fmt(f)?;
+                for param in params.iter() {
+                    f.write_str(" ")?;
+                    param.fmt(f)?;
+                }
+                f.write_str(")")
+            }
+            Self::Struct(id, props) => {
+                match id {
+                    Some(id) => id.fmt(f)?,
+                    None => f.write_str("_")?,
+                }
+                f.write_str("{")?;
+                let mut is_first = true;
+                for (prop, pattern) in props.iter() {
+                    if !is_first {
+                        f.write_str(", ")?;
+                    }
+                    f.write_fmt(format_args!("{prop}: {pattern}"))?;
+                    is_first = false;
+                }
+                f.write_str("}")
+            }
+            Self::As(id, pattern) => f.write_fmt(format_args!("{id}@{pattern}")),
+            Self::Or(alternatives) => {
+                f.write_str("(")?;
+                let mut is_first = true;
+                for alternative in alternatives.iter() {
+                    if !is_first {
+                        f.write_str(" | ")?;
+                    }
+                    alternative.fmt(f)?;
+                    is_first = false;
+                }
+                f.write_str(")")
+            }
         }
     }
 }
@@ -97,6 +215,7 @@ impl<S: Into<VarIdentifier>> From<S> for Pattern {
 
 impl Walk for Pattern {
     fn walk<V: Visitor<()>>(&self, v: &mut V) {
+        v.pattern(self);
         match self {
             Pattern::List(patterns) => {
                 for p in patterns.iter() {
@@ -125,6 +244,22 @@ impl Walk for Pattern {
                 }
                 rest.walk(v);
             }
+            Pattern::Constructor(_id, params) => {
+                for param in params.iter() {
+                    param.walk(v);
+                }
+            }
+            Pattern::Struct(_id, props) => {
+                for (_prop, pattern) in props.iter() {
+                    pattern.walk(v);
+                }
+            }
+            Pattern::As(_id, pattern) => pattern.walk(v),
+            Pattern::Or(alternatives) => {
+                for alternative in alternatives.iter() {
+                    alternative.walk(v);
+                }
+            }
             _ => {}
         }
     }
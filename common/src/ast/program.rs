@@ -1,14 +1,13 @@
-use super::AST;
+use super::{Node, AST};
 use std::fmt::Display;
-use std::rc::Rc;
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Program {
-    nodes: Vec<Rc<AST>>,
+    nodes: Vec<Node<AST>>,
 }
 
 impl Program {
-    pub fn new(nodes: Vec<Rc<AST>>) -> Self {
+    pub fn new(nodes: Vec<Node<AST>>) -> Self {
         Program { nodes }
     }
 
@@ -20,7 +19,7 @@ impl Program {
         self.len() == 0
     }
 
-    pub fn iter(&self) -> std::slice::Iter<Rc<AST>> {
+    pub fn iter(&self) -> std::slice::Iter<Node<AST>> {
         self.nodes.iter()
     }
 }
@@ -39,8 +38,8 @@ impl Display for Program {
     }
 }
 
-impl FromIterator<Rc<AST>> for Program {
-    fn from_iter<T: IntoIterator<Item = Rc<AST>>>(iter: T) -> Self {
+impl FromIterator<Node<AST>> for Program {
+    fn from_iter<T: IntoIterator<Item = Node<AST>>>(iter: T) -> Self {
         let mut nodes = vec![];
         for val in iter.into_iter() {
             nodes.push(val)
@@ -1,15 +1,15 @@
-use super::{expression::Expression, walker::Walk, ASTDepth, VarIdentifier};
-use std::{fmt::Display, rc::Rc};
+use super::{expression::Expression, walker::Walk, ASTDepth, Node, VarIdentifier};
+use std::fmt::Display;
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct Query {
     bindings: QueryBindings,
     guards: QueryGuards,
-    production: Rc<Expression>,
+    production: Node<Expression>,
 }
 
 impl Query {
-    pub fn new(bindings: QueryBindings, guards: QueryGuards, production: Rc<Expression>) -> Self {
+    pub fn new(bindings: QueryBindings, guards: QueryGuards, production: Node<Expression>) -> Self {
         Self {
             bindings,
             guards,
@@ -25,7 +25,7 @@ impl Query {
         &self.guards
     }
 
-    pub fn production(&self) -> &Expression {
+    pub fn production(&self) -> &Node<Expression> {
         &self.production
     }
 }
@@ -67,17 +67,17 @@ impl Walk for Query {
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct QueryGuards {
-    guards: Vec<Rc<Expression>>,
+    guards: Vec<Node<Expression>>,
 }
 
 impl QueryGuards {
-    pub fn new<Guards: IntoIterator<Item = Rc<Expression>>>(guards: Guards) -> Self {
+    pub fn new<Guards: IntoIterator<Item = Node<Expression>>>(guards: Guards) -> Self {
         QueryGuards {
             guards: guards.into_iter().collect(),
         }
     }
 
-    pub fn prepend_guard(&mut self, arg: Rc<Expression>) {
+    pub fn prepend_guard(&mut self, arg: Node<Expression>) {
         self.guards.insert(0, arg);
     }
 
@@ -85,7 +85,7 @@ impl QueryGuards {
         self.guards.len()
     }
 
-    pub fn iter(&self) -> std::slice::Iter<Rc<Expression>> {
+    pub fn iter(&self) -> std::slice::Iter<Node<Expression>> {
         self.guards.iter()
     }
 
@@ -112,12 +112,12 @@ impl Display for QueryGuards {
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct QueryBinding {
     ids: Vec<VarIdentifier>,
-    val: Rc<Expression>,
+    val: Node<Expression>,
     is_negated: bool,
 }
 
 impl QueryBinding {
-    pub fn new(ids: Vec<VarIdentifier>, val: Rc<Expression>) -> Self {
+    pub fn new(ids: Vec<VarIdentifier>, val: Node<Expression>) -> Self {
         QueryBinding {
             ids,
             val,
@@ -125,7 +125,7 @@ impl QueryBinding {
         }
     }
 
-    pub fn new_negated(ids: Vec<VarIdentifier>, val: Rc<Expression>) -> Self {
+    pub fn new_negated(ids: Vec<VarIdentifier>, val: Node<Expression>) -> Self {
         QueryBinding {
             ids,
             val,
@@ -141,8 +141,8 @@ impl QueryBinding {
         &self.ids
     }
 
-    pub fn val(&self) -> Rc<Expression> {
-        Rc::clone(&self.val)
+    pub fn val(&self) -> Node<Expression> {
+        Node::clone(&self.val)
     }
 }
 
@@ -0,0 +1,292 @@
+use std::{cell::RefCell, rc::Rc};
+
+use super::{
+    expression::{
+        Case, CaseArm, Expression, FnCall, FnCallArgs, IfElse, InterpolatedString, Lambda,
+        LambdaArgs, LambdaVariant, LetBindings, LetExpression, Literal, MapKVPair, Query,
+        QueryBinding, QueryBindings, QueryGuards, StringPart, StructProps, TupleItems,
+    },
+    fn_def::{FnDef, FnDefArgs, FnDefBody, FnDefVariant, FnDefVariants},
+    macro_def::MacroDef,
+    pattern::Pattern,
+    Node, Span, VarIdentifier, AST,
+};
+
+/// Rebuilds expressions and definitions, giving implementors the chance to replace any node
+/// along the way. Nodes that aren't replaced get rebuilt from their rewritten sub-expressions.
+/// Where the [`Visitor`](super::visitor::Visitor) only inspects code, this is used to transform it,
+/// e.g. for expanding quasi-quotes and macros.
+pub trait Rewriter {
+    type Error;
+
+    /// Rewrites a single expression.
+    /// Returning `None` keeps the expression, rewriting its sub-expressions instead.
+    fn rewrite_node(
+        &mut self,
+        _expr: &Expression,
+    ) -> Result<Option<Node<Expression>>, Self::Error> {
+        Ok(None)
+    }
+
+    /// Rewrites a variable bound by a `let` expression or a query.
+    fn rewrite_binding(&mut self, id: &VarIdentifier) -> Result<VarIdentifier, Self::Error> {
+        Ok(id.clone())
+    }
+
+    /// Rewrites an argument or case arm pattern.
+    fn rewrite_pattern(&mut self, pattern: &Node<Pattern>) -> Result<Node<Pattern>, Self::Error> {
+        Ok(Node::clone(pattern))
+    }
+
+    /// Called for errors raised within spanned code, e.g. to attach the span to them.
+    fn spanned_error(&self, error: Self::Error, _span: &Span) -> Self::Error {
+        error
+    }
+
+    /// Rewrites a nested definition, keeping its span for the rewritten definition.
+    fn rewrite_sub_ast(&mut self, ast: &Node<AST>) -> Result<Node<AST>, Self::Error> {
+        match self.rewrite_ast(ast) {
+            Ok(rewritten) => Ok(Node::new(rewritten).with_span(ast.span())),
+            Err(error) => Err(match ast.span() {
+                Some(span) => self.spanned_error(error, span),
+                None => error,
+            }),
+        }
+    }
+
+    /// Rewrites an expression, keeping its span for the rewritten expression.
+    fn rewrite_expr(&mut self, expr: &Node<Expression>) -> Result<Node<Expression>, Self::Error> {
+        let rewritten = match self.rewrite_node(expr) {
+            Ok(Some(rewritten)) => Ok(rewritten),
+            Ok(None) => self.rewrite_sub_exprs(expr).map(Node::new),
+            Err(error) => Err(error),
+        };
+        match rewritten {
+            Ok(rewritten) => Ok(rewritten.with_span(expr.span())),
+            Err(error) => Err(match expr.span() {
+                Some(span) => self.spanned_error(error, span),
+                None => error,
+            }),
+        }
+    }
+
+    /// Rebuilds the given expression from its rewritten sub-expressions.
+    fn rewrite_sub_exprs(&mut self, expr: &Expression) -> Result<Expression, Self::Error> {
+        let rewritten = match expr {
+            Expression::Commented(c, e) => Expression::Commented(c.clone(), self.rewrite_expr(e)?),
+            Expression::Lit(lit) => Expression::Lit(self.rewrite_lit(lit)?),
+            Expression::FnCall(fn_call) => Expression::FnCall(FnCall::new(
+                fn_call.id.clone(),
+                FnCallArgs::new(rewrite_all(self, fn_call.args.iter())?),
+            )),
+            Expression::OpCall(id, left, right) => Expression::OpCall(
+                id.clone(),
+                self.rewrite_expr(left)?,
+                self.rewrite_expr(right)?,
+            ),
+            Expression::Var(_)
+            | Expression::ConstOrTypeRef(_)
+            | Expression::DBTypeRef(_)
+            | Expression::PropFnRef(_)
+            | Expression::FnRef(_)
+            | Expression::Symbol(_) => expr.clone(),
+            Expression::PropAccess(e, prop) => {
+                Expression::PropAccess(self.rewrite_expr(e)?, prop.clone())
+            }
+            Expression::EdgeProp(e, edge) => {
+                Expression::EdgeProp(self.rewrite_expr(e)?, edge.clone())
+            }
+            Expression::IfElse(if_else) => Expression::IfElse(IfElse::new(
+                self.rewrite_expr(&if_else.condition)?,
+                self.rewrite_expr(&if_else.then_expr)?,
+                self.rewrite_expr(&if_else.else_expr)?,
+            )),
+            Expression::Case(case) => {
+                let mut arms = Vec::with_capacity(case.arms.len());
+                for arm in case.arms_iter() {
+                    arms.push(CaseArm::new(
+                        self.rewrite_pattern(&arm.pattern)?,
+                        self.rewrite_expr(&arm.body)?,
+                    ))
+                }
+                Expression::Case(Case::new(self.rewrite_expr(&case.scrutinee)?, arms))
+            }
+            Expression::Let(let_expr) => {
+                let mut bindings = Vec::new();
+                for (id, val) in let_expr.bindings.iter() {
+                    bindings.push((self.rewrite_binding(id)?, self.rewrite_expr(val)?))
+                }
+                Expression::Let(LetExpression::new(
+                    LetBindings::new(bindings),
+                    self.rewrite_expr(&let_expr.body)?,
+                ))
+            }
+            Expression::Lambda(lambda) => Expression::Lambda(Rc::new(self.rewrite_lambda(lambda)?)),
+            Expression::Query(query) => Expression::Query(self.rewrite_query(query)?),
+            Expression::Quoted(e) => Expression::Quoted(self.rewrite_expr(e)?),
+            Expression::QuotedAST(ast) => Expression::QuotedAST(self.rewrite_sub_ast(ast)?),
+            Expression::Unquoted(e) => Expression::Unquoted(self.rewrite_expr(e)?),
+            Expression::UnquotedAST(ast) => Expression::UnquotedAST(self.rewrite_sub_ast(ast)?),
+            Expression::InlineFnDef(fn_def) => {
+                Expression::InlineFnDef(self.rewrite_fn_def(&fn_def.borrow())?)
+            }
+        };
+        Ok(rewritten)
+    }
+
+    fn rewrite_ast(&mut self, ast: &AST) -> Result<AST, Self::Error> {
+        match ast {
+            AST::FnDef(fn_def) => Ok(AST::FnDef(self.rewrite_fn_def(&fn_def.borrow())?)),
+            AST::MacroDef(macro_def) => Ok(AST::MacroDef(MacroDef::new(
+                macro_def.id().clone(),
+                FnDefArgs::new(rewrite_patterns(self, macro_def.args().iter())?),
+                self.rewrite_expr(macro_def.body())?,
+            ))),
+            AST::RootComment(_)
+            | AST::ModuleDef(_)
+            | AST::Use(_)
+            | AST::TypeDef(_)
+            | AST::FixityDef(_) => Ok(ast.clone()),
+        }
+    }
+
+    fn rewrite_lit(&mut self, lit: &Literal) -> Result<Literal, Self::Error> {
+        let rewritten = match lit {
+            Literal::Bool(_) | Literal::Number(_) | Literal::String(_) => lit.clone(),
+            Literal::InterpolatedString(string) => {
+                let mut parts = Vec::with_capacity(string.len());
+                for part in string.iter() {
+                    parts.push(match part {
+                        StringPart::Lit(s) => StringPart::Lit(s.clone()),
+                        StringPart::Expr(e) => StringPart::Expr(self.rewrite_expr(e)?),
+                    })
+                }
+                Literal::InterpolatedString(InterpolatedString::new(parts))
+            }
+            Literal::Tuple(items) => {
+                Literal::Tuple(TupleItems::from(rewrite_all(self, items.iter())?))
+            }
+            Literal::List(items) => {
+                Literal::List(TupleItems::from(rewrite_all(self, items.iter())?))
+            }
+            Literal::ListCons(first, rest) => {
+                Literal::ListCons(self.rewrite_expr(first)?, self.rewrite_expr(rest)?)
+            }
+            Literal::Struct(id, props) => {
+                Literal::Struct(id.clone(), Rc::new(rewrite_props(self, props)?))
+            }
+            Literal::StructUpdate(base, props) => Literal::StructUpdate(
+                self.rewrite_expr(base)?,
+                Rc::new(rewrite_props(self, props)?),
+            ),
+            Literal::Map(kv_pairs) => Literal::Map(rewrite_kv_pairs(self, kv_pairs)?),
+            Literal::MapCons(kv_pairs, rest) => {
+                Literal::MapCons(rewrite_kv_pairs(self, kv_pairs)?, self.rewrite_expr(rest)?)
+            }
+        };
+        Ok(rewritten)
+    }
+
+    fn rewrite_lambda(&mut self, lambda: &Lambda) -> Result<Lambda, Self::Error> {
+        let mut variants = Vec::new();
+        for variant in lambda.variants_iter() {
+            variants.push(Rc::new(LambdaVariant {
+                args: LambdaArgs::new(rewrite_patterns(self, variant.args.iter())?),
+                guard: rewrite_opt(self, variant.guard.as_ref())?,
+                body: self.rewrite_expr(&variant.body)?,
+            }))
+        }
+        Ok(Lambda::new(variants))
+    }
+
+    fn rewrite_query(&mut self, query: &Query) -> Result<Query, Self::Error> {
+        let mut bindings = Vec::with_capacity(query.bindings().len());
+        for binding in query.bindings().iter() {
+            let mut ids = Vec::with_capacity(binding.ids().len());
+            for id in binding.ids().iter() {
+                ids.push(self.rewrite_binding(id)?)
+            }
+            let val = self.rewrite_expr(&binding.val())?;
+            bindings.push(if binding.is_negated() {
+                QueryBinding::new_negated(ids, val)
+            } else {
+                QueryBinding::new(ids, val)
+            })
+        }
+        Ok(Query::new(
+            QueryBindings::new(bindings),
+            QueryGuards::new(rewrite_all(self, query.guards().iter())?),
+            self.rewrite_expr(query.production())?,
+        ))
+    }
+
+    fn rewrite_fn_def(&mut self, fn_def: &FnDef) -> Result<Rc<RefCell<FnDef>>, Self::Error> {
+        let mut variants = Vec::new();
+        for FnDefVariant(args, guard, body) in fn_def.variants_iter() {
+            let body = match &**body {
+                FnDefBody::RogatoFn(expr) => Rc::new(FnDefBody::rogato(self.rewrite_expr(expr)?)),
+                FnDefBody::NativeFn(_) => Rc::clone(body),
+            };
+            variants.push(FnDefVariant(
+                FnDefArgs::new(rewrite_patterns(self, args.iter())?),
+                rewrite_opt(self, guard.as_ref())?,
+                body,
+            ))
+        }
+        let rewritten = if fn_def.is_inline {
+            FnDef::new_inline_with_variants(fn_def.id.clone(), FnDefVariants::new(variants))
+        } else {
+            FnDef::new_with_variants(fn_def.id.clone(), FnDefVariants::new(variants))
+        };
+        if let Some(module_id) = fn_def.module_id() {
+            rewritten.borrow_mut().set_module_id(module_id.clone());
+        }
+        Ok(rewritten)
+    }
+}
+
+fn rewrite_all<'e, R: Rewriter + ?Sized, I: Iterator<Item = &'e Node<Expression>>>(
+    rewriter: &mut R,
+    exprs: I,
+) -> Result<Vec<Node<Expression>>, R::Error> {
+    exprs.map(|e| rewriter.rewrite_expr(e)).collect()
+}
+
+fn rewrite_opt<R: Rewriter + ?Sized>(
+    rewriter: &mut R,
+    expr: Option<&Node<Expression>>,
+) -> Result<Option<Node<Expression>>, R::Error> {
+    expr.map(|e| rewriter.rewrite_expr(e)).transpose()
+}
+
+fn rewrite_patterns<'p, R: Rewriter + ?Sized, I: Iterator<Item = &'p Node<Pattern>>>(
+    rewriter: &mut R,
+    patterns: I,
+) -> Result<Vec<Node<Pattern>>, R::Error> {
+    patterns.map(|p| rewriter.rewrite_pattern(p)).collect()
+}
+
+fn rewrite_props<R: Rewriter + ?Sized>(
+    rewriter: &mut R,
+    props: &StructProps,
+) -> Result<StructProps, R::Error> {
+    let mut rewritten = Vec::with_capacity(props.len());
+    for (id, val) in props.iter() {
+        rewritten.push((id.clone(), rewriter.rewrite_expr(val)?))
+    }
+    Ok(StructProps::from(rewritten))
+}
+
+fn rewrite_kv_pairs<R: Rewriter + ?Sized>(
+    rewriter: &mut R,
+    kv_pairs: &TupleItems<MapKVPair<Expression>>,
+) -> Result<TupleItems<MapKVPair<Expression>>, R::Error> {
+    let mut rewritten = Vec::with_capacity(kv_pairs.len());
+    for kv_pair in kv_pairs.iter() {
+        rewritten.push(Node::new(MapKVPair::new(
+            rewriter.rewrite_expr(&kv_pair.key)?,
+            rewriter.rewrite_expr(&kv_pair.value)?,
+        )))
+    }
+    Ok(TupleItems::from(rewritten))
+}
@@ -0,0 +1,77 @@
+use std::{fmt::Display, rc::Rc};
+
+/// Location of a parsed node within its source code.
+/// `start` and `end` are byte offsets, `line` and `column` are 1-based.
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Default)]
+pub struct Span {
+    source: Option<Rc<str>>,
+    start: usize,
+    end: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Span {
+    pub fn new(
+        source: Option<Rc<str>>,
+        start: usize,
+        end: usize,
+        line: usize,
+        column: usize,
+    ) -> Span {
+        Span {
+            source,
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Returns a span covering both `self` and `other`,
+    /// starting at the line and column of whichever starts first.
+    pub fn merge(&self, other: &Span) -> Span {
+        let first = if other.start < self.start {
+            other
+        } else {
+            self
+        };
+        Span {
+            source: self.source.clone(),
+            start: first.start,
+            end: self.end.max(other.end),
+            line: first.line,
+            column: first.column,
+        }
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(source) = &self.source {
+            f.write_str(source)?;
+            f.write_str(":")?;
+        }
+        f.write_fmt(format_args!("{}:{}", self.line, self.column))
+    }
+}
@@ -1,19 +1,19 @@
-use std::{fmt::Display, rc::Rc};
+use std::fmt::Display;
 
 use crate::util::indent;
 
 use self::super::expression::{LambdaArgs, TupleItems};
 
-use super::{ASTDepth, Identifier};
+use super::{ASTDepth, Identifier, Node};
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct TypeDef {
     id: Identifier,
-    type_expr: Rc<TypeExpression>,
+    type_expr: Node<TypeExpression>,
 }
 
 impl TypeDef {
-    pub fn new(id: Identifier, type_expr: Rc<TypeExpression>) -> TypeDef {
+    pub fn new(id: Identifier, type_expr: Node<TypeExpression>) -> TypeDef {
         TypeDef { id, type_expr }
     }
 
@@ -21,14 +21,19 @@ impl TypeDef {
         self.id.clone()
     }
 
-    pub fn type_expr(&self) -> Rc<TypeExpression> {
-        Rc::clone(&self.type_expr)
+    pub fn type_expr(&self) -> Node<TypeExpression> {
+        Node::clone(&self.type_expr)
     }
 }
 
 impl Display for TypeDef {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!("type {} :: {}", self.id, self.type_expr))
+        match *self.type_expr {
+            TypeExpression::Union(_) => {
+                f.write_fmt(format_args!("type {} = {}", self.id, self.type_expr))
+            }
+            _ => f.write_fmt(format_args!("type {} :: {}", self.id, self.type_expr)),
+        }
     }
 }
 
@@ -44,10 +49,11 @@ pub enum TypeExpression {
     NumberType,
     StringType,
     TypeRef(Identifier),
-    FunctionType(LambdaArgs<TypeExpression>, Rc<TypeExpression>), // args & return type
+    FunctionType(LambdaArgs<TypeExpression>, Node<TypeExpression>), // args & return type
     TupleType(TupleItems<TypeExpression>),
-    ListType(Rc<TypeExpression>),
+    ListType(Node<TypeExpression>),
     StructType(StructTypeProperties),
+    Union(UnionTypeVariants),
 }
 
 impl Display for TypeExpression {
@@ -77,6 +83,7 @@ impl Display for TypeExpression {
                 indent(struct_type_props).fmt(f)?;
                 f.write_str("\n}")
             }
+            TypeExpression::Union(variants) => variants.fmt(f),
         }
     }
 }
@@ -96,22 +103,25 @@ impl ASTDepth for TypeExpression {
             }
             TypeExpression::ListType(type_expr) => 1 + type_expr.ast_depth(),
             TypeExpression::StructType(struct_type) => 1 + struct_type.ast_depth(),
+            TypeExpression::Union(variants) => 1 + variants.ast_depth(),
         }
     }
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
 pub struct StructTypeProperties {
-    prop_types: Vec<(Identifier, Rc<TypeExpression>)>,
+    prop_types: Vec<(Identifier, Node<TypeExpression>)>,
 }
 
 impl StructTypeProperties {
-    pub fn new<Props: IntoIterator<Item = (Identifier, Rc<TypeExpression>)>>(props: Props) -> Self {
+    pub fn new<Props: IntoIterator<Item = (Identifier, Node<TypeExpression>)>>(
+        props: Props,
+    ) -> Self {
         let prop_types = props.into_iter().collect();
         StructTypeProperties { prop_types }
     }
 
-    pub fn iter(&self) -> std::slice::Iter<(Identifier, Rc<TypeExpression>)> {
+    pub fn iter(&self) -> std::slice::Iter<(Identifier, Node<TypeExpression>)> {
         self.prop_types.iter()
     }
 
@@ -124,8 +134,8 @@ impl StructTypeProperties {
     }
 }
 
-impl FromIterator<(Identifier, Rc<TypeExpression>)> for StructTypeProperties {
-    fn from_iter<T: IntoIterator<Item = (Identifier, Rc<TypeExpression>)>>(iter: T) -> Self {
+impl FromIterator<(Identifier, Node<TypeExpression>)> for StructTypeProperties {
+    fn from_iter<T: IntoIterator<Item = (Identifier, Node<TypeExpression>)>>(iter: T) -> Self {
         StructTypeProperties::new(iter)
     }
 }
@@ -154,3 +164,70 @@ impl ASTDepth for StructTypeProperties {
             .sum::<usize>()
     }
 }
+
+/// The constructor variants of a tagged union (sum) type, e.g. `Ok val | Err err`.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct UnionTypeVariants {
+    variants: Vec<(Identifier, Vec<Node<TypeExpression>>)>,
+}
+
+impl UnionTypeVariants {
+    pub fn new<Variants: IntoIterator<Item = (Identifier, Vec<Node<TypeExpression>>)>>(
+        variants: Variants,
+    ) -> Self {
+        let variants = variants.into_iter().collect();
+        UnionTypeVariants { variants }
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, (Identifier, Vec<Node<TypeExpression>>)> {
+        self.variants.iter()
+    }
+
+    pub fn get(&self, id: &Identifier) -> Option<&[Node<TypeExpression>]> {
+        self.variants
+            .iter()
+            .find(|(variant_id, _)| variant_id == id)
+            .map(|(_, params)| params.as_slice())
+    }
+
+    pub fn len(&self) -> usize {
+        self.variants.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl FromIterator<(Identifier, Vec<Node<TypeExpression>>)> for UnionTypeVariants {
+    fn from_iter<T: IntoIterator<Item = (Identifier, Vec<Node<TypeExpression>>)>>(iter: T) -> Self {
+        UnionTypeVariants::new(iter)
+    }
+}
+
+impl Display for UnionTypeVariants {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut is_first = true;
+        for (id, params) in self.variants.iter() {
+            if !is_first {
+                f.write_str(" | ")?;
+            }
+            id.fmt(f)?;
+            for param in params.iter() {
+                f.write_str(" ")?;
+                param.fmt(f)?;
+            }
+            is_first = false;
+        }
+        Ok(())
+    }
+}
+
+impl ASTDepth for UnionTypeVariants {
+    fn ast_depth(&self) -> usize {
+        self.variants
+            .iter()
+            .map(|(_id, params)| 1 + params.iter().map(|p| p.ast_depth()).sum::<usize>())
+            .sum::<usize>()
+    }
+}
@@ -0,0 +1,107 @@
+use super::{ASTDepth, Identifier};
+use std::fmt::Display;
+
+/// A `use` statement importing definitions from a module and/or giving it a local alias, e.g.:
+/// `use Std.List as L`, `use Std.List { map as lmap, Type Foo }` or `use Std.List { * }`.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct UseDef {
+    id: Identifier,
+    alias: Option<Identifier>,
+    imports: UseImports,
+}
+
+impl UseDef {
+    pub fn new(id: Identifier, alias: Option<Identifier>, imports: UseImports) -> UseDef {
+        UseDef { id, alias, imports }
+    }
+
+    pub fn id(&self) -> &Identifier {
+        &self.id
+    }
+
+    pub fn alias(&self) -> Option<&Identifier> {
+        self.alias.as_ref()
+    }
+
+    pub fn imports(&self) -> &UseImports {
+        &self.imports
+    }
+}
+
+impl Display for UseDef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("use ")?;
+        self.id.fmt(f)?;
+        if let Some(alias) = &self.alias {
+            f.write_fmt(format_args!(" as {alias}"))?;
+        }
+        match &self.imports {
+            UseImports::Specific(imports) if imports.is_empty() => Ok(()),
+            imports => f.write_fmt(format_args!(" {{ {imports} }}")),
+        }
+    }
+}
+
+impl ASTDepth for UseDef {
+    fn ast_depth(&self) -> usize {
+        1 + self.imports.ast_depth()
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum UseImports {
+    All,
+    Specific(Vec<UseImport>),
+}
+
+impl Display for UseImports {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UseImports::All => f.write_str("*"),
+            UseImports::Specific(imports) => {
+                let mut is_first = true;
+                for import in imports.iter() {
+                    if !is_first {
+                        f.write_str(", ")?;
+                    }
+                    import.fmt(f)?;
+                    is_first = false;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl ASTDepth for UseImports {
+    fn ast_depth(&self) -> usize {
+        match self {
+            UseImports::All => 1,
+            UseImports::Specific(imports) => imports.len(),
+        }
+    }
+}
+
+/// A single imported function or type, optionally aliased locally.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum UseImport {
+    Func(Identifier, Option<Identifier>),
+    Type(Identifier, Option<Identifier>),
+}
+
+impl Display for UseImport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (id, alias) = match self {
+            UseImport::Func(id, alias) => (id, alias),
+            UseImport::Type(id, alias) => {
+                f.write_str("Type ")?;
+                (id, alias)
+            }
+        };
+        id.fmt(f)?;
+        match alias {
+            Some(alias) => f.write_fmt(format_args!(" as {alias}")),
+            None => Ok(()),
+        }
+    }
+}
@@ -3,9 +3,10 @@ use fn_call::FnCall;
 use let_expression::LetExpression;
 
 use super::{
-    expression::{Lambda, Literal, Query},
+    expression::{Case, Lambda, Literal, Query},
     if_else::IfElse,
     pattern::Pattern,
+    use_def::UseDef,
     *,
 };
 
@@ -16,7 +17,7 @@ pub trait Visitor<T: Default> {
     fn module_def(&mut self, _mod_def: &ModuleDef) -> T {
         T::default()
     }
-    fn use_stmt(&mut self, _mod_def: &Identifier, _imports: &[Identifier]) -> T {
+    fn use_def(&mut self, _use_def: &UseDef) -> T {
         T::default()
     }
     fn fn_def(&mut self, _fn_def: &FnDef) -> T {
@@ -25,6 +26,12 @@ pub trait Visitor<T: Default> {
     fn type_def(&mut self, _type_def: &TypeDef) -> T {
         T::default()
     }
+    fn fixity_def(&mut self, _fixity_def: &FixityDef) -> T {
+        T::default()
+    }
+    fn macro_def(&mut self, _macro_def: &MacroDef) -> T {
+        T::default()
+    }
     fn commented(&mut self, _commented: &str, _expr: &Expression) -> T {
         T::default()
     }
@@ -49,12 +56,21 @@ pub trait Visitor<T: Default> {
     fn prop_fn_ref(&mut self, _id: &Identifier) -> T {
         T::default()
     }
+    fn fn_ref(&mut self, _id: &Identifier) -> T {
+        T::default()
+    }
+    fn prop_access(&mut self, _expr: &Expression, _prop: &Identifier) -> T {
+        T::default()
+    }
     fn edge_prop(&mut self, _expr: &Expression, _edge: &Identifier) -> T {
         T::default()
     }
     fn if_else(&mut self, _if_else: &IfElse) -> T {
         T::default()
     }
+    fn case(&mut self, _case: &Case) -> T {
+        T::default()
+    }
     fn let_(&mut self, _let_expr: &LetExpression) -> T {
         T::default()
     }
@@ -1,5 +1,5 @@
 use super::{
-    expression::{Expression, Literal},
+    expression::{Expression, Literal, StringPart},
     fn_def::FnDefVariant,
     visitor::Visitor,
     AST,
@@ -14,18 +14,29 @@ impl Walk for AST {
         match self {
             AST::RootComment(c) => v.root_comment(c),
             AST::ModuleDef(mod_def) => v.module_def(mod_def),
-            AST::Use(id, imports) => v.use_stmt(id, imports),
+            AST::Use(use_def) => v.use_def(use_def),
             AST::FnDef(fn_def) => {
                 let fn_def = fn_def.borrow();
                 v.fn_def(&fn_def);
-                for FnDefVariant(args, body) in fn_def.variants_iter() {
+                for FnDefVariant(args, guard, body) in fn_def.variants_iter() {
                     for a in args.iter() {
                         a.walk(v);
                     }
+                    if let Some(guard) = guard {
+                        guard.walk(v);
+                    }
                     body.walk(v);
                 }
             }
             AST::TypeDef(type_def) => v.type_def(type_def),
+            AST::FixityDef(fixity_def) => v.fixity_def(fixity_def),
+            AST::MacroDef(macro_def) => {
+                v.macro_def(macro_def);
+                for a in macro_def.args().iter() {
+                    a.walk(v);
+                }
+                macro_def.body().walk(v);
+            }
         }
     }
 }
@@ -43,6 +54,13 @@ impl Walk for Expression {
                     Literal::Bool(_) => {}
                     Literal::Number(_) => {}
                     Literal::String(_) => {}
+                    Literal::InterpolatedString(string) => {
+                        for part in string.iter() {
+                            if let StringPart::Expr(expr) = part {
+                                expr.walk(v)
+                            }
+                        }
+                    }
                     Literal::List(vals) => {
                         for val in vals.iter() {
                             val.walk(v)
@@ -62,6 +80,12 @@ impl Walk for Expression {
                             val.walk(v)
                         }
                     }
+                    Literal::StructUpdate(base, props) => {
+                        base.walk(v);
+                        for (_prop_name, val) in props.iter() {
+                            val.walk(v)
+                        }
+                    }
                     Literal::Map(kv_pairs) => {
                         for kv_pair in kv_pairs.iter() {
                             kv_pair.key.walk(v);
@@ -92,8 +116,11 @@ impl Walk for Expression {
             Expression::ConstOrTypeRef(id) => v.const_or_type_ref(id),
             Expression::DBTypeRef(id) => v.db_type_ref(id),
             Expression::PropFnRef(id) => v.prop_fn_ref(id),
+            Expression::FnRef(id) => v.fn_ref(id),
+            Expression::PropAccess(expr, prop) => v.prop_access(expr, prop),
             Expression::EdgeProp(id, edge) => v.edge_prop(id, edge),
             Expression::IfElse(if_else) => if_else.walk(v),
+            Expression::Case(case) => case.walk(v),
             Expression::Let(let_expr) => let_expr.walk(v),
             Expression::Lambda(lambda) => lambda.walk(v),
             Expression::Query(query) => query.walk(v),
@@ -0,0 +1,50 @@
+use std::{error::Error, fmt::Display, ops::Deref, rc::Rc};
+
+/// A cloneable, type-erased error used to chain errors across crate boundaries,
+/// e.g. an interpreter error raised within a native function.
+/// Derefs to the wrapped error, so when used as an error's `#[source]` the wrapped error itself
+/// shows up in the `source()` chain and can be recovered via `downcast_ref`.
+/// Two boxed errors are equal if they wrap equal errors of the same type.
+#[derive(Clone, Debug)]
+pub struct BoxedError(
+    Rc<dyn Error>,
+    fn(&(dyn Error + 'static), &(dyn Error + 'static)) -> bool,
+);
+
+impl BoxedError {
+    pub fn new<E: Error + PartialEq + 'static>(error: E) -> Self {
+        BoxedError(Rc::new(error), Self::eq_as::<E>)
+    }
+
+    fn eq_as<E: Error + PartialEq + 'static>(
+        a: &(dyn Error + 'static),
+        b: &(dyn Error + 'static),
+    ) -> bool {
+        match (a.downcast_ref::<E>(), b.downcast_ref::<E>()) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Deref for BoxedError {
+    type Target = dyn Error;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.0
+    }
+}
+
+impl Display for BoxedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl PartialEq for BoxedError {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0) || (self.1)(&*self.0, &*other.0)
+    }
+}
+
+impl Eq for BoxedError {}
@@ -1,4 +1,5 @@
 pub mod ast;
+pub mod error;
 pub mod native_fn;
 pub mod util;
 pub mod val;
@@ -2,10 +2,12 @@ use std::{cell::RefCell, rc::Rc};
 
 use crate::{
     ast::{
+        expression::Expression,
         fn_def::FnDef,
         lambda::{Lambda, LambdaClosureContext, LambdaClosureEvalError},
-        Identifier, VarIdentifier,
+        Identifier, Node, VarIdentifier, AST,
     },
+    error::BoxedError,
     val::ValueRef,
 };
 use thiserror::Error;
@@ -18,13 +20,34 @@ pub enum NativeFnError {
     #[error("Invalid arguments for NativeFn ^{0}")]
     InvalidArguments(Identifier),
 
-    #[error("Evaluation failed for NativeFn ^{0} with: {1}")]
-    EvaluationFailed(Identifier, String),
+    #[error("FunctionRef invalid in ^{0}: ^{1}")]
+    FunctionRefInvalid(Identifier, Identifier),
+
+    #[error("Evaluation failed for NativeFn ^{0}")]
+    EvaluationFailed(Identifier, #[source] BoxedError),
+
+    #[error("Evaluation failed for lambda called in NativeFn: {0}")]
+    LambdaEvaluationFailed(Rc<Lambda>, #[source] BoxedError),
+
+    #[error("Evaluation failed for function value called in NativeFn: {0}")]
+    ValueCallFailed(ValueRef, #[source] BoxedError),
+}
+
+impl NativeFnError {
+    /// The error raised within the called function, lambda or code that caused this one, if any.
+    pub fn boxed_source(&self) -> Option<&BoxedError> {
+        match self {
+            NativeFnError::EvaluationFailed(_, source)
+            | NativeFnError::LambdaEvaluationFailed(_, source)
+            | NativeFnError::ValueCallFailed(_, source) => Some(source),
+            _ => None,
+        }
+    }
 }
 
 impl From<LambdaClosureEvalError> for NativeFnError {
     fn from(e: LambdaClosureEvalError) -> Self {
-        NativeFnError::EvaluationFailed("LambdaClosure".into(), e.to_string())
+        NativeFnError::EvaluationFailed("LambdaClosure".into(), BoxedError::new(e))
     }
 }
 
@@ -32,6 +55,10 @@ pub trait NativeFnContext {
     fn lookup_var(&self, id: &VarIdentifier) -> Option<ValueRef>;
     fn lookup_const(&self, id: &Identifier) -> Option<ValueRef>;
     fn lookup_function(&self, id: &Identifier) -> Option<Rc<RefCell<FnDef>>>;
+    fn current_func_id(&self) -> Identifier;
+
+    /// Returns the id of the union type the given variant constructor belongs to.
+    fn lookup_constructor_type(&self, id: &Identifier) -> Option<Identifier>;
 
     fn call_function(
         &mut self,
@@ -51,6 +78,16 @@ pub trait NativeFnContext {
         func: Rc<RefCell<FnDef>>,
         args: &[ValueRef],
     ) -> Result<ValueRef, NativeFnError>;
+
+    /// Calls a function value, i.e. a lambda, a function reference or a partial application.
+    fn call_value(&mut self, func: &ValueRef, args: &[ValueRef])
+        -> Result<ValueRef, NativeFnError>;
+
+    fn evaluate_expr(&mut self, expr: &Node<Expression>) -> Result<ValueRef, NativeFnError>;
+    fn evaluate_ast(&mut self, ast: &Node<AST>) -> Result<ValueRef, NativeFnError>;
+
+    /// A new context with only the std lib loaded, for evaluating code in isolation.
+    fn fresh_context(&self) -> Box<dyn NativeFnContext>;
 }
 
 pub type NativeFn =
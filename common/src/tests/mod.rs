@@ -1 +1,2 @@
-
+#[cfg(test)]
+pub mod span;
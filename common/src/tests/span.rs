@@ -0,0 +1,14 @@
+use crate::ast::Span;
+use std::rc::Rc;
+
+#[test]
+fn merged_spans() {
+    let source: Option<Rc<str>> = Some("f.roga".into());
+    let first = Span::new(source.clone(), 5, 10, 2, 1);
+    let second = Span::new(source, 14, 20, 3, 3);
+
+    for merged in [first.merge(&second), second.merge(&first)] {
+        assert_eq!((merged.start(), merged.end()), (5, 20));
+        assert_eq!(merged.to_string(), "f.roga:2:1");
+    }
+}
@@ -2,7 +2,7 @@ use std::{fmt::Display, hash::Hash, rc::Rc};
 
 use super::{Value, ValueRef};
 use crate::{
-    ast::{expression::TupleItems, ASTDepth},
+    ast::{expression::TupleItems, ASTDepth, Node},
     util::indent,
 };
 
@@ -121,7 +121,8 @@ impl ASTDepth for List {
 
 impl Display for List {
     fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let items: TupleItems<Value> = TupleItems::from_iter(self.iter().map(Rc::clone));
+        let items: TupleItems<Value> =
+            TupleItems::from_iter(self.iter().map(|v| Node::from(Rc::clone(v))));
 
         if items.ast_depth() > 6 {
             let items_str = format!("{items}");
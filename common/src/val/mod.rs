@@ -9,6 +9,7 @@ pub use serde_json::Number;
 pub mod list;
 pub mod map;
 pub mod object;
+pub mod partial;
 pub mod queue;
 pub mod set;
 pub mod stack;
@@ -17,6 +18,7 @@ pub mod vector;
 pub use list::List;
 pub use map::Map;
 pub use object::Object;
+pub use partial::Partial;
 pub use queue::Queue;
 pub use set::Set;
 pub use stack::Stack;
@@ -25,7 +27,8 @@ pub use vector::Vector;
 use crate::ast::lambda::{Lambda, LambdaClosureContext};
 use crate::ast::{
     expression::{Expression, TupleItems},
-    ASTDepth, Identifier, AST,
+    fn_def::FnDef,
+    ASTDepth, Identifier, Node, AST,
 };
 use crate::util::indent;
 
@@ -53,6 +56,19 @@ pub fn symbol<ID: Into<Identifier>>(s: ID) -> ValueRef {
     ValueRef::new(Value::Symbol(id))
 }
 
+/// A value of a union type, constructed via one of the type's variant constructors.
+pub fn variant<TID: Into<Identifier>, ID: Into<Identifier>, I: IntoIterator<Item = ValueRef>>(
+    type_id: TID,
+    id: ID,
+    args: I,
+) -> ValueRef {
+    ValueRef::new(Value::Variant(
+        type_id.into(),
+        id.into(),
+        args.into_iter().collect(),
+    ))
+}
+
 pub fn bool(b: bool) -> ValueRef {
     ValueRef::new(Value::Bool(b))
 }
@@ -105,14 +121,45 @@ pub fn object<S: ToString, Props: IntoIterator<Item = (S, ValueRef)>>(props: Pro
     ValueRef::new(Value::Object(Object::from_iter(props)))
 }
 
+/// A struct value of the given struct type, e.g. `Person{name: "Ann", age: 42}`.
+pub fn struct_val<ID: Into<Identifier>, S: ToString, Props: IntoIterator<Item = (S, ValueRef)>>(
+    struct_id: ID,
+    props: Props,
+) -> ValueRef {
+    let props: Vec<(String, ValueRef)> = props
+        .into_iter()
+        .map(|(prop, val)| (prop.to_string(), val))
+        .collect();
+    ValueRef::new(Value::Struct(struct_id.into(), Object::from_iter(props)))
+}
+
 pub fn lambda(ctx: Rc<RefCell<dyn LambdaClosureContext>>, l: Rc<Lambda>) -> ValueRef {
     ValueRef::new(Value::Lambda(ctx, l))
 }
 
-pub fn quoted(expr: Rc<Expression>) -> ValueRef {
+/// A reference to the given function, e.g. `&Std.List.map`.
+pub fn fn_ref(func: Rc<RefCell<FnDef>>) -> ValueRef {
+    ValueRef::new(Value::FnRef(func))
+}
+
+/// A partial application of the given function to the given (too few) arguments.
+pub fn partial_fn(func: Rc<RefCell<FnDef>>, args: Vec<ValueRef>) -> ValueRef {
+    ValueRef::new(Value::Partial(Partial::Fn(func, args)))
+}
+
+/// A partial application of the given lambda to the given (too few) arguments.
+pub fn partial_lambda(
+    ctx: Rc<RefCell<dyn LambdaClosureContext>>,
+    l: Rc<Lambda>,
+    args: Vec<ValueRef>,
+) -> ValueRef {
+    ValueRef::new(Value::Partial(Partial::Lambda(ctx, l, args)))
+}
+
+pub fn quoted(expr: Node<Expression>) -> ValueRef {
     ValueRef::new(Value::Quoted(expr))
 }
-pub fn quoted_ast(ast: Rc<AST>) -> ValueRef {
+pub fn quoted_ast(ast: Node<AST>) -> ValueRef {
     ValueRef::new(Value::QuotedAST(ast))
 }
 
@@ -139,9 +186,13 @@ pub enum Value {
     Set(Set),
     Map(Map),
     Object(Object),
+    Struct(Identifier, Object),
     Lambda(Rc<RefCell<dyn LambdaClosureContext>>, Rc<Lambda>),
-    Quoted(Rc<Expression>),
-    QuotedAST(Rc<AST>),
+    FnRef(Rc<RefCell<FnDef>>),
+    Partial(Partial),
+    Quoted(Node<Expression>),
+    QuotedAST(Node<AST>),
+    Variant(Identifier, Identifier, Vec<ValueRef>),
 }
 
 impl Value {
@@ -210,9 +261,15 @@ impl PartialEq for Value {
             (Value::Set(a), Value::Set(b)) => a.eq(b),
             (Value::Map(a), Value::Map(b)) => a.eq(b),
             (Value::Object(a), Value::Object(b)) => a.eq(b),
+            (Value::Struct(id_a, a), Value::Struct(id_b, b)) => id_a.eq(id_b) && a.eq(b),
             (Value::Lambda(_, a), Value::Lambda(_, b)) => a.eq(b),
+            (Value::FnRef(a), Value::FnRef(b)) => Rc::ptr_eq(a, b),
+            (Value::Partial(a), Value::Partial(b)) => a.eq(b),
             (Value::Quoted(a), Value::Quoted(b)) => a.eq(b),
             (Value::QuotedAST(a), Value::QuotedAST(b)) => a.eq(b),
+            (Value::Variant(type_a, id_a, a), Value::Variant(type_b, id_b, b)) => {
+                type_a.eq(type_b) && id_a.eq(id_b) && a.eq(b)
+            }
             _ => false,
         }
     }
@@ -243,6 +300,12 @@ impl Hash for Value {
             Value::List(l) => {
                 Hash::hash(&l, h);
             }
+            Value::FnRef(func) => {
+                Hash::hash(&Rc::as_ptr(func), h);
+            }
+            Value::Partial(p) => {
+                Hash::hash(&p, h);
+            }
             Value::Vector(v) => {
                 Hash::hash(&v, h);
             }
@@ -261,6 +324,10 @@ impl Hash for Value {
             Value::Object(o) => {
                 Hash::hash(&o, h);
             }
+            Value::Struct(id, o) => {
+                Hash::hash(&id, h);
+                Hash::hash(&o, h);
+            }
             Value::Lambda(ctx, l) => {
                 Hash::hash(&ctx.as_ptr(), h);
                 Hash::hash(&l, h);
@@ -271,6 +338,11 @@ impl Hash for Value {
             Value::QuotedAST(q) => {
                 Hash::hash(&q, h);
             }
+            Value::Variant(type_id, id, args) => {
+                Hash::hash(&type_id, h);
+                Hash::hash(&id, h);
+                Hash::hash(&args, h);
+            }
         }
     }
 }
@@ -292,7 +364,8 @@ impl Display for Value {
             Value::Bool(b) => b.fmt(f),
             Value::Number(d) => d.fmt(f),
             Value::Tuple(_size, items) => {
-                let items: TupleItems<Value> = TupleItems::from(items.clone());
+                let items: TupleItems<Value> =
+                    items.iter().map(|v| Node::from(Rc::clone(v))).collect();
                 if items.ast_depth() > 6 {
                     let items_str = format!("{items}");
                     if items_str.lines().count() == 1 {
@@ -311,7 +384,25 @@ impl Display for Value {
             Value::Set(set) => set.fmt(f),
             Value::Map(map) => map.fmt(f),
             Value::Object(object) => object.fmt(f),
+            Value::Struct(id, object) => {
+                id.fmt(f)?;
+                f.write_str("{")?;
+                let mut is_first = true;
+                for (prop, val) in object.sorted_properties() {
+                    if !is_first {
+                        f.write_str(",")?;
+                    }
+                    f.write_fmt(format_args!(" {prop}: {val}"))?;
+                    is_first = false;
+                }
+                f.write_str(" }")
+            }
             Value::Lambda(_, lambda) => lambda.fmt(f),
+            Value::FnRef(func) => {
+                f.write_str("&")?;
+                func.borrow().id().fmt(f)
+            }
+            Value::Partial(partial) => partial.fmt(f),
             Value::Quoted(expr) => {
                 f.write_str("^")?;
                 expr.fmt(f)
@@ -321,6 +412,18 @@ impl Display for Value {
                 ast.fmt(f)?;
                 f.write_str(")")
             }
+            Value::Variant(_, id, args) => {
+                id.fmt(f)?;
+                for arg in args.iter() {
+                    match &**arg {
+                        Value::Variant(_, _, nested_args) if !nested_args.is_empty() => {
+                            f.write_fmt(format_args!(" ({arg})"))?
+                        }
+                        _ => f.write_fmt(format_args!(" {arg}"))?,
+                    }
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -342,9 +445,13 @@ impl ASTDepth for Value {
             Value::Set(set) => set.ast_depth(),
             Value::Map(items) => items.ast_depth(),
             Value::Object(object) => object.ast_depth(),
+            Value::Struct(_, object) => object.ast_depth(),
             Value::Lambda(_, lambda) => lambda.ast_depth(),
+            Value::FnRef(_) => 1,
+            Value::Partial(partial) => partial.ast_depth(),
             Value::Quoted(expr) => 1 + expr.ast_depth(),
             Value::QuotedAST(ast) => 1 + ast.ast_depth(),
+            Value::Variant(_, _, args) => 1 + args.iter().map(|a| a.ast_depth()).sum::<usize>(),
         }
     }
 }
@@ -20,6 +20,42 @@ impl FromIterator<(String, ValueRef)> for Object {
     }
 }
 
+type ObjectIter<'a> = rpds::map::hash_trie_map::Iter<'a, String, ValueRef, archery::RcK>;
+
+impl Object {
+    pub fn iter(&self) -> ObjectIter<'_> {
+        self.properties.iter()
+    }
+
+    /// The properties sorted by name, for a stable display order.
+    pub fn sorted_properties(&self) -> Vec<(&String, &ValueRef)> {
+        let mut props: Vec<_> = self.properties.iter().collect();
+        props.sort_by_key(|(prop, _)| *prop);
+        props
+    }
+
+    /// Returns a copy with the given properties added or replaced.
+    pub fn update<Props: IntoIterator<Item = (String, ValueRef)>>(&self, props: Props) -> Self {
+        let mut properties = self.properties.clone();
+        for (prop, value) in props {
+            properties = properties.insert(prop, value)
+        }
+        Object { properties }
+    }
+
+    pub fn get(&self, prop: &str) -> Option<&ValueRef> {
+        self.properties.get(prop)
+    }
+
+    pub fn len(&self) -> usize {
+        self.properties.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
+    }
+}
+
 impl PartialEq for Object {
     fn eq(&self, other: &Self) -> bool {
         self.properties.eq(&other.properties)
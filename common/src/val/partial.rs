@@ -0,0 +1,101 @@
+use crate::ast::{
+    fn_def::FnDef,
+    lambda::{Lambda, LambdaClosureContext},
+    ASTDepth,
+};
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
+
+use super::{Value, ValueRef};
+
+/// A function or lambda applied to fewer arguments than it requires, e.g. `add 1`.
+/// Calling it applies the function to the captured arguments followed by the given ones.
+#[derive(Clone, Eq, Debug)]
+pub enum Partial {
+    Fn(Rc<RefCell<FnDef>>, Vec<ValueRef>),
+    Lambda(
+        Rc<RefCell<dyn LambdaClosureContext>>,
+        Rc<Lambda>,
+        Vec<ValueRef>,
+    ),
+}
+
+impl Partial {
+    /// The arguments applied so far.
+    pub fn args(&self) -> &[ValueRef] {
+        match self {
+            Partial::Fn(_, args) | Partial::Lambda(_, _, args) => args,
+        }
+    }
+
+    /// The captured arguments followed by the given ones.
+    pub fn args_with(&self, args: &[ValueRef]) -> Vec<ValueRef> {
+        let mut all_args = Vec::with_capacity(self.args().len() + args.len());
+        all_args.extend(self.args().iter().map(ValueRef::clone));
+        all_args.extend(args.iter().map(ValueRef::clone));
+        all_args
+    }
+}
+
+impl PartialEq for Partial {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Partial::Fn(a, args_a), Partial::Fn(b, args_b)) => {
+                Rc::ptr_eq(a, b) && args_a.eq(args_b)
+            }
+            (Partial::Lambda(_, a, args_a), Partial::Lambda(_, b, args_b)) => {
+                a.eq(b) && args_a.eq(args_b)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Hash for Partial {
+    fn hash<H: Hasher>(&self, h: &mut H) {
+        match self {
+            Partial::Fn(func, args) => {
+                Hash::hash(&Rc::as_ptr(func), h);
+                Hash::hash(&args, h);
+            }
+            Partial::Lambda(ctx, lambda, args) => {
+                Hash::hash(&ctx.as_ptr(), h);
+                Hash::hash(&lambda, h);
+                Hash::hash(&args, h);
+            }
+        }
+    }
+}
+
+impl Display for Partial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("(")?;
+        match self {
+            Partial::Fn(func, _) => func.borrow().id().fmt(f)?,
+            Partial::Lambda(_, lambda, _) => lambda.fmt(f)?,
+        }
+        for arg in self.args() {
+            match &**arg {
+                Value::Variant(_, _, nested_args) if !nested_args.is_empty() => {
+                    f.write_fmt(format_args!(" ({arg})"))?
+                }
+                _ => f.write_fmt(format_args!(" {arg}"))?,
+            }
+        }
+        f.write_str(")")
+    }
+}
+
+impl ASTDepth for Partial {
+    fn ast_depth(&self) -> usize {
+        let callee_depth = match self {
+            Partial::Fn(_, _) => 1,
+            Partial::Lambda(_, lambda, _) => lambda.ast_depth(),
+        };
+        callee_depth + self.args().iter().map(|a| a.ast_depth()).sum::<usize>()
+    }
+}
@@ -96,7 +96,7 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> {
         let f32_type = self.context.f32_type();
 
         // TODO: add support for multiple fn variants
-        let FnDefVariant(args, body) = fn_def.get_variant(0).unwrap();
+        let FnDefVariant(args, _guard, body) = fn_def.get_variant(0).unwrap();
 
         let fn_arg_types: Vec<BasicMetadataTypeEnum<'ctx>> = args
             .iter()
@@ -264,8 +264,10 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> {
                 Ok(())
             }
             AST::ModuleDef(mod_def) => self.codegen_module_def(mod_def),
-            AST::Use(_id, _imports) => todo!(),
+            AST::Use(_use_def) => todo!(),
             AST::TypeDef(type_def) => self.codegen_type_def(type_def),
+            AST::FixityDef(_) => Ok(()),
+            AST::MacroDef(_) => Ok(()),
         }
     }
 
@@ -290,10 +292,13 @@ impl<'a, 'ctx> Codegen<'a, 'ctx> {
             Expression::ConstOrTypeRef(_id) => todo!(),
             Expression::DBTypeRef(_id) => todo!(),
             Expression::PropFnRef(_id) => todo!(),
+            Expression::FnRef(_id) => todo!(),
+            Expression::PropAccess(_expr, _prop) => todo!(),
             Expression::EdgeProp(_id, _edge) => todo!(),
             Expression::IfElse(if_else) => self.codegen_if_else(if_else),
             Expression::Let(_let_expr) => todo!(),
             Expression::Lambda(_lambda) => todo!(),
+            Expression::Case(_case) => todo!(),
             Expression::Query(_query) => todo!(),
             Expression::Symbol(_id) => todo!(),
             Expression::Quoted(_expr) => todo!(),
@@ -0,0 +1,45 @@
+use crate::{
+    pattern_matching::{PatternMatch, PatternMatching},
+    tail_call::{EvaluateTail, Tail},
+    EvalContext, EvalError, Evaluate,
+};
+use rogato_common::ast::case::Case;
+use rogato_common::val::ValueRef;
+
+#[cfg(feature = "flame_it")]
+use flamer::flame;
+
+impl Evaluate<ValueRef> for Case {
+    #[cfg_attr(feature = "flame_it", flame("Case::"))]
+    fn evaluate(&self, context: &mut EvalContext) -> Result<ValueRef, EvalError> {
+        let val = self.scrutinee.evaluate(context)?;
+        for arm in self.arms_iter() {
+            let mut arm_ctx = context.with_child_env();
+            match arm
+                .pattern
+                .pattern_match(&mut arm_ctx, ValueRef::clone(&val))?
+            {
+                PatternMatch::Matched(_) => return arm.body.evaluate(&mut arm_ctx),
+                PatternMatch::TryNextPattern => continue,
+            }
+        }
+        Err(EvalError::CaseNoArmMatched(ValueRef::clone(&val)))
+    }
+}
+
+impl EvaluateTail for Case {
+    fn evaluate_tail(&self, context: &mut EvalContext) -> Result<Tail, EvalError> {
+        let val = self.scrutinee.evaluate(context)?;
+        for arm in self.arms_iter() {
+            let mut arm_ctx = context.with_child_env();
+            match arm
+                .pattern
+                .pattern_match(&mut arm_ctx, ValueRef::clone(&val))?
+            {
+                PatternMatch::Matched(_) => return arm.body.evaluate_tail(&mut arm_ctx),
+                PatternMatch::TryNextPattern => continue,
+            }
+        }
+        Err(EvalError::CaseNoArmMatched(ValueRef::clone(&val)))
+    }
+}
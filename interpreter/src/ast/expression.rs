@@ -1,18 +1,28 @@
-use std::rc::Rc;
-
-use crate::{EvalContext, EvalError, Evaluate};
+use crate::{
+    quasi_quote::QuasiQuote,
+    tail_call::{EvaluateTail, Tail},
+    EvalContext, EvalError, Evaluate,
+};
 use rogato_common::{
     ast::{
         expression::Expression,
-        helpers::{fn_call, lambda, var},
+        helpers::{lambda, prop_access, var},
+        Node,
     },
-    val::ValueRef,
-    val::{self},
+    val::{self, Value, ValueRef},
 };
 
 #[cfg(feature = "flame_it")]
 use flamer::flame;
 
+impl Evaluate<ValueRef> for Node<Expression> {
+    fn evaluate(&self, context: &mut EvalContext) -> Result<ValueRef, EvalError> {
+        (**self)
+            .evaluate(context)
+            .map_err(|e| e.with_span(self.span()))
+    }
+}
+
 impl Evaluate<ValueRef> for Expression {
     #[cfg_attr(feature = "flame_it", flame("Expression::"))]
     fn evaluate(&self, context: &mut EvalContext) -> Result<ValueRef, EvalError> {
@@ -29,9 +39,15 @@ impl Evaluate<ValueRef> for Expression {
             }
             Expression::Var(id) => match context.lookup_var(id) {
                 Some(var) => Ok(var),
-                None => match context.call_function(&id.into(), &[]) {
-                    Some(val) => Ok(val?),
-                    None => Err(EvalError::VarNotDefined(id.clone())),
+                None => match context.lookup_fn(&id.into()) {
+                    // functions without args are constants, all others are referenced
+                    Some(func) if func.borrow().required_args() == 0 => {
+                        context.call_function_direct(func, &[])
+                    }
+                    Some(func) => Ok(val::fn_ref(func)),
+                    None => {
+                        Err(context.private_or(&id.into(), EvalError::VarNotDefined(id.clone())))
+                    }
                 },
             },
             Expression::ConstOrTypeRef(id) => match context.lookup_const(id) {
@@ -42,7 +58,17 @@ impl Evaluate<ValueRef> for Expression {
                         ("id", val::string(type_.id())),
                         ("expression", val::string(format!("{type_}"))),
                     ])),
-                    None => Err(EvalError::ConstOrTypeNotFound(id.clone())),
+                    // constructors without params are constants, all others need arguments
+                    None => match context.lookup_constructor_fn(id) {
+                        Some(func) => Err(EvalError::ConstructorArityMismatch(
+                            id.clone(),
+                            func.borrow().max_args(),
+                            0,
+                        )),
+                        None => {
+                            Err(context.private_or(id, EvalError::ConstOrTypeNotFound(id.clone())))
+                        }
+                    },
                 },
             },
             Expression::DBTypeRef(id) => match context.lookup_db_type(id) {
@@ -54,20 +80,63 @@ impl Evaluate<ValueRef> for Expression {
                 None => Err(EvalError::DBTypeNotFound(id.clone())),
             },
             Expression::PropFnRef(id) => {
-                let lambda = lambda(["object"], fn_call(id, [var("object")]));
-                lambda.evaluate(context)
+                let getter = id.split('.').fold(var("object"), prop_access);
+                lambda(["object"], getter).evaluate(context)
+            }
+            Expression::FnRef(id) => match context.lookup_fn(id) {
+                Some(func) => Ok(val::fn_ref(func)),
+                None => Err(context.private_or(id, EvalError::FunctionNotDefined(id.clone()))),
+            },
+            Expression::PropAccess(expr, prop) => {
+                let value = expr.evaluate(context)?;
+                match &*value {
+                    Value::Object(object) | Value::Struct(_, object) => match object.get(prop) {
+                        Some(prop_value) => Ok(ValueRef::clone(prop_value)),
+                        None => Err(EvalError::PropertyNotFound(prop.clone(), value)),
+                    },
+                    _ => Err(EvalError::PropertyNotFound(prop.clone(), value)),
+                }
             }
             Expression::EdgeProp(_id, _edge) => Ok(val::string("eval edge prop")),
             Expression::IfElse(if_else) => if_else.evaluate(context),
+            Expression::Case(case) => case.evaluate(context),
             Expression::Let(let_expr) => let_expr.evaluate(context),
             Expression::Lambda(lambda) => lambda.evaluate(context),
             Expression::Query(query) => query.evaluate(context),
             Expression::Symbol(id) => Ok(val::symbol(id.clone())),
-            Expression::Quoted(expr) => Ok(val::quoted(Rc::clone(expr))),
-            Expression::QuotedAST(ast) => Ok(val::quoted_ast(Rc::clone(ast))),
-            Expression::Unquoted(expr) => Ok(val::string(format!("~({expr})"))),
-            Expression::UnquotedAST(ast) => Ok(val::string(format!("~({ast})"))),
+            Expression::Quoted(expr) => {
+                let expanded = QuasiQuote::new(context).expand_expr(expr)?;
+                Ok(val::quoted(expanded))
+            }
+            Expression::QuotedAST(ast) => {
+                let expanded = QuasiQuote::new(context).expand_ast(ast)?;
+                Ok(val::quoted_ast(Node::new(expanded)))
+            }
+            Expression::Unquoted(_) | Expression::UnquotedAST(_) => {
+                Err(EvalError::UnquoteOutsideQuote(Node::new(self.clone())))
+            }
             Expression::InlineFnDef(fn_def) => fn_def.borrow().evaluate(context),
         }
     }
 }
+
+impl EvaluateTail for Node<Expression> {
+    fn evaluate_tail(&self, context: &mut EvalContext) -> Result<Tail, EvalError> {
+        (**self)
+            .evaluate_tail(context)
+            .map_err(|e| e.with_span(self.span()))
+    }
+}
+
+impl EvaluateTail for Expression {
+    fn evaluate_tail(&self, context: &mut EvalContext) -> Result<Tail, EvalError> {
+        match self {
+            Expression::Commented(_c, e) => e.evaluate_tail(context),
+            Expression::FnCall(fn_call) => fn_call.evaluate_tail(context),
+            Expression::IfElse(if_else) => if_else.evaluate_tail(context),
+            Expression::Case(case) => case.evaluate_tail(context),
+            Expression::Let(let_expr) => let_expr.evaluate_tail(context),
+            _ => self.evaluate(context).map(Tail::Value),
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use rogato_common::{
+    ast::fixity::FixityDef,
+    val::{self, ValueRef},
+};
+
+use crate::{EvalContext, EvalError, Evaluate};
+
+#[cfg(feature = "flame_it")]
+use flamer::flame;
+
+impl Evaluate<ValueRef> for FixityDef {
+    #[cfg_attr(feature = "flame_it", flame("FixityDef::"))]
+    fn evaluate(&self, context: &mut EvalContext) -> Result<ValueRef, EvalError> {
+        context.define_fixity(self);
+        Ok(val::string(format!("FixityDef {self}")))
+    }
+}
@@ -1,5 +1,3 @@
-use std::rc::Rc;
-
 use rogato_common::{
     ast::{
         fn_call::{FnCall, FnCallArgs},
@@ -11,28 +9,51 @@ use rogato_common::{
 #[cfg(feature = "flame_it")]
 use flamer::flame;
 
-use crate::{EvalContext, EvalError, Evaluate};
+use crate::{
+    tail_call::{EvaluateTail, Tail, TailCall},
+    EvalContext, EvalError, Evaluate,
+};
 
 impl Evaluate<ValueRef> for FnCall {
     #[cfg_attr(feature = "flame_it", flame("Fncall::"))]
     fn evaluate(&self, context: &mut EvalContext) -> Result<ValueRef, EvalError> {
+        self.evaluate_tail(context)?.resolve(context)
+    }
+}
+
+impl EvaluateTail for FnCall {
+    fn evaluate_tail(&self, context: &mut EvalContext) -> Result<Tail, EvalError> {
         let fn_ident = &self.id;
         let args = &self.args;
         let call_args = args.evaluate(context)?;
-        match context.call_function(fn_ident, &call_args) {
-            Some(val) => Ok(val?),
-            None => match context.lookup_var(&VarIdentifier::new(fn_ident.clone())) {
-                Some(val2) => match &*val2 {
-                    Value::Lambda(lambda_ctx, lambda) => {
-                        context.call_lambda(Rc::clone(lambda_ctx), lambda, &call_args)
-                    }
-                    Value::Symbol(fn_id) => context
-                        .call_function(fn_id, &call_args)
-                        .unwrap_or_else(|| Err(EvalError::FunctionNotDefined(fn_id.clone()))),
-                    _ => Err(EvalError::FunctionNotDefined(fn_ident.clone())),
-                },
-                None => Err(EvalError::FunctionNotDefined(fn_ident.clone())),
+        if let Some(func) = context.lookup_fn(fn_ident) {
+            // unlike functions, the variant values built by constructors can't take more args
+            let arity = func.borrow().max_args();
+            if call_args.len() > arity && context.lookup_constructor_fn(fn_ident).is_some() {
+                return Err(EvalError::ConstructorArityMismatch(
+                    fn_ident.clone(),
+                    arity,
+                    call_args.len(),
+                ));
+            }
+            return Ok(Tail::Call(TailCall::Fn(context.clone(), func, call_args)));
+        }
+        match context.lookup_var(&VarIdentifier::new(fn_ident.clone())) {
+            Some(val2) => match &*val2 {
+                Value::Lambda(..) | Value::FnRef(..) | Value::Partial(..) | Value::Symbol(..) => {
+                    context.tail_call_value(&val2, call_args)
+                }
+                _ => Err(EvalError::FunctionNotDefined(fn_ident.clone())),
             },
+            None if context.lookup_constructor(fn_ident).is_some() => Err(
+                EvalError::ConstructorArityMismatch(fn_ident.clone(), 0, call_args.len()),
+            ),
+            None if context.lookup_macro(fn_ident).is_some() => {
+                Err(EvalError::MacroNotExpanded(fn_ident.clone()))
+            }
+            None => {
+                Err(context.private_or(fn_ident, EvalError::FunctionNotDefined(fn_ident.clone())))
+            }
         }
     }
 }
@@ -1,11 +1,5 @@
-use std::rc::Rc;
-
 use crate::{EvalContext, EvalError, Evaluate};
-use rogato_common::{
-    ast::fn_def::{FnDef, FnDefVariant},
-    val,
-    val::ValueRef,
-};
+use rogato_common::{ast::fn_def::FnDef, val, val::ValueRef};
 
 #[cfg(feature = "flame_it")]
 use flamer::flame;
@@ -13,9 +7,8 @@ use flamer::flame;
 impl Evaluate<ValueRef> for FnDef {
     #[cfg_attr(feature = "flame_it", flame("FnDef::"))]
     fn evaluate(&self, context: &mut EvalContext) -> Result<ValueRef, EvalError> {
-        for FnDefVariant(args, body) in self.variants_iter() {
-            let fn_def_variant: FnDefVariant = FnDefVariant(args.clone(), Rc::clone(body));
-            context.define_fn(self.id(), fn_def_variant);
+        for fn_def_variant in self.variants_iter() {
+            context.define_fn(self.id(), fn_def_variant.clone());
         }
         Ok(val::symbol(self.id().clone()))
     }
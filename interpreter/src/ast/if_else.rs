@@ -1,4 +1,7 @@
-use crate::{EvalContext, EvalError, Evaluate};
+use crate::{
+    tail_call::{EvaluateTail, Tail},
+    EvalContext, EvalError, Evaluate,
+};
 use rogato_common::ast::if_else::IfElse;
 use rogato_common::val::{Value, ValueRef};
 
@@ -16,3 +19,14 @@ impl Evaluate<ValueRef> for IfElse {
         }
     }
 }
+
+impl EvaluateTail for IfElse {
+    fn evaluate_tail(&self, context: &mut EvalContext) -> Result<Tail, EvalError> {
+        let val = self.condition.evaluate(context)?;
+        match *val {
+            Value::Bool(true) => self.then_expr.evaluate_tail(context),
+            Value::Bool(false) => self.else_expr.evaluate_tail(context),
+            _ => Err(EvalError::IFElseConditionNotBool(ValueRef::clone(&val))),
+        }
+    }
+}
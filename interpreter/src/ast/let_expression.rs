@@ -1,8 +1,9 @@
-use std::rc::Rc;
-
-use crate::{EvalContext, EvalError, Evaluate};
+use crate::{
+    tail_call::{EvaluateTail, Tail},
+    EvalContext, EvalError, Evaluate,
+};
 use rogato_common::{
-    ast::{expression::Expression, fn_def::FnDefVariant, let_expression::LetExpression},
+    ast::{expression::Expression, let_expression::LetExpression},
     val::ValueRef,
 };
 
@@ -12,24 +13,36 @@ use flamer::flame;
 impl Evaluate<ValueRef> for LetExpression {
     #[cfg_attr(feature = "flame_it", flame("LetExpression::"))]
     fn evaluate(&self, context: &mut EvalContext) -> Result<ValueRef, EvalError> {
-        let mut context = context.with_child_env();
+        let mut context = bind(self, context)?;
+        self.body.evaluate(&mut context)
+    }
+}
+
+impl EvaluateTail for LetExpression {
+    fn evaluate_tail(&self, context: &mut EvalContext) -> Result<Tail, EvalError> {
+        let mut context = bind(self, context)?;
+        self.body.evaluate_tail(&mut context)
+    }
+}
+
+/// A child context with the let expression's bindings defined in it.
+fn bind(let_expr: &LetExpression, context: &EvalContext) -> Result<EvalContext, EvalError> {
+    let mut context = context.with_child_env();
 
-        for (id, expr) in self.bindings.iter() {
-            match &**expr {
-                Expression::InlineFnDef(fn_def) => {
-                    let fn_def = fn_def.borrow();
-                    for variant in fn_def.variants_iter() {
-                        let fn_variant = FnDefVariant(variant.0.clone(), Rc::clone(&variant.1));
-                        context.define_fn(fn_def.id(), fn_variant);
-                    }
+    for (id, expr) in let_expr.bindings.iter() {
+        match &**expr {
+            Expression::InlineFnDef(fn_def) => {
+                let fn_def = fn_def.borrow();
+                for variant in fn_def.variants_iter() {
+                    context.define_fn(fn_def.id(), variant.clone());
                 }
-                _ => match expr.evaluate(&mut context) {
-                    Ok(val) => context.define_var(id, val),
-                    Err(e) => return Err(e),
-                },
             }
+            _ => match expr.evaluate(&mut context) {
+                Ok(val) => context.define_var(id, val),
+                Err(e) => return Err(e),
+            },
         }
-
-        self.body.evaluate(&mut context)
     }
+
+    Ok(context)
 }
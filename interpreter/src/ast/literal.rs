@@ -1,8 +1,13 @@
-use std::fmt::Display;
+use std::{fmt::Display, rc::Rc};
 
+use super::type_expression::value_has_type;
 use crate::{EvalContext, EvalError, Evaluate};
 use rogato_common::{
-    ast::literal::{Literal, TupleItems},
+    ast::{
+        literal::{Literal, TupleItems},
+        type_expression::{StructTypeProperties, TypeDef, TypeExpression},
+        Identifier, Node,
+    },
     val::{self, Value, ValueRef},
 };
 
@@ -16,6 +21,7 @@ impl Evaluate<ValueRef> for Literal {
             Literal::Bool(boolean) => Ok(val::bool(*boolean)),
             Literal::Number(number) => Ok(val::number(*number)),
             Literal::String(string) => Ok(val::string(string)),
+            Literal::InterpolatedString(string) => string.desugar().evaluate(context),
             Literal::Tuple(items) => {
                 let mut values = Vec::with_capacity(items.len());
                 for item in items.iter() {
@@ -38,12 +44,56 @@ impl Evaluate<ValueRef> for Literal {
                     _ => Err(EvalError::ListConsInvalidList(rest)),
                 }
             }
-            Literal::Struct(_struct_id, props) => {
+            Literal::Struct(struct_id, props) => {
+                let (type_id, type_def, prop_types) = struct_type(context, struct_id)?;
+
                 let mut prop_values = Vec::with_capacity(props.len());
                 for (id, expr) in props.iter() {
-                    prop_values.push((id.clone(), expr.evaluate(context)?))
+                    let value = expr.evaluate(context)?;
+                    check_struct_prop(context, &type_def, &prop_types, id, &value)?;
+                    prop_values.push((id.clone(), value))
+                }
+
+                for (prop, _) in prop_types.iter() {
+                    if !props.iter().any(|(id, _)| id == prop) {
+                        return Err(EvalError::StructPropertyMissing(
+                            type_def.id(),
+                            prop.clone(),
+                        ));
+                    }
+                }
+
+                Ok(val::struct_val(type_id, prop_values))
+            }
+            Literal::StructUpdate(base, props) => {
+                let base = base.evaluate(context)?;
+                let mut prop_values = Vec::with_capacity(props.len());
+                for (id, expr) in props.iter() {
+                    prop_values.push((id.to_string(), expr.evaluate(context)?))
+                }
+
+                match &*base {
+                    Value::Object(object) => Ok(object.update(prop_values).into()),
+                    Value::Struct(struct_id, object) => {
+                        // looked up by its qualified id, as another type may have the same name here
+                        let (_, type_def, prop_types) = struct_type(context, struct_id)?;
+                        let type_ctx = type_module_context(context, struct_id);
+                        for (id, value) in prop_values.iter() {
+                            check_struct_prop(
+                                &type_ctx,
+                                &type_def,
+                                &prop_types,
+                                &id.into(),
+                                value,
+                            )?;
+                        }
+                        Ok(ValueRef::new(Value::Struct(
+                            struct_id.clone(),
+                            object.update(prop_values),
+                        )))
+                    }
+                    _ => Err(EvalError::StructUpdateInvalidValue(base)),
                 }
-                Ok(val::object(prop_values))
             }
             Literal::Map(kv_pairs) => {
                 let mut pairs: Vec<(ValueRef, ValueRef)> = Vec::with_capacity(kv_pairs.len());
@@ -77,6 +127,61 @@ impl Evaluate<ValueRef> for Literal {
     }
 }
 
+/// Looks up the struct type with the given id along with its fully qualified id
+/// and its property types.
+fn struct_type(
+    context: &EvalContext,
+    struct_id: &Identifier,
+) -> Result<(Identifier, Rc<TypeDef>, StructTypeProperties), EvalError> {
+    let (type_id, type_def) = context.lookup_qualified_type(struct_id).ok_or_else(|| {
+        context.private_or(struct_id, EvalError::StructTypeNotFound(struct_id.clone()))
+    })?;
+    let prop_types = struct_prop_types(&type_def, struct_id)?;
+    Ok((type_id, type_def, prop_types))
+}
+
+fn struct_prop_types(
+    type_def: &TypeDef,
+    struct_id: &Identifier,
+) -> Result<StructTypeProperties, EvalError> {
+    match &*type_def.type_expr() {
+        TypeExpression::StructType(prop_types) => Ok(prop_types.clone()),
+        _ => Err(EvalError::NotAStructType(struct_id.clone())),
+    }
+}
+
+/// A child context within the module defining the struct type with the given qualified id,
+/// so its property types refer to the same types as in its definition.
+fn type_module_context(context: &EvalContext, qualified_id: &Identifier) -> EvalContext {
+    let mut type_ctx = context.with_child_env();
+    if let Some((module_id, _)) = qualified_id.rsplit_once('.') {
+        type_ctx.set_current_module(module_id.into());
+    }
+    type_ctx
+}
+
+/// Checks that the struct type has a property with the given id and that the value has its type.
+fn check_struct_prop(
+    context: &EvalContext,
+    type_def: &TypeDef,
+    prop_types: &StructTypeProperties,
+    id: &Identifier,
+    value: &ValueRef,
+) -> Result<(), EvalError> {
+    match prop_types.iter().find(|(prop, _)| prop == id) {
+        Some((_, prop_type)) if !value_has_type(context, value, prop_type)? => {
+            Err(EvalError::StructPropertyTypeMismatch(
+                type_def.id(),
+                id.clone(),
+                Node::clone(prop_type),
+                ValueRef::clone(value),
+            ))
+        }
+        Some(_) => Ok(()),
+        None => Err(EvalError::StructPropertyUnknown(type_def.id(), id.clone())),
+    }
+}
+
 impl<T: Evaluate<ValueRef> + Display> Evaluate<ValueRef> for TupleItems<T> {
     fn evaluate(&self, context: &mut EvalContext) -> Result<ValueRef, EvalError> {
         let mut values = Vec::with_capacity(self.len());
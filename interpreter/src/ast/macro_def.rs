@@ -0,0 +1,13 @@
+use crate::{EvalContext, EvalError, Evaluate};
+use rogato_common::{ast::macro_def::MacroDef, val, val::ValueRef};
+
+#[cfg(feature = "flame_it")]
+use flamer::flame;
+
+impl Evaluate<ValueRef> for MacroDef {
+    #[cfg_attr(feature = "flame_it", flame("MacroDef::"))]
+    fn evaluate(&self, context: &mut EvalContext) -> Result<ValueRef, EvalError> {
+        context.define_macro(self);
+        Ok(val::symbol(self.id().clone()))
+    }
+}
@@ -1,13 +1,13 @@
 use rogato_common::{
-    ast::AST,
+    ast::{Node, AST},
     val::{self, ValueRef},
 };
 
-use crate::environment::{ImportedIdentifier, Imports};
-
 use super::{EvalContext, EvalError, Evaluate};
 
+pub mod case;
 pub mod expression;
+pub mod fixity_def;
 pub mod fn_call;
 pub mod fn_def;
 pub mod identifier;
@@ -15,14 +15,24 @@ pub mod if_else;
 pub mod lambda;
 pub mod let_expression;
 pub mod literal;
+pub mod macro_def;
 pub mod module_def;
 pub mod program;
 pub mod query;
 pub mod type_expression;
+pub mod use_def;
 
 #[cfg(feature = "flame_it")]
 use flamer::flame;
 
+impl Evaluate<ValueRef> for Node<AST> {
+    fn evaluate(&self, context: &mut EvalContext) -> Result<ValueRef, EvalError> {
+        (**self)
+            .evaluate(context)
+            .map_err(|e| e.with_span(self.span()))
+    }
+}
+
 impl Evaluate<ValueRef> for AST {
     #[cfg_attr(feature = "flame_it", flame("AST::"))]
     fn evaluate(&self, context: &mut EvalContext) -> Result<ValueRef, EvalError> {
@@ -30,16 +40,10 @@ impl Evaluate<ValueRef> for AST {
             AST::RootComment(_) => Ok(val::none()),
             AST::FnDef(fn_def) => fn_def.borrow().evaluate(context),
             AST::ModuleDef(mod_def) => mod_def.evaluate(context),
-            AST::Use(id, imports) => {
-                let imports = Imports::Specific(
-                    imports
-                        .iter()
-                        .map(|imp| ImportedIdentifier::Func(imp.clone()))
-                        .collect(),
-                );
-                context.import(id, imports)
-            }
+            AST::Use(use_def) => use_def.evaluate(context),
             AST::TypeDef(type_def) => type_def.evaluate(context),
+            AST::FixityDef(fixity_def) => fixity_def.evaluate(context),
+            AST::MacroDef(macro_def) => macro_def.evaluate(context),
         }
     }
 }
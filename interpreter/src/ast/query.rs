@@ -1,14 +1,17 @@
-use std::{borrow::Borrow, rc::Rc};
+use std::borrow::Borrow;
 
 use rogato_common::{
     ast::{
         expression::Expression,
         query::{Query, QueryGuards},
+        Node,
     },
     val::Value,
 };
 
-use crate::{query_planner::QueryError, EvalContext, EvalError, Evaluate, ValueRef};
+use crate::{
+    call_stack::CallFrame, query_planner::QueryError, EvalContext, EvalError, Evaluate, ValueRef,
+};
 
 #[cfg(feature = "flame_it")]
 use flamer::flame;
@@ -18,7 +21,7 @@ impl Evaluate<ValueRef> for Query {
     fn evaluate(&self, context: &mut EvalContext) -> Result<ValueRef, EvalError> {
         match context.schedule_query(self) {
             Ok(val) => Ok(val),
-            Err(e) => Err(EvalError::from(e)),
+            Err(e) => Err(context.with_call_trace(EvalError::from(e))),
         }
     }
 }
@@ -28,18 +31,20 @@ impl Evaluate<Vec<ValueRef>> for QueryGuards {
     fn evaluate(&self, context: &mut EvalContext) -> Result<Vec<ValueRef>, EvalError> {
         let mut results = Vec::with_capacity(self.len());
         for guard_expr in self.iter() {
-            results.push(QueryGuard::new(Rc::clone(guard_expr)).evaluate(context)?)
+            let frame = CallFrame::Query("guard", Node::clone(guard_expr));
+            let guard = QueryGuard::new(Node::clone(guard_expr));
+            results.push(context.in_frame(frame, |ctx| guard.evaluate(ctx))?)
         }
         Ok(results)
     }
 }
 
 struct QueryGuard {
-    guard_expr: Rc<Expression>,
+    guard_expr: Node<Expression>,
 }
 
 impl QueryGuard {
-    pub fn new(guard_expr: Rc<Expression>) -> Self {
+    pub fn new(guard_expr: Node<Expression>) -> Self {
         Self { guard_expr }
     }
 }
@@ -50,12 +55,16 @@ impl Evaluate<ValueRef> for QueryGuard {
         let result_value = self.guard_expr.evaluate(context)?;
 
         match result_value.borrow() {
-            Value::Bool(false) => {
-                Err(QueryError::GuardConditionFalse(Rc::clone(&self.guard_expr)).into())
-            }
-            Value::Option(None) => {
-                Err(QueryError::GuardConditionNone(Rc::clone(&self.guard_expr)).into())
-            }
+            Value::Bool(false) => Err(QueryError::GuardConditionFalse(Node::clone(
+                &self.guard_expr,
+            ))
+            .with_span(self.guard_expr.span())
+            .into()),
+            Value::Option(None) => Err(QueryError::GuardConditionNone(Node::clone(
+                &self.guard_expr,
+            ))
+            .with_span(self.guard_expr.span())
+            .into()),
             _ => Ok(result_value),
         }
     }
@@ -1,6 +1,11 @@
-use crate::{EvalContext, EvalError, Evaluate, ValueRef};
+use std::rc::Rc;
+
+use crate::{EvalContext, EvalError, Evaluate, Value, ValueRef};
 use rogato_common::{
-    ast::type_expression::{StructTypeProperties, TypeDef, TypeExpression},
+    ast::{
+        type_expression::{StructTypeProperties, TypeDef, TypeExpression, UnionTypeVariants},
+        Identifier, Node,
+    },
     val,
 };
 
@@ -10,10 +15,12 @@ use flamer::flame;
 impl Evaluate<ValueRef> for TypeDef {
     #[cfg_attr(feature = "flame_it", flame("TypeDef::"))]
     fn evaluate(&self, context: &mut EvalContext) -> Result<ValueRef, EvalError> {
+        let type_expr = self.type_expr().evaluate(context)?;
+        context.define_type(Rc::new(self.clone()));
         Ok(val::object([
             ("type", val::string("TypeDef")),
             ("name", val::string(self.id().to_string())),
-            ("type_expr", self.type_expr().evaluate(context)?),
+            ("type_expr", type_expr),
         ]))
     }
 }
@@ -50,6 +57,10 @@ impl Evaluate<ValueRef> for TypeExpression {
                 ("type", val::string("StructType")),
                 ("props", prop_types.evaluate(context)?),
             ]),
+            TypeExpression::Union(variants) => val::object([
+                ("type", val::string("Union")),
+                ("variants", variants.evaluate(context)?),
+            ]),
         })
     }
 }
@@ -64,3 +75,107 @@ impl Evaluate<ValueRef> for StructTypeProperties {
         Ok(val::list(vec))
     }
 }
+
+impl Evaluate<ValueRef> for UnionTypeVariants {
+    #[cfg_attr(feature = "flame_it", flame("UnionTypeVariants::"))]
+    fn evaluate(&self, context: &mut EvalContext) -> Result<ValueRef, EvalError> {
+        let mut vec = Vec::with_capacity(self.len());
+        for (id, params) in self.iter() {
+            let mut param_vals = Vec::with_capacity(params.len());
+            for param in params.iter() {
+                param_vals.push(param.evaluate(context)?)
+            }
+            vec.push(val::object([
+                ("name", val::string(id)),
+                ("params", val::list(param_vals)),
+            ]))
+        }
+        Ok(val::list(vec))
+    }
+}
+
+/// Checks if a value is of the given type.
+/// Struct and union types are nominal, i.e. a value only has a struct type if it was constructed
+/// as that struct and a union type if it's one of its variants.
+/// Type variables (lowercase type references like `val` in `Ok val`) match any value,
+/// while references to unknown types are an error.
+pub fn value_has_type(
+    context: &EvalContext,
+    value: &Value,
+    type_expr: &Node<TypeExpression>,
+) -> Result<bool, EvalError> {
+    check_type(context, value, type_expr).map_err(|e| e.with_span(type_expr.span()))
+}
+
+fn check_type(
+    context: &EvalContext,
+    value: &Value,
+    type_expr: &TypeExpression,
+) -> Result<bool, EvalError> {
+    let has_type = match (type_expr, value) {
+        (TypeExpression::BoolType, Value::Bool(_)) => true,
+        (TypeExpression::NumberType, Value::Number(_)) => true,
+        (TypeExpression::StringType, Value::String(_)) => true,
+        (TypeExpression::TypeRef(id), _) => match context.lookup_qualified_type(id) {
+            Some((type_id, type_def)) => match (&*type_def.type_expr(), value) {
+                (TypeExpression::StructType(_), Value::Struct(struct_id, _)) => {
+                    *struct_id == type_id
+                }
+                (TypeExpression::StructType(_), _) => false,
+                (TypeExpression::Union(_), Value::Variant(variant_type_id, _, _))
+                    if *variant_type_id != type_id =>
+                {
+                    false
+                }
+                (TypeExpression::TypeRef(ref_id), _) if ref_id == id => true,
+                (_, value) => value_has_type(context, value, &type_def.type_expr())?,
+            },
+            None if is_type_variable(id) => true,
+            None => return Err(EvalError::TypeNotFound(id.clone())),
+        },
+        (
+            TypeExpression::FunctionType(_, _),
+            Value::Lambda(_, _) | Value::FnRef(_) | Value::Partial(_),
+        ) => true,
+        (TypeExpression::TupleType(item_types), Value::Tuple(size, items)) => {
+            if item_types.len() != *size {
+                return Ok(false);
+            }
+            for (item_type, item) in item_types.iter().zip(items.iter()) {
+                if !value_has_type(context, item, item_type)? {
+                    return Ok(false);
+                }
+            }
+            true
+        }
+        (TypeExpression::ListType(item_type), Value::List(items)) => {
+            for item in items.iter() {
+                if !value_has_type(context, item, item_type)? {
+                    return Ok(false);
+                }
+            }
+            true
+        }
+        (
+            TypeExpression::StructType(prop_types),
+            Value::Object(object) | Value::Struct(_, object),
+        ) => {
+            for (prop, prop_type) in prop_types.iter() {
+                match object.get(prop) {
+                    Some(val) if value_has_type(context, val, prop_type)? => {}
+                    _ => return Ok(false),
+                }
+            }
+            true
+        }
+        (TypeExpression::Union(variants), Value::Variant(_, id, args)) => variants
+            .iter()
+            .any(|(variant_id, params)| variant_id == id && params.len() == args.len()),
+        (_, _) => false,
+    };
+    Ok(has_type)
+}
+
+fn is_type_variable(id: &Identifier) -> bool {
+    id.starts_with(|c: char| c.is_lowercase())
+}
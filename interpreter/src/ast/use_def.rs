@@ -0,0 +1,45 @@
+use rogato_common::{
+    ast::use_def::{UseDef, UseImport, UseImports},
+    val::{self, ValueRef},
+};
+
+use crate::{
+    environment::{ImportedIdentifier, Imports},
+    EvalContext, EvalError, Evaluate,
+};
+
+#[cfg(feature = "flame_it")]
+use flamer::flame;
+
+impl Evaluate<ValueRef> for UseDef {
+    #[cfg_attr(feature = "flame_it", flame("UseDef::"))]
+    fn evaluate(&self, context: &mut EvalContext) -> Result<ValueRef, EvalError> {
+        let module = context.load_module(self.id())?;
+
+        if let Some(alias) = self.alias() {
+            context.alias_module(&module, alias);
+        }
+
+        let imports = match self.imports() {
+            UseImports::All => Imports::All,
+            UseImports::Specific(imports) => Imports::Specific(
+                imports
+                    .iter()
+                    .map(|import| match import {
+                        UseImport::Func(id, None) => ImportedIdentifier::Func(id.clone()),
+                        UseImport::Func(id, Some(alias)) => {
+                            ImportedIdentifier::AliasedFunc(id.clone(), alias.clone())
+                        }
+                        UseImport::Type(id, None) => ImportedIdentifier::Type(id.clone()),
+                        UseImport::Type(id, Some(alias)) => {
+                            ImportedIdentifier::AliasedType(id.clone(), alias.clone())
+                        }
+                    })
+                    .collect(),
+            ),
+        };
+
+        context.import(self.id(), imports)?;
+        Ok(val::none())
+    }
+}
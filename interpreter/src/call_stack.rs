@@ -0,0 +1,147 @@
+use std::{fmt::Display, rc::Rc};
+
+use rogato_common::{
+    ast::{expression::Expression, lambda::Lambda, Identifier, Node},
+    val::ValueRef,
+};
+
+/// Max. number of characters shown per argument in a call frame.
+const MAX_ARG_SUMMARY_LEN: usize = 40;
+
+/// Max. number of arguments kept per call frame.
+const MAX_FRAME_ARGS: usize = 4;
+
+/// A single function, lambda or query stage invocation along with its arguments.
+/// Arguments are only summarized when displayed, to keep pushing frames cheap.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CallFrame {
+    Fn(Identifier, CallArgs),
+    NativeFn(Identifier, CallArgs),
+    Lambda(Rc<Lambda>, CallArgs),
+    Query(&'static str, Node<Expression>),
+}
+
+/// The first few arguments of a call, along with the total number of arguments.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CallArgs {
+    args: Vec<ValueRef>,
+    count: usize,
+}
+
+impl CallArgs {
+    pub fn new(args: &[ValueRef]) -> Self {
+        CallArgs {
+            args: args.iter().take(MAX_FRAME_ARGS).cloned().collect(),
+            count: args.len(),
+        }
+    }
+}
+
+impl Display for CallArgs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for arg in self.args.iter() {
+            f.write_fmt(format_args!(" {}", summarize(arg)))?;
+        }
+        if self.count > self.args.len() {
+            f.write_fmt(format_args!(" … ({} more)", self.count - self.args.len()))?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for CallFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CallFrame::Fn(id, args) => f.write_fmt(format_args!("fn {id}{args}")),
+            CallFrame::NativeFn(id, args) => f.write_fmt(format_args!("native fn {id}{args}")),
+            CallFrame::Lambda(lambda, args) => {
+                f.write_fmt(format_args!("lambda {}{args}", summarize(lambda)))
+            }
+            CallFrame::Query(stage, expr) => {
+                f.write_fmt(format_args!("query {stage} {}", summarize(expr)))
+            }
+        }
+    }
+}
+
+/// Shortens a value's display to a single line of limited length.
+fn summarize<D: Display>(value: D) -> String {
+    let display = value.to_string();
+    let single_line = display.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if single_line.chars().count() > MAX_ARG_SUMMARY_LEN {
+        let short: String = single_line.chars().take(MAX_ARG_SUMMARY_LEN).collect();
+        format!("{short}…")
+    } else {
+        single_line
+    }
+}
+
+/// The frames that were active when an error occurred, most recent call first.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CallTrace {
+    frames: Vec<CallFrame>,
+}
+
+impl CallTrace {
+    pub fn frames(&self) -> std::slice::Iter<'_, CallFrame> {
+        self.frames.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+impl Display for CallTrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Call stack (most recent call first):")?;
+        for frame in self.frames.iter() {
+            f.write_fmt(format_args!("\n    at {frame}"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Keeps track of the currently active call frames.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct CallStack {
+    frames: Vec<CallFrame>,
+}
+
+impl CallStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, frame: CallFrame) {
+        self.frames.push(frame);
+    }
+
+    pub fn pop(&mut self) {
+        self.frames.pop();
+    }
+
+    /// Replaces the most recent frame, for a tail call taking over the frame of its caller.
+    pub fn replace(&mut self, frame: CallFrame) {
+        self.frames.pop();
+        self.frames.push(frame);
+    }
+
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// The currently active frames, unless there are none.
+    pub fn trace(&self) -> Option<CallTrace> {
+        if self.frames.is_empty() {
+            return None;
+        }
+        Some(CallTrace {
+            frames: self.frames.iter().rev().cloned().collect(),
+        })
+    }
+}
@@ -44,7 +44,7 @@ impl Imports {
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct ImportedModules {
-    imports: HashMap<Identifier, Imports>,
+    imports: HashMap<Identifier, Vec<Imports>>,
 }
 
 impl ImportedModules {
@@ -54,21 +54,51 @@ impl ImportedModules {
         }
     }
 
-    pub fn iter(&self) -> std::collections::hash_map::Iter<'_, Identifier, Imports> {
-        self.imports.iter()
+    /// Iterates over all imports along with the id of the module they're imported from.
+    /// A module imported in full can additionally have specific (aliased) imports.
+    pub fn iter(&self) -> impl Iterator<Item = (&Identifier, &Imports)> {
+        self.imports
+            .iter()
+            .flat_map(|(module_id, imports)| imports.iter().map(move |i| (module_id, i)))
     }
 
+    /// Adds the given imports to the ones already imported from the module.
     pub fn import(&mut self, module: &Module, imports: Imports) -> &mut Self {
-        self.imports.insert(module.id(), imports);
+        let module_imports = self.imports.entry(module.id()).or_default();
+        match imports {
+            Imports::All => {
+                if !module_imports.contains(&Imports::All) {
+                    module_imports.push(Imports::All)
+                }
+            }
+            Imports::Specific(new_ids) => {
+                let imported_ids = module_imports.iter_mut().find_map(|i| match i {
+                    Imports::Specific(imported_ids) => Some(imported_ids),
+                    Imports::All => None,
+                });
+                match imported_ids {
+                    Some(imported_ids) => {
+                        for new_id in new_ids {
+                            if !imported_ids.contains(&new_id) {
+                                imported_ids.push(new_id);
+                            }
+                        }
+                    }
+                    None => module_imports.push(Imports::Specific(new_ids)),
+                }
+            }
+        }
         self
     }
 }
 
 impl FromIterator<(Identifier, Imports)> for ImportedModules {
     fn from_iter<T: IntoIterator<Item = (Identifier, Imports)>>(iter: T) -> Self {
-        ImportedModules {
-            imports: HashMap::from_iter(iter),
+        let mut imports: HashMap<Identifier, Vec<Imports>> = HashMap::new();
+        for (module_id, module_imports) in iter {
+            imports.entry(module_id).or_default().push(module_imports);
         }
+        ImportedModules { imports }
     }
 }
 
@@ -181,6 +211,82 @@ impl Environment {
         }
     }
 
+    /// A child of the given ancestor holding a copy of all variables visible from this environment
+    /// that are defined below the ancestor, with the current module and imports of this environment.
+    /// Used to keep chains of environments from growing with each tail call.
+    pub fn collapsed_into(&self, ancestor: &Environment) -> Environment {
+        let mut variables = HashMap::new();
+        let mut env = Some(self.clone());
+        while let Some(current) = env {
+            if Rc::ptr_eq(&current.state, &ancestor.state) {
+                break;
+            }
+            let state = current.state.borrow();
+            for (id, val) in state.variables.iter() {
+                variables
+                    .entry(id.clone())
+                    .or_insert_with(|| ValueRef::clone(val));
+            }
+            env = state.parent.clone();
+        }
+
+        let curr_state = self.state.borrow();
+        let state = State {
+            parent: Some(ancestor.clone()),
+            variables,
+            modules: Rc::clone(&curr_state.modules),
+            imported_modules: curr_state.imported_modules.clone(),
+            aliased_modules: HashMap::new(),
+            current_module_name: curr_state.current_module_name.clone(),
+        };
+        Environment {
+            state: Rc::new(RefCell::new(state)),
+        }
+    }
+
+    /// A root environment holding a copy of all variables, module aliases and modules visible
+    /// from this environment, with its current module and imports.
+    /// Definitions made within it don't affect this environment or any of its modules.
+    pub fn isolated(&self) -> Environment {
+        let mut variables = HashMap::new();
+        let mut aliased_modules = HashMap::new();
+        let mut env = Some(self.clone());
+        while let Some(current) = env {
+            let state = current.state.borrow();
+            for (id, val) in state.variables.iter() {
+                variables
+                    .entry(id.clone())
+                    .or_insert_with(|| ValueRef::clone(val));
+            }
+            for (alias, module_id) in state.aliased_modules.iter() {
+                aliased_modules
+                    .entry(alias.clone())
+                    .or_insert_with(|| module_id.clone());
+            }
+            env = state.parent.clone();
+        }
+
+        let curr_state = self.state.borrow();
+        let mut fn_copies = HashMap::new();
+        let modules = curr_state
+            .modules
+            .borrow()
+            .iter()
+            .map(|(id, module)| (id.clone(), module.copy(&mut fn_copies)))
+            .collect();
+        let state = State {
+            parent: None,
+            variables,
+            modules: Rc::new(RefCell::new(modules)),
+            imported_modules: curr_state.imported_modules.clone(),
+            aliased_modules,
+            current_module_name: curr_state.current_module_name.clone(),
+        };
+        Environment {
+            state: Rc::new(RefCell::new(state)),
+        }
+    }
+
     pub fn imported_modules(&self) -> ImportedModules {
         self.state.borrow().imported_modules.clone()
     }
@@ -229,6 +335,17 @@ impl Environment {
         state.modules.borrow_mut().insert(id, module);
     }
 
+    pub fn remove_module(&mut self, id: &Identifier) {
+        let state = self.state.borrow_mut();
+        state.modules.borrow_mut().remove(id);
+    }
+
+    pub fn module_ids(&self) -> Vec<Identifier> {
+        let state = self.state.borrow();
+        let modules = state.modules.borrow();
+        modules.keys().cloned().collect()
+    }
+
     pub fn current_module(&self) -> Module {
         let state = self.state.borrow();
         self.lookup_module(&state.current_module_name)
@@ -277,12 +394,13 @@ impl Environment {
         if let Some((module_id, fn_id)) = self.qualified_lookup(id) {
             return self
                 .lookup_module(&module_id)
+                .filter(|m| self.is_visible(m, &fn_id))
                 .and_then(|m| m.lookup_const(&fn_id));
         }
 
         match self
-            .lookup_module_for_const(id)
-            .and_then(|m| m.lookup_const(id))
+            .lookup_module_for(Definition::Const, id)
+            .and_then(|(m, local_id)| m.lookup_const(&local_id))
         {
             Some(val) => Some(val),
             None => {
@@ -297,21 +415,29 @@ impl Environment {
 
     #[cfg_attr(feature = "flame_it", flame)]
     pub fn lookup_type(&self, id: &Identifier) -> Option<Rc<TypeDef>> {
-        if let Some((module_id, fn_id)) = self.qualified_lookup(id) {
+        self.lookup_qualified_type(id).map(|(_, type_def)| type_def)
+    }
+
+    /// Looks up a (possibly qualified) type id like `lookup_type`, returning the type along with
+    /// its fully qualified id, e.g. `Foo.Bar.Pt` for a type `Pt` defined in module `Foo.Bar`.
+    #[cfg_attr(feature = "flame_it", flame)]
+    pub fn lookup_qualified_type(&self, id: &Identifier) -> Option<(Identifier, Rc<TypeDef>)> {
+        if let Some((module_id, type_id)) = self.qualified_lookup(id) {
             return self
                 .lookup_module(&module_id)
-                .and_then(|m| m.lookup_type(&fn_id));
+                .filter(|m| self.is_visible(m, &type_id))
+                .and_then(|m| Some((m.qualified_id(&type_id), m.lookup_type(&type_id)?)));
         }
 
         match self
-            .lookup_module_for_type(id)
-            .and_then(|m| m.lookup_type(id))
+            .lookup_module_for(Definition::Type, id)
+            .and_then(|(m, local_id)| Some((m.qualified_id(&local_id), m.lookup_type(&local_id)?)))
         {
             Some(type_) => Some(type_),
             None => {
                 let state = self.state.borrow();
                 match &state.parent {
-                    Some(parent_env) => parent_env.lookup_type(id),
+                    Some(parent_env) => parent_env.lookup_qualified_type(id),
                     None => None,
                 }
             }
@@ -323,10 +449,14 @@ impl Environment {
         if let Some((module_id, fn_id)) = self.qualified_lookup(id) {
             return self
                 .lookup_module(&module_id)
+                .filter(|m| self.is_visible(m, &fn_id))
                 .and_then(|m| m.lookup_fn(&fn_id));
         }
 
-        match self.lookup_module_for_fn(id).and_then(|m| m.lookup_fn(id)) {
+        match self
+            .lookup_module_for(Definition::Fn, id)
+            .and_then(|(m, local_id)| m.lookup_fn(&local_id))
+        {
             Some(fn_def) => Some(fn_def),
             None => {
                 let state = self.state.borrow();
@@ -338,135 +468,187 @@ impl Environment {
         }
     }
 
-    fn qualified_lookup(&self, id: &Identifier) -> Option<(Identifier, Identifier)> {
-        let parts: Vec<&str> = id.split('.').collect();
-        match parts.len() {
-            0 => None,
-            1 => None,
-            len => {
-                let (module_id, fn_id) = parts.split_at(len - 1);
-                let mid: Identifier = module_id.join(".").into();
-                let fid: Identifier = fn_id.join("").into();
-                Some((mid, fid))
-            }
+    /// Returns the id of the union type the given constructor belongs to,
+    /// along with the constructor's id within its module.
+    #[cfg_attr(feature = "flame_it", flame)]
+    pub fn lookup_constructor(&self, id: &Identifier) -> Option<(Identifier, Identifier)> {
+        if let Some((module_id, local_id)) = self.qualified_lookup(id) {
+            return self
+                .lookup_module(&module_id)
+                .filter(|m| self.is_visible(m, &local_id))
+                .and_then(|m| m.lookup_constructor(&local_id))
+                .map(|type_id| (type_id, local_id));
         }
-    }
 
-    #[cfg_attr(feature = "flame_it", flame)]
-    pub fn lookup_module_for_fn(&self, id: &Identifier) -> Option<Module> {
-        let curr_mod = self.current_module();
-        match curr_mod.lookup_fn(id) {
-            Some(_) => Some(curr_mod),
+        match self
+            .lookup_module_for(Definition::Constructor, id)
+            .and_then(|(m, local_id)| m.lookup_constructor(&local_id).zip(Some(local_id)))
+        {
+            Some(constructor) => Some(constructor),
             None => {
                 let state = self.state.borrow();
-                for (module_id, imports) in state.imported_modules.iter() {
-                    match imports {
-                        Imports::All => match self.lookup_module(module_id) {
-                            Some(module) => match module.lookup_fn(id) {
-                                Some(_) => return Some(module),
-                                None => continue,
-                            },
-                            None => continue,
-                        },
-                        Imports::Specific(imported_ids) => {
-                            for iid in imported_ids.iter() {
-                                if import_id_matches(iid, id) {
-                                    let module =
-                                        self.lookup_module(module_id).unwrap_or_else(|| {
-                                            panic!("module should exist: {module_id} for fn: {id}")
-                                        });
-                                    return Some(module);
-                                }
-                            }
-                        }
-                    }
+                match &state.parent {
+                    Some(parent_env) => parent_env.lookup_constructor(id),
+                    None => None,
                 }
-
-                None
             }
         }
     }
 
     #[cfg_attr(feature = "flame_it", flame)]
-    pub fn lookup_module_for_type(&self, id: &Identifier) -> Option<Module> {
-        let curr_mod = self.current_module();
-        match curr_mod.lookup_type(id) {
-            Some(_) => Some(curr_mod),
+    pub fn lookup_macro(&self, id: &Identifier) -> Option<Rc<RefCell<FnDef>>> {
+        if let Some((module_id, macro_id)) = self.qualified_lookup(id) {
+            return self
+                .lookup_module(&module_id)
+                .filter(|m| self.is_visible(m, &macro_id))
+                .and_then(|m| m.lookup_macro(&macro_id));
+        }
+
+        match self
+            .lookup_module_for(Definition::Macro, id)
+            .and_then(|(m, local_id)| m.lookup_macro(&local_id))
+        {
+            Some(macro_def) => Some(macro_def),
             None => {
                 let state = self.state.borrow();
-                for (module_id, imports) in state.imported_modules.iter() {
-                    match imports {
-                        Imports::All => match self.lookup_module(module_id) {
-                            Some(module) => match module.lookup_type(id) {
-                                Some(_) => return Some(module),
-                                None => continue,
-                            },
-                            None => continue,
-                        },
-                        Imports::Specific(imported_ids) => {
-                            for iid in imported_ids.iter() {
-                                if import_id_matches(iid, id) {
-                                    let module =
-                                        self.lookup_module(module_id).unwrap_or_else(|| {
-                                            panic!(
-                                                "module should exist: {module_id} for type: {id}"
-                                            )
-                                        });
-                                    return Some(module);
-                                }
-                            }
+                match &state.parent {
+                    Some(parent_env) => parent_env.lookup_macro(id),
+                    None => None,
+                }
+            }
+        }
+    }
+
+    /// Definitions are visible from within their own module, and everywhere else if exported.
+    fn is_visible(&self, module: &Module, id: &Identifier) -> bool {
+        module.id() == self.state.borrow().current_module_name || module.is_exported(id)
+    }
+
+    /// Returns the id of the defining module along with the module-local id,
+    /// if the given id refers to a private definition that isn't visible from the current module.
+    pub fn lookup_private(&self, id: &Identifier) -> Option<(Identifier, Identifier)> {
+        if let Some((module_id, local_id)) = self.qualified_lookup(id) {
+            return self
+                .lookup_module(&module_id)
+                .filter(|m| m.has_definition(&local_id) && !self.is_visible(m, &local_id))
+                .map(|m| (m.id(), local_id));
+        }
+
+        let state = self.state.borrow();
+        for (module_id, imports) in state.imported_modules.iter() {
+            for kind in [Definition::Fn, Definition::Type] {
+                if let Some(local_id) = imports.local_id(kind, id) {
+                    match self.lookup_module(module_id) {
+                        Some(m)
+                            if m.has_definition(&local_id) && !self.is_visible(&m, &local_id) =>
+                        {
+                            return Some((m.id(), local_id))
                         }
+                        _ => continue,
                     }
                 }
+            }
+        }
+
+        match &state.parent {
+            Some(parent_env) => parent_env.lookup_private(id),
+            None => None,
+        }
+    }
 
-                None
+    fn qualified_lookup(&self, id: &Identifier) -> Option<(Identifier, Identifier)> {
+        let parts: Vec<&str> = id.split('.').collect();
+        match parts.len() {
+            0 => None,
+            1 => None,
+            len => {
+                let (module_id, fn_id) = parts.split_at(len - 1);
+                let mid: Identifier = module_id.join(".").into();
+                let fid: Identifier = fn_id.join("").into();
+                Some((mid, fid))
             }
         }
     }
 
+    /// Finds the module an unqualified id refers to, either the current module or an imported one.
+    /// Returns it along with the id of the definition within that module,
+    /// which differs from the given id for aliased imports.
     #[cfg_attr(feature = "flame_it", flame)]
-    pub fn lookup_module_for_const(&self, id: &Identifier) -> Option<Module> {
+    fn lookup_module_for(&self, kind: Definition, id: &Identifier) -> Option<(Module, Identifier)> {
         let curr_mod = self.current_module();
-        match curr_mod.lookup_type(id) {
-            Some(_) => Some(curr_mod),
-            None => {
-                let state = self.state.borrow();
-                for (module_id, imports) in state.imported_modules.iter() {
-                    match imports {
-                        Imports::All => match self.lookup_module(module_id) {
-                            Some(module) => match module.lookup_const(id) {
-                                Some(_) => return Some(module),
-                                None => continue,
-                            },
-                            None => continue,
-                        },
-                        Imports::Specific(imported_ids) => {
-                            for iid in imported_ids.iter() {
-                                if import_id_matches(iid, id) {
-                                    let module =
-                                        self.lookup_module(module_id).unwrap_or_else(|| {
-                                            panic!(
-                                                "module should exist: {module_id} for const: {id}"
-                                            )
-                                        });
-                                    return Some(module);
-                                }
-                            }
-                        }
-                    }
-                }
+        if kind.is_defined_in(&curr_mod, id) {
+            return Some((curr_mod, id.clone()));
+        }
 
-                None
+        let state = self.state.borrow();
+        for (module_id, imports) in state.imported_modules.iter() {
+            let local_id = match imports.local_id(kind, id) {
+                Some(local_id) => local_id,
+                None => continue,
+            };
+            match self.lookup_module(module_id) {
+                Some(module)
+                    if kind.is_defined_in(&module, &local_id)
+                        && self.is_visible(&module, &local_id) =>
+                {
+                    return Some((module, local_id))
+                }
+                _ => continue,
             }
         }
+
+        None
+    }
+}
+
+/// The kinds of definitions that can be looked up in and imported from a module.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Definition {
+    Fn,
+    Macro,
+    Type,
+    Const,
+    Constructor,
+}
+
+impl Definition {
+    fn is_defined_in(self, module: &Module, id: &Identifier) -> bool {
+        match self {
+            Definition::Fn => module.lookup_fn(id).is_some(),
+            Definition::Macro => module.lookup_macro(id).is_some(),
+            Definition::Type => module.lookup_type(id).is_some(),
+            Definition::Const => module.lookup_const(id).is_some(),
+            Definition::Constructor => module.lookup_constructor(id).is_some(),
+        }
     }
 }
 
-fn import_id_matches(imported_id: &ImportedIdentifier, id: &Identifier) -> bool {
-    match imported_id {
-        ImportedIdentifier::Func(func_id) => func_id == id,
-        ImportedIdentifier::Type(type_id) => type_id == id,
-        ImportedIdentifier::AliasedFunc(_import_id, alias_id) => alias_id == id,
-        ImportedIdentifier::AliasedType(_import_id, alias_id) => alias_id == id,
+impl Imports {
+    /// The id within the imported module that the given id refers to, if it's imported.
+    /// Types are imported via `Type` imports, functions, macros and constants via all others.
+    fn local_id(&self, kind: Definition, id: &Identifier) -> Option<Identifier> {
+        let imported_ids = match self {
+            Imports::All => return Some(id.clone()),
+            Imports::Specific(imported_ids) => imported_ids,
+        };
+
+        imported_ids
+            .iter()
+            .find_map(|imported_id| match (kind, imported_id) {
+                (Definition::Type, ImportedIdentifier::Type(type_id)) if type_id == id => {
+                    Some(type_id.clone())
+                }
+                (Definition::Type, ImportedIdentifier::AliasedType(type_id, alias))
+                    if alias == id =>
+                {
+                    Some(type_id.clone())
+                }
+                (Definition::Type, _) => None,
+                (_, ImportedIdentifier::Func(func_id)) if func_id == id => Some(func_id.clone()),
+                (_, ImportedIdentifier::AliasedFunc(func_id, alias)) if alias == id => {
+                    Some(func_id.clone())
+                }
+                (_, _) => None,
+            })
     }
 }
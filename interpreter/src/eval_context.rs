@@ -1,27 +1,39 @@
-use super::{environment::Environment, module::Module, EvalError, ValueRef};
+use super::{environment::Environment, module::Module, EvalError, Value, ValueRef};
 use crate::{
+    call_stack::{CallArgs, CallFrame, CallStack},
     environment::Imports,
     lib_std,
+    macro_expander::MacroExpander,
+    module_loader::ModuleLoader,
     pattern_matching::{PatternMatch, PatternMatching, PatternMatchingError},
     query_planner::{QueryPlanner, QueryResult},
+    tail_call::{EvaluateTail, Tail, TailCall},
     Evaluate,
 };
 use rogato_common::{
     ast::{
         expression::Expression,
+        fixity::FixityDef,
         fn_def::{FnDefBody, FnDefVariant},
-        lambda::{Lambda, LambdaClosureContext, LambdaClosureEvalError},
-        Identifier, VarIdentifier,
+        lambda::{Lambda, LambdaClosureContext, LambdaClosureEvalError, LambdaVariant},
+        macro_def::MacroDef,
+        Identifier, Node, Program, VarIdentifier, AST,
     },
+    error::BoxedError,
     flame_guard,
     native_fn::{NativeFnContext, NativeFnError},
 };
 use rogato_common::{
-    ast::{expression::Query, fn_def::FnDef, type_expression::TypeDef},
-    val,
+    ast::{
+        expression::Query,
+        fn_def::FnDef,
+        type_expression::{TypeDef, TypeExpression},
+    },
+    val::{self, Partial},
 };
 use rogato_db::db::ObjectStorage;
-use std::{cell::RefCell, rc::Rc};
+use rogato_parser::{parse_file, parse_use_defs, ParserContext};
+use std::{any::Any, cell::RefCell, path::PathBuf, rc::Rc};
 use uuid::Uuid;
 
 #[cfg(feature = "flame_it")]
@@ -33,6 +45,8 @@ pub struct EvalContext {
     env: Environment,
     obj_storage: ObjectStorage,
     query_planner: QueryPlanner,
+    module_loader: Rc<RefCell<ModuleLoader>>,
+    call_stack: Rc<RefCell<CallStack>>,
     current_func_id: Option<Identifier>,
 }
 
@@ -43,22 +57,28 @@ impl Default for EvalContext {
 }
 
 impl EvalContext {
+    /// A new context with the std lib loaded.
+    /// Panics if the std lib fails to load, see `try_new` for reporting that instead.
     pub fn new() -> EvalContext {
-        EvalContext {
-            id: uuid::Uuid::new_v4(),
-            env: lib_std::env(),
-            obj_storage: ObjectStorage::new(),
-            query_planner: QueryPlanner::new(),
-            current_func_id: None,
+        match Self::try_new() {
+            Ok(context) => context,
+            Err(e) => panic!("Failed to load std lib: {e}"),
         }
     }
 
+    /// A new context with the std lib loaded, unless it fails to load.
+    pub fn try_new() -> Result<EvalContext, EvalError> {
+        Ok(Self::from_env(lib_std::env()?))
+    }
+
     pub fn from_env(env: Environment) -> EvalContext {
         EvalContext {
             id: uuid::Uuid::new_v4(),
             env,
             obj_storage: ObjectStorage::new(),
             query_planner: QueryPlanner::new(),
+            module_loader: Rc::new(RefCell::new(ModuleLoader::default())),
+            call_stack: Rc::new(RefCell::new(CallStack::new())),
             current_func_id: None,
         }
     }
@@ -69,10 +89,36 @@ impl EvalContext {
             env: self.env.child(),
             obj_storage: self.obj_storage.clone(),
             query_planner: self.query_planner.clone(),
+            module_loader: Rc::clone(&self.module_loader),
+            call_stack: Rc::clone(&self.call_stack),
+            current_func_id: self.current_func_id.clone(),
+        }
+    }
+
+    /// A context with a copy of this context's environment and module search paths,
+    /// for evaluating code without affecting this context.
+    pub fn isolated(&self) -> Self {
+        EvalContext {
+            id: uuid::Uuid::new_v4(),
+            env: self.env.isolated(),
+            obj_storage: self.obj_storage.clone(),
+            query_planner: self.query_planner.clone(),
+            module_loader: Rc::new(RefCell::new(self.module_loader.borrow().clone())),
+            call_stack: Rc::new(RefCell::new(CallStack::new())),
             current_func_id: self.current_func_id.clone(),
         }
     }
 
+    /// A child context for evaluating the body of the given function.
+    /// Functions defined in a module see that module's definitions, no matter where they're called from.
+    fn with_fn_env(&self, func: &FnDef) -> Self {
+        let mut fn_ctx = self.with_child_env();
+        if let Some(module_id) = func.module_id() {
+            fn_ctx.set_current_module(module_id.clone());
+        }
+        fn_ctx
+    }
+
     #[cfg_attr(feature = "flame_it", flame)]
     #[inline]
     pub fn clear(&mut self) {
@@ -83,6 +129,38 @@ impl EvalContext {
         self.current_func_id.clone().unwrap_or_else(|| "N/A".into())
     }
 
+    /// Wraps an error raised by code evaluated on behalf of the current native function.
+    fn native_fn_error(&self, error: EvalError) -> NativeFnError {
+        match error {
+            EvalError::NativeFnFailed(e) => e,
+            e => NativeFnError::EvaluationFailed(self.current_func_id(), BoxedError::new(e)),
+        }
+    }
+
+    /// Runs the given call within a new call stack frame.
+    pub fn in_frame<T, E, F: FnOnce(&mut Self) -> Result<T, E>>(
+        &mut self,
+        frame: CallFrame,
+        call: F,
+    ) -> Result<T, E> {
+        self.call_stack.borrow_mut().push(frame);
+        let result = call(self);
+        self.call_stack.borrow_mut().pop();
+        result
+    }
+
+    /// Attaches the currently active call frames to an error raised within them,
+    /// unless it already carries the trace of where it was raised.
+    pub fn with_call_trace(&self, error: EvalError) -> EvalError {
+        if error.call_trace().is_some() {
+            return error;
+        }
+        match self.call_stack.borrow().trace() {
+            Some(trace) => EvalError::WithCallTrace(trace, Box::new(error)),
+            None => error,
+        }
+    }
+
     pub fn import(
         &mut self,
         module_id: &Identifier,
@@ -96,16 +174,144 @@ impl EvalContext {
         Ok(val::none())
     }
 
+    /// Adds a directory in which `use Foo.Bar` looks for a `Foo/Bar.roga` source file.
+    pub fn add_search_path<P: Into<PathBuf>>(&mut self, path: P) {
+        self.module_loader.borrow_mut().add_search_path(path)
+    }
+
+    pub fn search_paths(&self) -> Vec<PathBuf> {
+        self.module_loader
+            .borrow()
+            .search_paths()
+            .cloned()
+            .collect()
+    }
+
+    /// Makes sure the given module is defined, loading it from the search paths if needed.
+    /// Modules already defined in the environment (either native or loaded before) are reused.
+    pub fn load_module(&mut self, module_id: &Identifier) -> Result<Module, EvalError> {
+        self.module_loader.borrow_mut().start_loading(module_id)?;
+        let result = self.load_module_source(module_id);
+        self.module_loader.borrow_mut().finish_loading(module_id);
+        result
+    }
+
+    fn load_module_source(&mut self, module_id: &Identifier) -> Result<Module, EvalError> {
+        if let Some(module) = self.lookup_module(module_id) {
+            return Ok(module);
+        }
+
+        let not_found = || EvalError::ImportFailed(module_id.clone(), vec![]);
+        let file_path = self
+            .module_loader
+            .borrow()
+            .find_module_file(module_id)
+            .ok_or_else(not_found)?;
+        let file_name = file_path.to_string_lossy().to_string();
+
+        let code = std::fs::read_to_string(&file_path).map_err(|e| {
+            EvalError::ModuleLoadFailed(module_id.clone(), file_name.clone(), e.to_string())
+        })?;
+        let parser_ctx = ParserContext::new();
+        self.import_fixities(&code, &parser_ctx)?;
+        let program = parse_file(&code, &file_name, &parser_ctx).map_err(|e| {
+            EvalError::ModuleLoadFailed(module_id.clone(), file_name.clone(), e.to_string())
+        })?;
+
+        // definitions before a `module` statement would end up in the importing code's module
+        if declared_module_id(&program) != Some(module_id) {
+            return Err(EvalError::ModuleLoadFailed(
+                module_id.clone(),
+                file_name,
+                format!("expected it to start with `module {module_id}`"),
+            ));
+        }
+
+        // evaluated in its own scope so the module's `module` and `use` statements
+        // don't change the current module or imports of the importing code
+        let defined_modules = self.env.module_ids();
+        let mut module_ctx = self.with_child_env();
+        let result = module_ctx
+            .evaluate_program(&program)
+            .and_then(|_| self.lookup_module(module_id).ok_or_else(not_found));
+        if result.is_err() {
+            // don't leave any partially defined modules behind
+            for id in self.env.module_ids() {
+                if !defined_modules.contains(&id) {
+                    self.env.remove_module(&id);
+                }
+            }
+        }
+        result
+    }
+
+    /// Declares the fixities exported by all modules imported in the given code within the
+    /// parser context, loading the modules if needed, so the code parses their operators
+    /// accordingly.
+    pub fn import_fixities(
+        &mut self,
+        code: &str,
+        parser_ctx: &ParserContext,
+    ) -> Result<(), EvalError> {
+        for use_def in parse_use_defs(code, parser_ctx) {
+            let module = self.load_module(use_def.id())?;
+            for fixity_def in module.fixity_defs() {
+                parser_ctx.declare_fixity(fixity_def.id().clone(), fixity_def.fixity());
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluates the given program, expanding the macro calls within each root definition
+    /// right before evaluating it. Parsed code should always be evaluated through here,
+    /// as it may contain macro calls.
+    pub fn evaluate_program(&mut self, program: &Program) -> Result<ValueRef, EvalError> {
+        let mut values = Vec::with_capacity(program.len());
+        for ast in program.iter() {
+            values.push(self.evaluate_root(ast)?)
+        }
+        Ok(val::list(values))
+    }
+
+    /// Evaluates the given root definition after expanding the macro calls within it.
+    pub fn evaluate_root(&mut self, ast: &Node<AST>) -> Result<ValueRef, EvalError> {
+        MacroExpander::new(self)
+            .expand_root(ast)
+            .and_then(|expanded| expanded.evaluate(self))
+            .map_err(|e| e.with_span(ast.span()))
+    }
+
+    /// Evaluates the given expression after expanding the macro calls within it.
+    pub fn evaluate_expression(&mut self, expr: &Node<Expression>) -> Result<ValueRef, EvalError> {
+        MacroExpander::new(self)
+            .expand_expr(expr)
+            .and_then(|expr| expr.evaluate(self))
+    }
+
+    /// Makes the given module accessible under the alias in qualified lookups, e.g. `L.map`.
+    pub fn alias_module(&mut self, module: &Module, alias: &Identifier) {
+        self.env.alias_module(module, alias)
+    }
+
     pub fn define_fn(&mut self, id: &Identifier, fn_variant: FnDefVariant) -> ValueRef {
         let mut module = self.current_module();
         module.fn_def(id.clone(), fn_variant);
         val::string(format!("FnDef {id}"))
     }
 
-    fn lookup_fn(&self, id: &Identifier) -> Option<Rc<RefCell<FnDef>>> {
+    pub fn lookup_fn(&self, id: &Identifier) -> Option<Rc<RefCell<FnDef>>> {
         self.env.lookup_fn(id)
     }
 
+    pub fn define_macro(&mut self, macro_def: &MacroDef) {
+        let mut module = self.current_module();
+        module.macro_def(macro_def);
+    }
+
+    pub fn lookup_macro(&self, id: &Identifier) -> Option<Rc<RefCell<FnDef>>> {
+        self.env.lookup_macro(id)
+    }
+
     #[cfg_attr(feature = "flame_it", flame)]
     pub fn call_lambda(
         &mut self,
@@ -113,10 +319,14 @@ impl EvalContext {
         lambda: &Lambda,
         args: &[ValueRef],
     ) -> Result<ValueRef, EvalError> {
-        lambda_ctx
-            .borrow_mut()
-            .evaluate_lambda_call(lambda, args)
-            .map_err(|e| EvalError::Unknown(e.to_string()))
+        let lambda = Rc::new(lambda.clone());
+        match TailCall::lambda(&lambda_ctx, &lambda, args.to_vec()) {
+            Some(call) => self.perform_call(call),
+            None => lambda_ctx
+                .borrow_mut()
+                .evaluate_lambda_call(&lambda, args)
+                .map_err(|e| self.with_call_trace(EvalError::LambdaClosureError(e))),
+        }
     }
 
     #[cfg_attr(feature = "flame_it", flame)]
@@ -125,47 +335,199 @@ impl EvalContext {
         func: Rc<RefCell<FnDef>>,
         args: &[ValueRef],
     ) -> Result<ValueRef, EvalError> {
-        let func = func.borrow();
+        self.perform_call(TailCall::Fn(self.clone(), func, args.to_vec()))
+    }
+
+    /// Performs the given call along with all tail calls it results in.
+    /// Tail calls are performed in a loop instead of recursively, so they don't grow the stack.
+    /// They share a single call stack frame on top of the initial call's frame, and the context
+    /// they're called from gets collapsed into the one of the initial call, so it doesn't grow either.
+    pub fn perform_call(&mut self, call: TailCall) -> Result<ValueRef, EvalError> {
+        self.call_stack.borrow_mut().push(call.frame());
+        let root_ctx = match &call {
+            TailCall::Fn(context, _, _) | TailCall::Lambda(context, _, _) => context.clone(),
+        };
+        let mut in_tail_call = false;
+        let mut result = Self::call_step(&call);
+        let mut current = call;
+
+        let result = loop {
+            let call = match result {
+                Ok(Tail::Value(val)) => break Ok(val),
+                Ok(Tail::Call(TailCall::Fn(call_ctx, func, args))) => match &current {
+                    // self-recursive calls don't need any variables of the previous call
+                    TailCall::Fn(context, current_func, _) if Rc::ptr_eq(current_func, &func) => {
+                        TailCall::Fn(context.clone(), func, args)
+                    }
+                    _ => TailCall::Fn(call_ctx.collapsed_into(&root_ctx), func, args),
+                },
+                Ok(Tail::Call(call @ TailCall::Lambda(..))) => call,
+                Err(e) => break Err(self.with_call_trace(e)),
+            };
+
+            let frame = call.frame();
+            if in_tail_call {
+                self.call_stack.borrow_mut().replace(frame);
+            } else {
+                self.call_stack.borrow_mut().push(frame);
+                in_tail_call = true;
+            }
+            result = Self::call_step(&call);
+            current = call;
+        };
 
-        if func.is_tail_recursive() {
-            return self.call_tail_recursive_function_direct(&func, args);
+        if in_tail_call {
+            self.call_stack.borrow_mut().pop();
+        }
+        self.call_stack.borrow_mut().pop();
+        result
+    }
+
+    /// Performs a single call. Calls with too few arguments result in a partial application,
+    /// calls with too many apply the result of calling with as many as accepted to the rest.
+    /// Calling a function that requires arguments with none at all is an arity mismatch,
+    /// references to functions are only created where they're named, see `Expression::Var`.
+    fn call_step(call: &TailCall) -> Result<Tail, EvalError> {
+        match call {
+            TailCall::Fn(context, func, args) => {
+                let (required_argc, max_argc) = {
+                    let func = func.borrow();
+                    (func.required_args(), func.max_args())
+                };
+                if args.is_empty() && required_argc > 0 {
+                    return Err(EvalError::FunctionArityMismatch(
+                        func.borrow().id().clone(),
+                        required_argc,
+                        0,
+                    ));
+                }
+                if args.len() < required_argc {
+                    return Ok(Tail::Value(val::partial_fn(Rc::clone(func), args.clone())));
+                }
+                if args.len() > max_argc {
+                    let (args, rest) = args.split_at(max_argc);
+                    let call = TailCall::Fn(context.clone(), Rc::clone(func), args.to_vec());
+                    return context.clone().call_with_rest(call, rest);
+                }
+                context.call_fn_variants(&func.borrow(), args)
+            }
+            TailCall::Lambda(closure_ctx, lambda, args) => {
+                let min_argc = lambda.min_arg_count();
+                if args.is_empty() && min_argc > 0 {
+                    return Err(EvalError::LambdaArityMismatch(min_argc, 0));
+                }
+                if args.len() < min_argc {
+                    return Ok(Tail::Value(val::partial_lambda(
+                        Rc::new(RefCell::new(closure_ctx.clone())),
+                        Rc::clone(lambda),
+                        args.clone(),
+                    )));
+                }
+                let max_argc = lambda.max_arg_count();
+                if args.len() > max_argc {
+                    let (args, rest) = args.split_at(max_argc);
+                    let call =
+                        TailCall::Lambda(closure_ctx.clone(), Rc::clone(lambda), args.to_vec());
+                    return closure_ctx.clone().call_with_rest(call, rest);
+                }
+                closure_ctx
+                    .call_lambda_variants(lambda, args)
+                    .map(|(_, tail)| tail)
+                    .map_err(EvalError::LambdaClosureError)
+            }
         }
+    }
 
-        let given_argc = args.len();
-        let required_argc = func.required_args();
+    /// Performs the given call and calls its result with the rest of the arguments
+    /// of an over-applied call, e.g. `adder 1 2` with `let adder x = (y -> x + y)`.
+    fn call_with_rest(&mut self, call: TailCall, rest: &[ValueRef]) -> Result<Tail, EvalError> {
+        let func = self.perform_call(call)?;
+        self.tail_call_value(&func, rest.to_vec())
+    }
 
-        if given_argc < required_argc {
-            eprintln!(
-                "Function arity mismatch for {}: Expected at least {} but got {}",
-                func.id().clone(),
-                required_argc,
-                given_argc
-            );
-            return Err(EvalError::FunctionArityMismatch(
-                func.id().clone(),
-                required_argc,
-                given_argc,
-            ));
+    /// A call of the given function value in tail position. Function values are lambdas,
+    /// function references, partial applications and symbols referring to a function.
+    pub fn tail_call_value(
+        &mut self,
+        func: &ValueRef,
+        args: Vec<ValueRef>,
+    ) -> Result<Tail, EvalError> {
+        match &**func {
+            Value::Lambda(lambda_ctx, lambda) => self.tail_call_lambda(lambda_ctx, lambda, args),
+            Value::FnRef(func) => Ok(Tail::Call(TailCall::Fn(
+                self.clone(),
+                Rc::clone(func),
+                args,
+            ))),
+            Value::Partial(partial @ Partial::Fn(func, _)) => Ok(Tail::Call(TailCall::Fn(
+                self.clone(),
+                Rc::clone(func),
+                partial.args_with(&args),
+            ))),
+            Value::Partial(partial @ Partial::Lambda(lambda_ctx, lambda, _)) => {
+                self.tail_call_lambda(lambda_ctx, lambda, partial.args_with(&args))
+            }
+            Value::Symbol(fn_id) => match self.lookup_fn(fn_id) {
+                Some(func) => Ok(Tail::Call(TailCall::Fn(self.clone(), func, args))),
+                None => Err(self.private_or(fn_id, EvalError::FunctionNotDefined(fn_id.clone()))),
+            },
+            _ => Err(EvalError::ValueNotCallable(ValueRef::clone(func))),
         }
+    }
 
-        let last_current_func_id = self.current_func_id.clone();
-        self.current_func_id = Some(func.id().clone());
+    fn tail_call_lambda(
+        &mut self,
+        lambda_ctx: &Rc<RefCell<dyn LambdaClosureContext>>,
+        lambda: &Rc<Lambda>,
+        args: Vec<ValueRef>,
+    ) -> Result<Tail, EvalError> {
+        match TailCall::lambda(lambda_ctx, lambda, args.clone()) {
+            Some(call) => Ok(Tail::Call(call)),
+            None => self
+                .call_lambda(Rc::clone(lambda_ctx), lambda, &args)
+                .map(Tail::Value),
+        }
+    }
 
-        let mut last_attempted_pattern = None;
+    /// Calls the given function value, see `tail_call_value`.
+    pub fn call_value(
+        &mut self,
+        func: &ValueRef,
+        args: &[ValueRef],
+    ) -> Result<ValueRef, EvalError> {
+        self.tail_call_value(func, args.to_vec())?.resolve(self)
+    }
 
+    /// A context for a tail call made from this context, with all variables visible from here
+    /// that are defined below the given context collapsed into a single child of it.
+    fn collapsed_into(&self, context: &EvalContext) -> Self {
+        EvalContext {
+            id: uuid::Uuid::new_v4(),
+            env: self.env.collapsed_into(&context.env),
+            obj_storage: self.obj_storage.clone(),
+            query_planner: self.query_planner.clone(),
+            module_loader: Rc::clone(&self.module_loader),
+            call_stack: Rc::clone(&self.call_stack),
+            current_func_id: self.current_func_id.clone(),
+        }
+    }
+
+    fn call_fn_variants(&self, func: &FnDef, args: &[ValueRef]) -> Result<Tail, EvalError> {
         flame_guard!("ƒ⡟ {}", func.id());
 
-        for FnDefVariant(arg_patterns, body) in func.variants_iter() {
-            if arg_patterns.len() < args.len() {
+        let mut fn_ctx = self.with_fn_env(func);
+        fn_ctx.current_func_id = Some(func.id().clone());
+
+        for FnDefVariant(arg_patterns, guard, body) in func.variants_iter() {
+            if !arg_patterns.accepts(args.len()) {
                 continue;
             }
-            let mut fn_ctx = self.with_child_env();
+            fn_ctx.clear();
             let mut matched = 0;
             let mut attempted = 0;
             for (arg_pattern, arg_val) in arg_patterns.iter().zip(args) {
                 flame_guard!("ƒ⡟ {} {}", func.id(), arg_pattern);
                 attempted += 1;
-                last_attempted_pattern = Some(Rc::clone(arg_pattern));
                 match arg_pattern.pattern_match(&mut fn_ctx, ValueRef::clone(arg_val)) {
                     Ok(PatternMatch::Matched(_)) => {
                         matched += 1;
@@ -180,138 +542,27 @@ impl EvalContext {
                 }
             }
 
-            if matched == attempted {
-                let return_val = match &**body {
-                    FnDefBody::NativeFn(f) => f(&mut fn_ctx, args).map_err(EvalError::from),
-                    FnDefBody::RogatoFn(expr) => expr.evaluate(&mut fn_ctx),
+            if matched == attempted && fn_ctx.guard_passes(guard.as_ref())? {
+                return match &**body {
+                    FnDefBody::NativeFn(f) => f(&mut fn_ctx, args)
+                        .map(Tail::Value)
+                        .map_err(EvalError::from),
+                    FnDefBody::RogatoFn(expr) => expr.evaluate_tail(&mut fn_ctx),
                 };
-                return return_val;
-            }
-        }
-
-        self.current_func_id = last_current_func_id;
-
-        return Err(EvalError::PatternMatchFailed(
-            func.id().clone(),
-            PatternMatchingError::NoFnVariantMatched(
-                func.id().clone(),
-                last_attempted_pattern,
-                args.to_vec(),
-            ),
-        ));
-    }
-
-    #[cfg_attr(feature = "flame_it", flame)]
-    pub fn call_tail_recursive_function_direct(
-        &mut self,
-        func: &FnDef,
-        args: &[ValueRef],
-    ) -> Result<ValueRef, EvalError> {
-        let last_current_func_id = self.current_func_id.clone();
-        self.current_func_id = Some(func.id().clone());
-
-        let mut last_attempted_pattern = None;
-
-        let mut return_val = None;
-        let mut loop_args = Vec::with_capacity(args.len());
-        for arg in args.iter() {
-            loop_args.push(ValueRef::clone(arg));
-        }
-
-        let mut fn_ctx = self.with_child_env();
-        'looping: loop {
-            flame_guard!("∞ƒ {}", func.id());
-            fn_ctx.clear();
-
-            flame_guard!("∞ƒ⡟ {}", func.id());
-
-            for FnDefVariant(arg_patterns, body) in func.variants_iter() {
-                let mut matched = 0;
-                let mut attempted = 0;
-                for (arg_pattern, arg_val) in arg_patterns.iter().zip(loop_args.iter()) {
-                    flame_guard!("∞ƒ⡟ {} {}", func.id(), arg_pattern);
-                    attempted += 1;
-                    last_attempted_pattern = Some(Rc::clone(arg_pattern));
-                    match arg_pattern.pattern_match(&mut fn_ctx, ValueRef::clone(arg_val)) {
-                        Ok(PatternMatch::Matched(_)) => {
-                            matched += 1;
-                            continue;
-                        }
-                        Ok(PatternMatch::TryNextPattern) => {
-                            break;
-                        }
-                        Err(e) => {
-                            return Err(e.into());
-                        }
-                    }
-                }
-
-                if matched == attempted {
-                    match &**body {
-                        FnDefBody::NativeFn(f) => {
-                            return_val = Some(f(&mut fn_ctx, args).map_err(EvalError::from)?);
-                            break 'looping;
-                        }
-                        FnDefBody::RogatoFn(expr) => match &**expr {
-                            Expression::FnCall(fn_call) => {
-                                if fn_call.id == func.id {
-                                    loop_args = Vec::with_capacity(fn_call.args.len());
-                                    for arg_expr in fn_call.args.iter() {
-                                        loop_args.push(arg_expr.evaluate(&mut fn_ctx)?);
-                                    }
-                                    continue 'looping;
-                                } else {
-                                    return_val = Some(expr.evaluate(&mut fn_ctx)?);
-                                    break 'looping;
-                                }
-                            }
-                            Expression::Let(let_expr) => match &*let_expr.body {
-                                Expression::FnCall(fn_call) => {
-                                    for (id, expr) in let_expr.bindings.iter() {
-                                        let val = expr.evaluate(&mut fn_ctx)?;
-                                        self.define_var(id, val);
-                                    }
-                                    if fn_call.id == func.id {
-                                        loop_args = Vec::with_capacity(fn_call.args.len());
-                                        for arg_expr in fn_call.args.iter() {
-                                            loop_args.push(arg_expr.evaluate(&mut fn_ctx)?);
-                                        }
-                                        continue 'looping;
-                                    } else {
-                                        return_val = Some(expr.evaluate(&mut fn_ctx)?);
-                                        break 'looping;
-                                    }
-                                }
-                                expr => {
-                                    return_val = Some(expr.evaluate(&mut fn_ctx)?);
-                                    break 'looping;
-                                }
-                            },
-                            _ => {
-                                return_val = Some(expr.evaluate(&mut fn_ctx)?);
-                                break 'looping;
-                            }
-                        },
-                    }
-                }
             }
-            break 'looping;
-        }
-
-        self.current_func_id = last_current_func_id;
-
-        if let Some(value) = return_val {
-            return Ok(value);
         }
 
-        return Err(EvalError::PatternMatchFailed(
+        Err(EvalError::PatternMatchFailed(
             func.id().clone(),
             PatternMatchingError::NoFnVariantMatched(
                 func.id().clone(),
-                last_attempted_pattern,
+                func.variants_iter()
+                    .filter(|variant| variant.0.accepts(args.len()))
+                    .map(|variant| variant.0.clone())
+                    .collect(),
                 args.to_vec(),
             ),
-        ));
+        ))
     }
 
     #[cfg_attr(feature = "flame_it", flame)]
@@ -349,11 +600,56 @@ impl EvalContext {
         self.env.lookup_const(id)
     }
 
+    /// Defines a type in the current module, along with the constructors of all its variants
+    /// if it's a union type.
+    pub fn define_type(&mut self, type_def: Rc<TypeDef>) {
+        let mut module = self.current_module();
+        if let TypeExpression::Union(variants) = &*type_def.type_expr() {
+            for (id, params) in variants.iter() {
+                module.constructor_def(&type_def.id(), id, params.len());
+            }
+        }
+        module.type_def(type_def.id(), type_def);
+    }
+
+    /// Declares the fixity of an operator in the current module.
+    pub fn define_fixity(&mut self, fixity_def: &FixityDef) {
+        self.current_module().fixity_def(fixity_def)
+    }
+
+    /// Returns the id of the union type the given (possibly qualified) variant constructor
+    /// belongs to, along with the constructor's id within its module.
+    #[inline]
+    pub fn lookup_constructor(&self, id: &Identifier) -> Option<(Identifier, Identifier)> {
+        self.env.lookup_constructor(id)
+    }
+
+    /// Returns the constructor function of the given (possibly qualified) variant constructor
+    /// taking params. Constructors without params are defined as constants instead.
+    pub fn lookup_constructor_fn(&self, id: &Identifier) -> Option<Rc<RefCell<FnDef>>> {
+        self.lookup_constructor(id).and_then(|_| self.lookup_fn(id))
+    }
+
     #[inline]
     pub fn lookup_type(&self, id: &Identifier) -> Option<Rc<TypeDef>> {
         self.env.lookup_type(id)
     }
 
+    /// Looks up a type along with its fully qualified id, which struct and variant values store.
+    #[inline]
+    pub fn lookup_qualified_type(&self, id: &Identifier) -> Option<(Identifier, Rc<TypeDef>)> {
+        self.env.lookup_qualified_type(id)
+    }
+
+    /// Turns the given lookup error for an id into an `EvalError::PrivateDefinition`
+    /// if the id refers to a definition that's private to another module.
+    pub fn private_or(&self, id: &Identifier, error: EvalError) -> EvalError {
+        match self.env.lookup_private(id) {
+            Some((module_id, local_id)) => EvalError::PrivateDefinition(module_id, local_id),
+            None => error,
+        }
+    }
+
     #[inline]
     pub fn lookup_db_type(&self, id: &Identifier) -> Option<Rc<TypeDef>> {
         // TODO: do lookup / verification with DB instead
@@ -369,6 +665,20 @@ impl EvalContext {
         self.env.set_current_module(module_id)
     }
 
+    /// Evaluates the `when` guard of a fn or lambda variant after its argument patterns
+    /// have been bound in this context. Variants without a guard always pass.
+    pub fn guard_passes(&mut self, guard: Option<&Node<Expression>>) -> Result<bool, EvalError> {
+        let guard = match guard {
+            Some(guard) => guard,
+            None => return Ok(true),
+        };
+        let val = guard.evaluate(self)?;
+        match *val {
+            Value::Bool(passed) => Ok(passed),
+            _ => Err(EvalError::GuardNotBool(ValueRef::clone(&val))),
+        }
+    }
+
     pub fn schedule_query(&mut self, query: &Query) -> QueryResult {
         let mut eval_ctx = self.with_child_env();
         self.query_planner.query(&mut eval_ctx, query)
@@ -388,13 +698,22 @@ impl NativeFnContext for EvalContext {
         self.lookup_fn(id)
     }
 
+    fn current_func_id(&self) -> Identifier {
+        self.current_func_id()
+    }
+
+    fn lookup_constructor_type(&self, id: &Identifier) -> Option<Identifier> {
+        self.lookup_constructor(id).map(|(type_id, _)| type_id)
+    }
+
     fn call_function(
         &mut self,
         id: &Identifier,
         args: &[ValueRef],
     ) -> Option<Result<ValueRef, NativeFnError>> {
-        self.call_function(id, args)
-            .map(|res| res.map_err(|e| NativeFnError::EvaluationFailed(id.clone(), e.to_string())))
+        self.call_function(id, args).map(|res| {
+            res.map_err(|e| NativeFnError::EvaluationFailed(id.clone(), BoxedError::new(e)))
+        })
     }
 
     fn call_lambda(
@@ -403,8 +722,9 @@ impl NativeFnContext for EvalContext {
         lambda: &Lambda,
         args: &[ValueRef],
     ) -> Result<ValueRef, NativeFnError> {
-        self.call_lambda(lambda_ctx, lambda, args)
-            .map_err(|e| NativeFnError::Unknown(lambda.to_string().into(), e.to_string()))
+        self.call_lambda(lambda_ctx, lambda, args).map_err(|e| {
+            NativeFnError::LambdaEvaluationFailed(Rc::new(lambda.clone()), BoxedError::new(e))
+        })
     }
 
     fn call_function_direct(
@@ -414,9 +734,36 @@ impl NativeFnContext for EvalContext {
     ) -> Result<ValueRef, rogato_common::native_fn::NativeFnError> {
         let id = func.borrow().id().clone();
         self.call_function_direct(func, args).map_err(|e| {
-            rogato_common::native_fn::NativeFnError::EvaluationFailed(id, e.to_string())
+            rogato_common::native_fn::NativeFnError::EvaluationFailed(id, BoxedError::new(e))
         })
     }
+
+    fn call_value(
+        &mut self,
+        func: &ValueRef,
+        args: &[ValueRef],
+    ) -> Result<ValueRef, NativeFnError> {
+        self.call_value(func, args)
+            .map_err(|e| NativeFnError::ValueCallFailed(ValueRef::clone(func), BoxedError::new(e)))
+    }
+
+    fn evaluate_expr(&mut self, expr: &Node<Expression>) -> Result<ValueRef, NativeFnError> {
+        self.evaluate_expression(expr)
+            .map_err(|e| self.native_fn_error(e))
+    }
+
+    fn evaluate_ast(&mut self, ast: &Node<AST>) -> Result<ValueRef, NativeFnError> {
+        self.evaluate_root(ast).map_err(|e| self.native_fn_error(e))
+    }
+
+    fn fresh_context(&self) -> Box<dyn NativeFnContext> {
+        let mut context = EvalContext::new();
+        for search_path in self.search_paths() {
+            context.add_search_path(search_path);
+        }
+        context.current_func_id = self.current_func_id.clone();
+        Box::new(context)
+    }
 }
 
 impl LambdaClosureContext for EvalContext {
@@ -436,13 +783,35 @@ impl LambdaClosureContext for EvalContext {
         Box::new(self.with_child_env())
     }
 
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
     #[cfg_attr(feature = "flame_it", flame)]
     fn evaluate_lambda_call(
         &mut self,
         lambda: &Lambda,
         args: &[ValueRef],
     ) -> Result<ValueRef, LambdaClosureEvalError> {
+        let frame = CallFrame::Lambda(Rc::new(lambda.clone()), CallArgs::new(args));
+        let (variant, tail) = self.in_frame(frame, |ctx| ctx.call_lambda_variants(lambda, args))?;
+        tail.resolve(self)
+            .map_err(|e| LambdaClosureEvalError::EvaluationFailed(variant, BoxedError::new(e)))
+    }
+}
+
+impl EvalContext {
+    /// Evaluates the body of the first matching lambda variant in tail position,
+    /// returning it along with the variant.
+    fn call_lambda_variants(
+        &self,
+        lambda: &Lambda,
+        args: &[ValueRef],
+    ) -> Result<(Rc<LambdaVariant>, Tail), LambdaClosureEvalError> {
         for lambda_variant in lambda.variants_iter() {
+            if lambda_variant.arg_count() != args.len() {
+                continue;
+            }
             let mut call_ctx = self.with_child_env();
             let mut matched: u32 = 0;
             let mut attempted: u32 = 0;
@@ -460,11 +829,10 @@ impl LambdaClosureContext for EvalContext {
                     Ok(PatternMatch::TryNextPattern) => {
                         break;
                     }
-                    Err(e) => {
-                        eprintln!("evaluate_lambda_call: {e}");
+                    Err(_) => {
                         return Err(LambdaClosureEvalError::LambdaVariantArgumentMismatch(
                             Rc::clone(lambda_variant),
-                            Rc::clone(arg_pattern),
+                            Node::clone(arg_pattern),
                             ValueRef::clone(arg_val),
                         ));
                     }
@@ -472,13 +840,24 @@ impl LambdaClosureContext for EvalContext {
             }
 
             if matched == attempted {
-                return lambda_variant.body.evaluate(&mut call_ctx).map_err(|e| {
-                    eprintln!("evaluate_lambda_call: {e}");
-                    LambdaClosureEvalError::EvaluationFailed(
+                let guard_passed = call_ctx
+                    .guard_passes(lambda_variant.guard.as_ref())
+                    .map_err(|e| {
+                        LambdaClosureEvalError::EvaluationFailed(
+                            Rc::clone(lambda_variant),
+                            BoxedError::new(self.with_call_trace(e)),
+                        )
+                    })?;
+                if !guard_passed {
+                    continue;
+                }
+                return match lambda_variant.body.evaluate_tail(&mut call_ctx) {
+                    Ok(tail) => Ok((Rc::clone(lambda_variant), tail)),
+                    Err(e) => Err(LambdaClosureEvalError::EvaluationFailed(
                         Rc::clone(lambda_variant),
-                        e.to_string(),
-                    )
-                });
+                        BoxedError::new(self.with_call_trace(e)),
+                    )),
+                };
             }
         }
 
@@ -488,3 +867,14 @@ impl LambdaClosureContext for EvalContext {
         ));
     }
 }
+
+/// The id of the module declared by the first statement of the given program, if any.
+fn declared_module_id(program: &Program) -> Option<&Identifier> {
+    program
+        .iter()
+        .find(|ast| !matches!(&***ast, AST::RootComment(_)))
+        .and_then(|ast| match &**ast {
+            AST::ModuleDef(module_def) => Some(module_def.id()),
+            _ => None,
+        })
+}
@@ -2,23 +2,34 @@
 pub mod tests;
 
 pub mod ast;
+pub mod call_stack;
 pub mod environment;
 pub mod eval_context;
 pub mod lib_std;
+pub mod macro_expander;
 pub mod module;
+pub mod module_loader;
 pub mod pattern_matching;
+pub mod quasi_quote;
 pub mod query_planner;
+pub mod tail_call;
 
+use call_stack::CallTrace;
 pub use eval_context::EvalContext;
 use pattern_matching::PatternMatchingError;
 use query_planner::QueryError;
-use rogato_common::ast::{lambda::LambdaClosureEvalError, Identifier};
+use rogato_common::ast::{
+    expression::Expression, lambda::LambdaClosureEvalError, type_expression::TypeExpression,
+    Identifier, Node, Span,
+};
+use rogato_common::error::BoxedError;
 pub use rogato_common::{
     ast::VarIdentifier,
     native_fn::{NativeFn, NativeFnError},
     val::{Value, ValueRef},
 };
 
+use std::error::Error;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq, Eq, Clone)]
@@ -29,6 +40,12 @@ pub enum EvalError {
     #[error("Import failed, module not found for: {0} {{  }}")]
     ImportFailed(Identifier, Vec<Identifier>),
 
+    #[error("Import cycle detected: {}", display_import_cycle(.0))]
+    ImportCycle(Vec<Identifier>),
+
+    #[error("Failed to load module {0} from {1}: {2}")]
+    ModuleLoadFailed(Identifier, String, String),
+
     #[error("{0}")]
     QueryFailed(QueryError),
 
@@ -38,6 +55,27 @@ pub enum EvalError {
     #[error("Constant or Type not found: {0}")]
     ConstOrTypeNotFound(Identifier),
 
+    #[error("Struct type not found: {0}")]
+    StructTypeNotFound(Identifier),
+
+    #[error("Not a struct type: {0}")]
+    NotAStructType(Identifier),
+
+    #[error("Type not found: {0}")]
+    TypeNotFound(Identifier),
+
+    #[error("Missing property {1} for struct {0}")]
+    StructPropertyMissing(Identifier, Identifier),
+
+    #[error("Unknown property {1} for struct {0}")]
+    StructPropertyUnknown(Identifier, Identifier),
+
+    #[error("Property {1} of struct {0} expects type {2} but was given: {3}")]
+    StructPropertyTypeMismatch(Identifier, Identifier, Node<TypeExpression>, ValueRef),
+
+    #[error("Property {0} not found in: {1}")]
+    PropertyNotFound(Identifier, ValueRef),
+
     #[error("DB Type not found: {0}")]
     DBTypeNotFound(Identifier),
 
@@ -50,29 +88,197 @@ pub enum EvalError {
     #[error("Function not defined: {0}")]
     FunctionNotDefined(Identifier),
 
+    #[error("Cannot access private definition {1} of module {0}")]
+    PrivateDefinition(Identifier, Identifier),
+
     #[error("Function arity mismatch for {0} : Expected at least: {1} but got: {2}")]
     FunctionArityMismatch(Identifier, usize, usize),
 
+    #[error("Constructor arity mismatch for {0} : Expected: {1} but got: {2}")]
+    ConstructorArityMismatch(Identifier, usize, usize),
+
+    #[error("Value is not a function: {0}")]
+    ValueNotCallable(ValueRef),
+
     #[error("Lambda arity mismatch: Expected: {0} but got: {1}")]
     LambdaArityMismatch(usize, usize),
 
     #[error("Lambda closure error: {0}")]
-    LambdaClosureError(LambdaClosureEvalError),
+    LambdaClosureError(#[source] LambdaClosureEvalError),
 
     #[error("{0}")]
-    NativeFnFailed(NativeFnError),
+    NativeFnFailed(#[source] NativeFnError),
 
     #[error("IfElse condition is not a Bool value: {0}")]
     IFElseConditionNotBool(ValueRef),
 
+    #[error("No case arm matched value: {0}")]
+    CaseNoArmMatched(ValueRef),
+
+    #[error("Guard is not a Bool value: {0}")]
+    GuardNotBool(ValueRef),
+
     #[error("List cons requires List, was given: {0}")]
     ListConsInvalidList(ValueRef),
 
+    #[error("Struct update requires Object or Struct, was given: {0}")]
+    StructUpdateInvalidValue(ValueRef),
+
     #[error("Map cons requires Map, was given: {0}")]
     MapConsInvalidMap(ValueRef),
 
+    #[error("Unquote used outside of a quoted expression: {0}")]
+    UnquoteOutsideQuote(Node<Expression>),
+
+    #[error("Unquoted value cannot be spliced into quoted code: {0}")]
+    UnquoteNotSpliceable(ValueRef),
+
+    #[error("Macro {0} called at runtime, macro calls need to be expanded before evaluation")]
+    MacroNotExpanded(Identifier),
+
+    #[error("Expansion of macro {0} failed: {1}")]
+    MacroExpansionFailed(Identifier, #[source] Box<EvalError>),
+
+    #[error("Expansion of macro {0} exceeded the max. depth of {1} nested expansions")]
+    MacroExpansionTooDeep(Identifier, usize),
+
     #[error("EvalError during pattern match in {0} : {1}")]
     PatternMatchFailed(Identifier, PatternMatchingError),
+
+    #[error("{1}\n\tat {0}")]
+    Spanned(Span, Box<EvalError>),
+
+    #[error("{1}")]
+    WithCallTrace(CallTrace, Box<EvalError>),
+}
+
+impl EvalError {
+    /// Attaches the given source span, unless the error already points to a more specific location.
+    pub fn with_span(self, span: Option<&Span>) -> EvalError {
+        match span {
+            Some(span) if self.span().is_none() => match self {
+                EvalError::PatternMatchFailed(id, e) => {
+                    EvalError::PatternMatchFailed(id, e.with_span(Some(span)))
+                }
+                EvalError::WithCallTrace(trace, e) => {
+                    EvalError::WithCallTrace(trace, Box::new(e.with_span(Some(span))))
+                }
+                e => EvalError::Spanned(span.clone(), Box::new(e)),
+            },
+            _ => self,
+        }
+    }
+
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            EvalError::Spanned(span, _) => Some(span),
+            EvalError::FnCallArgumentError(e) => e.span(),
+            EvalError::QueryFailed(e) => e.span(),
+            EvalError::WithCallTrace(_, e) => e.span(),
+            EvalError::PatternMatchFailed(_, e) => e.span(),
+            _ => None,
+        }
+    }
+
+    /// The call stack at the point where the error occurred, if it was raised within a call.
+    /// Follows errors passed through native functions, lambdas and queries, like `root_cause`.
+    pub fn call_trace(&self) -> Option<&CallTrace> {
+        match self {
+            EvalError::WithCallTrace(trace, _) => Some(trace),
+            EvalError::Spanned(_, e)
+            | EvalError::FnCallArgumentError(e)
+            | EvalError::MacroExpansionFailed(_, e) => e.call_trace(),
+            EvalError::NativeFnFailed(e) => {
+                e.boxed_source()?.downcast_ref::<EvalError>()?.call_trace()
+            }
+            EvalError::LambdaClosureError(LambdaClosureEvalError::EvaluationFailed(_, source)) => {
+                source.downcast_ref::<EvalError>()?.call_trace()
+            }
+            EvalError::QueryFailed(e) => e.eval_error()?.call_trace(),
+            _ => None,
+        }
+    }
+
+    /// Returns the error without any attached call trace, e.g. for comparing errors.
+    pub fn without_call_trace(self) -> EvalError {
+        match self {
+            EvalError::WithCallTrace(_, e) => e.without_call_trace(),
+            EvalError::Spanned(span, e) => {
+                EvalError::Spanned(span, Box::new(e.without_call_trace()))
+            }
+            EvalError::FnCallArgumentError(e) => {
+                EvalError::FnCallArgumentError(Box::new(e.without_call_trace()))
+            }
+            EvalError::MacroExpansionFailed(id, e) => {
+                EvalError::MacroExpansionFailed(id, Box::new(e.without_call_trace()))
+            }
+            e => e,
+        }
+    }
+
+    /// The error message followed by the errors it was caused by and its call trace, if any,
+    /// for reporting to users.
+    pub fn report(&self) -> String {
+        let mut report = self.to_string();
+        let mut cause = self.boxed_source();
+        while let Some(error) = cause {
+            report.push_str(&format!("\nCaused by: {error}"));
+            cause = boxed_source(&**error);
+        }
+        if let Some(trace) = self.call_trace() {
+            report.push_str(&format!("\n{trace}"));
+        }
+        report
+    }
+
+    /// The error passed through a native function or lambda that caused this one, if any.
+    fn boxed_source(&self) -> Option<&BoxedError> {
+        match self {
+            EvalError::Spanned(_, e)
+            | EvalError::WithCallTrace(_, e)
+            | EvalError::FnCallArgumentError(e)
+            | EvalError::MacroExpansionFailed(_, e) => e.boxed_source(),
+            EvalError::NativeFnFailed(e) => boxed_source(e),
+            EvalError::LambdaClosureError(e) => boxed_source(e),
+            EvalError::QueryFailed(e) => e.eval_error()?.boxed_source(),
+            _ => None,
+        }
+    }
+
+    /// The innermost error that caused this one, following errors that were passed through
+    /// native functions and lambdas, e.g. a `PatternMatchFailed` raised within `Std.List.map`.
+    pub fn root_cause(&self) -> &EvalError {
+        let cause = match self {
+            EvalError::Spanned(_, e)
+            | EvalError::WithCallTrace(_, e)
+            | EvalError::FnCallArgumentError(e)
+            | EvalError::MacroExpansionFailed(_, e) => Some(&**e),
+            EvalError::NativeFnFailed(e) => e
+                .boxed_source()
+                .and_then(|source| source.downcast_ref::<EvalError>()),
+            EvalError::LambdaClosureError(LambdaClosureEvalError::EvaluationFailed(_, source)) => {
+                source.downcast_ref::<EvalError>()
+            }
+            _ => None,
+        };
+        match cause {
+            Some(e) => e.root_cause(),
+            None => self,
+        }
+    }
+}
+
+/// The boxed error that caused the given one, for errors wrapped by native functions and lambdas.
+fn boxed_source<'a>(error: &'a (dyn Error + 'static)) -> Option<&'a BoxedError> {
+    if let Some(e) = error.downcast_ref::<EvalError>() {
+        e.boxed_source()
+    } else if let Some(e) = error.downcast_ref::<NativeFnError>() {
+        e.boxed_source()
+    } else if let Some(LambdaClosureEvalError::EvaluationFailed(_, source)) = error.downcast_ref() {
+        Some(source)
+    } else {
+        None
+    }
 }
 
 impl From<QueryError> for EvalError {
@@ -87,24 +293,21 @@ impl From<NativeFnError> for EvalError {
     }
 }
 
-impl From<EvalError> for NativeFnError {
-    fn from(e: EvalError) -> Self {
-        NativeFnError::EvaluationFailed("EvalError: NativeFnError:".into(), format!("{e}"))
-    }
-}
-
 impl From<PatternMatchingError> for EvalError {
     fn from(e: PatternMatchingError) -> Self {
         match &e {
             PatternMatchingError::Unknown(func_id, _) => {
                 EvalError::PatternMatchFailed(func_id.clone(), e)
             }
-            PatternMatchingError::MatchFailed(func_id, _, _) => {
-                EvalError::PatternMatchFailed(func_id.clone(), e)
-            }
             PatternMatchingError::NoFnVariantMatched(func_id, _, _) => {
                 EvalError::PatternMatchFailed(func_id.clone(), e)
             }
+            PatternMatchingError::Spanned(_, inner) => match EvalError::from((**inner).clone()) {
+                EvalError::PatternMatchFailed(func_id, _) => {
+                    EvalError::PatternMatchFailed(func_id, e)
+                }
+                other => other,
+            },
         }
     }
 }
@@ -118,3 +321,11 @@ impl From<LambdaClosureEvalError> for EvalError {
 pub trait Evaluate<T> {
     fn evaluate(&self, context: &mut EvalContext) -> Result<T, EvalError>;
 }
+
+fn display_import_cycle(module_ids: &[Identifier]) -> String {
+    module_ids
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<String>>()
+        .join(" -> ")
+}
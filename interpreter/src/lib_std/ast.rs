@@ -0,0 +1,1043 @@
+use std::rc::Rc;
+
+use super::invalid_args;
+use crate::{module::Module, quasi_quote::splice_value};
+use rogato_common::{
+    ast::{
+        expression::{
+            Case, CaseArm, Expression, FnCall, FnCallArgs, IfElse, InterpolatedString, Lambda,
+            LambdaArgs, LambdaVariant, LetBindings, LetExpression, Literal, MapKVPair, Query,
+            QueryBinding, QueryBindings, QueryGuards, StringPart, StructProps, TupleItems,
+        },
+        fixity::{Associativity, Fixity, FixityDef},
+        fn_def::{FnDef, FnDefArgs, FnDefBody, FnDefVariant, FnDefVariants},
+        macro_def::MacroDef,
+        module_def::{ModuleDef, ModuleExports},
+        pattern::Pattern,
+        type_expression::{StructTypeProperties, TypeDef, TypeExpression, UnionTypeVariants},
+        use_def::{UseDef, UseImport, UseImports},
+        Identifier, Node, VarIdentifier, AST,
+    },
+    error::BoxedError,
+    native_fn::{NativeFnContext, NativeFnError},
+    val::{self, Object, Value, ValueRef},
+};
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+
+pub fn module() -> Module {
+    let mut module = Module::new("Std.AST");
+    module.export(&ModuleExports::new(vec![
+        "eval".into(),
+        "evalFresh".into(),
+        "toData".into(),
+        "fromData".into(),
+        "show".into(),
+    ]));
+
+    module.fn_def_native("eval", &["code"], move |ctx, args| {
+        match (args.len(), args.first()) {
+            (1, Some(code)) => eval(ctx, code),
+            _ => Err(invalid_args("Std.AST.eval")),
+        }
+    });
+
+    module.fn_def_native("evalFresh", &["code"], move |ctx, args| {
+        match (args.len(), args.first()) {
+            (1, Some(code)) => eval(&mut *ctx.fresh_context(), code),
+            _ => Err(invalid_args("Std.AST.evalFresh")),
+        }
+    });
+
+    module.fn_def_native("toData", &["code"], move |_ctx, args| {
+        match (args.len(), args.first()) {
+            (1, Some(code)) => match &**code {
+                Value::Quoted(expr) => Ok(expr_data(expr)),
+                Value::QuotedAST(ast) => Ok(ast_data(ast)),
+                _ => Err(invalid_args("Std.AST.toData")),
+            },
+            _ => Err(invalid_args("Std.AST.toData")),
+        }
+    });
+
+    module.fn_def_native("fromData", &["data"], move |_ctx, args| {
+        match (args.len(), args.first()) {
+            (1, Some(data)) => from_data(data),
+            _ => Err(invalid_args("Std.AST.fromData")),
+        }
+    });
+
+    module.fn_def_native("show", &["code"], move |_ctx, args| {
+        let code = match (args.len(), args.first()) {
+            (1, Some(code)) => match &**code {
+                Value::Quoted(_) | Value::QuotedAST(_) => ValueRef::clone(code),
+                _ => from_data(code)?,
+            },
+            _ => return Err(invalid_args("Std.AST.show")),
+        };
+        match &*code {
+            Value::Quoted(expr) => Ok(val::string(expr)),
+            Value::QuotedAST(ast) => Ok(val::string(ast)),
+            _ => Err(invalid_args("Std.AST.show")),
+        }
+    });
+
+    module
+}
+
+/// Evaluates a quoted expression or definition in the given context.
+pub fn eval(ctx: &mut dyn NativeFnContext, code: &ValueRef) -> Result<ValueRef, NativeFnError> {
+    match &**code {
+        Value::Quoted(expr) => ctx.evaluate_expr(expr),
+        Value::QuotedAST(ast) => ctx.evaluate_ast(ast),
+        _ => Err(invalid_args("Std.AST.eval")),
+    }
+}
+
+/// Builds a quoted expression or definition from node data as returned by [`expr_data`]
+/// or [`ast_data`]. Values that aren't nodes are spliced in as literals, like unquoted values
+/// in quoted code.
+pub fn from_data(data: &ValueRef) -> Result<ValueRef, NativeFnError> {
+    match NodeData::from_data(data) {
+        Some(node) if node.is_definition() => Ok(val::quoted_ast(Node::new(node.ast()?))),
+        _ => Ok(val::quoted(Node::new(data_expr(data)?))),
+    }
+}
+
+/// Breaks an expression into a node object tagged with its `kind`, with its child nodes and
+/// literal values as properties, e.g. `{kind: ^OpCall, op: "+", left: ..., right: ...}`.
+/// Patterns are broken into nodes the same way, see [`pattern_data`].
+pub fn expr_data(expr: &Expression) -> ValueRef {
+    match expr {
+        Expression::Commented(c, e) => node(
+            "Commented",
+            [("comment", val::string(c)), ("expr", expr_data(e))],
+        ),
+        Expression::Lit(lit) => lit_data(lit),
+        Expression::FnCall(fn_call) => node(
+            "FnCall",
+            [
+                ("id", val::string(&fn_call.id)),
+                ("args", val::list(fn_call.args.iter().map(|a| expr_data(a)))),
+            ],
+        ),
+        Expression::OpCall(op, left, right) => node(
+            "OpCall",
+            [
+                ("op", val::string(op)),
+                ("left", expr_data(left)),
+                ("right", expr_data(right)),
+            ],
+        ),
+        Expression::Var(id) => node("Var", [("id", val::string(id))]),
+        Expression::ConstOrTypeRef(id) => node("ConstOrTypeRef", [("id", val::string(id))]),
+        Expression::DBTypeRef(id) => node("DBTypeRef", [("id", val::string(id))]),
+        Expression::PropFnRef(id) => node("PropFnRef", [("id", val::string(id))]),
+        Expression::FnRef(id) => node("FnRef", [("id", val::string(id))]),
+        Expression::Symbol(id) => node("Symbol", [("id", val::string(id))]),
+        Expression::PropAccess(e, prop) => node(
+            "PropAccess",
+            [("expr", expr_data(e)), ("prop", val::string(prop))],
+        ),
+        Expression::EdgeProp(e, edge) => node(
+            "EdgeProp",
+            [("expr", expr_data(e)), ("edge", val::string(edge))],
+        ),
+        Expression::IfElse(if_else) => node(
+            "IfElse",
+            [
+                ("condition", expr_data(&if_else.condition)),
+                ("then", expr_data(&if_else.then_expr)),
+                ("else", expr_data(&if_else.else_expr)),
+            ],
+        ),
+        Expression::Case(case) => {
+            node(
+                "Case",
+                [
+                    ("scrutinee", expr_data(&case.scrutinee)),
+                    (
+                        "arms",
+                        val::list(case.arms.iter().map(|arm| {
+                            val::tuple([pattern_data(&arm.pattern), expr_data(&arm.body)])
+                        })),
+                    ),
+                ],
+            )
+        }
+        Expression::Let(let_expr) => node(
+            "Let",
+            [
+                (
+                    "bindings",
+                    val::list(
+                        let_expr
+                            .bindings
+                            .iter()
+                            .map(|(id, e)| val::tuple([val::string(id), expr_data(e)])),
+                    ),
+                ),
+                ("body", expr_data(&let_expr.body)),
+            ],
+        ),
+        Expression::Lambda(lambda) => node(
+            "Lambda",
+            [(
+                "variants",
+                val::list(lambda.variants_iter().map(|variant| {
+                    variant_data(
+                        variant.args.iter(),
+                        variant.guard.as_ref(),
+                        expr_data(&variant.body),
+                    )
+                })),
+            )],
+        ),
+        Expression::Query(query) => node(
+            "Query",
+            [
+                (
+                    "bindings",
+                    val::list(query.bindings().iter().map(|binding| {
+                        node(
+                            "QueryBinding",
+                            [
+                                ("ids", val::list(binding.ids().iter().map(val::string))),
+                                ("value", expr_data(&binding.val())),
+                                ("negated", val::bool(binding.is_negated())),
+                            ],
+                        )
+                    })),
+                ),
+                (
+                    "guards",
+                    val::list(query.guards().iter().map(|g| expr_data(g))),
+                ),
+                ("production", expr_data(query.production())),
+            ],
+        ),
+        Expression::Quoted(e) => node("Quoted", [("expr", expr_data(e))]),
+        Expression::Unquoted(e) => node("Unquoted", [("expr", expr_data(e))]),
+        Expression::QuotedAST(ast) => node("QuotedAST", [("ast", ast_data(ast))]),
+        Expression::UnquotedAST(ast) => node("UnquotedAST", [("ast", ast_data(ast))]),
+        Expression::InlineFnDef(fn_def) => fn_def_data("InlineFnDef", &fn_def.borrow()),
+    }
+}
+
+/// Breaks a definition into a node object tagged with its `kind`, like [`expr_data`].
+pub fn ast_data(ast: &AST) -> ValueRef {
+    match ast {
+        AST::RootComment(c) => node("RootComment", [("comment", val::string(c))]),
+        AST::FnDef(fn_def) => fn_def_data("FnDef", &fn_def.borrow()),
+        AST::ModuleDef(mod_def) => node(
+            "ModuleDef",
+            [
+                ("id", val::string(mod_def.id())),
+                (
+                    "exports",
+                    val::list(mod_def.exports().iter().map(val::string)),
+                ),
+            ],
+        ),
+        AST::Use(use_def) => node(
+            "Use",
+            [
+                ("id", val::string(use_def.id())),
+                ("alias", val::option(use_def.alias().map(val::string))),
+                (
+                    "imports",
+                    match use_def.imports() {
+                        UseImports::All => val::symbol("all"),
+                        UseImports::Specific(imports) => {
+                            val::list(imports.iter().map(|import| match import {
+                                UseImport::Func(id, alias) => node(
+                                    "Func",
+                                    [
+                                        ("id", val::string(id)),
+                                        ("alias", val::option(alias.as_ref().map(val::string))),
+                                    ],
+                                ),
+                                UseImport::Type(id, alias) => node(
+                                    "Type",
+                                    [
+                                        ("id", val::string(id)),
+                                        ("alias", val::option(alias.as_ref().map(val::string))),
+                                    ],
+                                ),
+                            }))
+                        }
+                    },
+                ),
+            ],
+        ),
+        AST::TypeDef(type_def) => node(
+            "TypeDef",
+            [
+                ("id", val::string(type_def.id())),
+                ("type", type_data(&type_def.type_expr())),
+            ],
+        ),
+        AST::FixityDef(fixity_def) => {
+            let fixity = fixity_def.fixity();
+            let associativity = match fixity.associativity() {
+                Associativity::Left => "left",
+                Associativity::Right => "right",
+                Associativity::None => "none",
+            };
+            node(
+                "FixityDef",
+                [
+                    ("id", val::string(fixity_def.id())),
+                    ("associativity", val::symbol(associativity)),
+                    ("precedence", val::number(fixity.precedence())),
+                ],
+            )
+        }
+        AST::MacroDef(macro_def) => node(
+            "MacroDef",
+            [
+                ("id", val::string(macro_def.id())),
+                ("args", patterns_data(macro_def.args().iter())),
+                ("body", expr_data(macro_def.body())),
+            ],
+        ),
+    }
+}
+
+/// Breaks a pattern into a node object tagged with its `kind`, like [`expr_data`].
+/// Literal patterns become `Literal` nodes, e.g. `{kind: ^Literal, value: 1}`.
+pub fn pattern_data(pattern: &Pattern) -> ValueRef {
+    match pattern {
+        Pattern::Any => node("Any", []),
+        Pattern::EmptyList => node("EmptyList", []),
+        Pattern::ListCons(head, tail) => node(
+            "ListCons",
+            [("head", pattern_data(head)), ("tail", pattern_data(tail))],
+        ),
+        Pattern::List(items) => node("List", [("items", patterns_data(items.iter()))]),
+        Pattern::Tuple(_, items) => node("Tuple", [("items", patterns_data(items.iter()))]),
+        Pattern::Map(kv_pairs) => node("Map", [("entries", pattern_entries_data(kv_pairs))]),
+        Pattern::MapCons(kv_pairs, rest) => node(
+            "MapCons",
+            [
+                ("entries", pattern_entries_data(kv_pairs)),
+                ("rest", pattern_data(rest)),
+            ],
+        ),
+        Pattern::Var(id) => node("Var", [("id", val::string(id))]),
+        Pattern::Bool(b) => node("Literal", [("value", val::bool(*b))]),
+        Pattern::Number(num) => node("Literal", [("value", val::number(*num))]),
+        Pattern::String(string) => node("Literal", [("value", val::string(string))]),
+        Pattern::Symbol(id) => node("Symbol", [("id", val::string(id))]),
+        Pattern::Constructor(id, args) => node(
+            "Constructor",
+            [
+                ("id", val::string(id)),
+                ("args", patterns_data(args.iter())),
+            ],
+        ),
+        Pattern::Struct(id, props) => node(
+            "Struct",
+            [
+                ("id", val::option(id.as_ref().map(val::string))),
+                (
+                    "props",
+                    val::list(
+                        props
+                            .iter()
+                            .map(|(id, p)| val::tuple([val::string(id), pattern_data(p)])),
+                    ),
+                ),
+            ],
+        ),
+        Pattern::As(id, p) => node(
+            "As",
+            [("id", val::string(id)), ("pattern", pattern_data(p))],
+        ),
+        Pattern::Or(alternatives) => {
+            node("Or", [("alternatives", patterns_data(alternatives.iter()))])
+        }
+    }
+}
+
+/// Breaks a type expression into a node object tagged with its `kind`, like [`expr_data`].
+pub fn type_data(type_expr: &TypeExpression) -> ValueRef {
+    match type_expr {
+        TypeExpression::BoolType => node("BoolType", []),
+        TypeExpression::NumberType => node("NumberType", []),
+        TypeExpression::StringType => node("StringType", []),
+        TypeExpression::TypeRef(id) => node("TypeRef", [("id", val::string(id))]),
+        TypeExpression::FunctionType(args, result) => node(
+            "FunctionType",
+            [
+                ("args", val::list(args.iter().map(type_data))),
+                ("result", type_data(result)),
+            ],
+        ),
+        TypeExpression::TupleType(items) => node(
+            "TupleType",
+            [("items", val::list(items.iter().map(|t| type_data(t))))],
+        ),
+        TypeExpression::ListType(item) => node("ListType", [("item", type_data(item))]),
+        TypeExpression::StructType(props) => node(
+            "StructType",
+            [(
+                "props",
+                val::list(
+                    props
+                        .iter()
+                        .map(|(id, t)| val::tuple([val::string(id), type_data(t)])),
+                ),
+            )],
+        ),
+        TypeExpression::Union(variants) => node(
+            "Union",
+            [(
+                "variants",
+                val::list(variants.iter().map(|(id, params)| {
+                    val::tuple([
+                        val::string(id),
+                        val::list(params.iter().map(|t| type_data(t))),
+                    ])
+                })),
+            )],
+        ),
+    }
+}
+
+fn fn_def_data(kind: &str, fn_def: &FnDef) -> ValueRef {
+    node(
+        kind,
+        [
+            ("id", val::string(fn_def.id())),
+            (
+                "variants",
+                val::list(
+                    fn_def
+                        .variants_iter()
+                        .map(|FnDefVariant(args, guard, body)| {
+                            let body = match &**body {
+                                FnDefBody::RogatoFn(body) => expr_data(body),
+                                FnDefBody::NativeFn(_) => node("NativeFn", []),
+                            };
+                            variant_data(args.iter(), guard.as_ref(), body)
+                        }),
+                ),
+            ),
+        ],
+    )
+}
+
+fn variant_data<'a>(
+    args: impl Iterator<Item = &'a Node<Pattern>>,
+    guard: Option<&Node<Expression>>,
+    body: ValueRef,
+) -> ValueRef {
+    node(
+        "Variant",
+        [
+            ("args", patterns_data(args)),
+            ("guard", val::option(guard.map(|g| expr_data(g)))),
+            ("body", body),
+        ],
+    )
+}
+
+fn patterns_data<'a>(patterns: impl Iterator<Item = &'a Node<Pattern>>) -> ValueRef {
+    val::list(patterns.map(|p| pattern_data(p)))
+}
+
+fn pattern_entries_data(kv_pairs: &TupleItems<MapKVPair<Pattern>>) -> ValueRef {
+    val::list(
+        kv_pairs
+            .iter()
+            .map(|kv| val::tuple([pattern_data(&kv.key), pattern_data(&kv.value)])),
+    )
+}
+
+fn lit_data(lit: &Literal) -> ValueRef {
+    match lit {
+        Literal::Bool(b) => node("Literal", [("value", val::bool(*b))]),
+        Literal::Number(num) => node("Literal", [("value", val::number(*num))]),
+        Literal::String(string) => node("Literal", [("value", val::string(string))]),
+        Literal::InterpolatedString(string) => node(
+            "InterpolatedString",
+            [(
+                "parts",
+                val::list(string.iter().map(|part| match part {
+                    StringPart::Lit(s) => val::string(s),
+                    StringPart::Expr(e) => expr_data(e),
+                })),
+            )],
+        ),
+        Literal::Tuple(items) => node("Tuple", [("items", list_data(items))]),
+        Literal::List(items) => node("List", [("items", list_data(items))]),
+        Literal::ListCons(head, tail) => node(
+            "ListCons",
+            [("head", expr_data(head)), ("tail", expr_data(tail))],
+        ),
+        Literal::Struct(id, props) => node(
+            "Struct",
+            [("id", val::string(id)), ("props", props_data(props))],
+        ),
+        Literal::StructUpdate(base, props) => node(
+            "StructUpdate",
+            [("base", expr_data(base)), ("props", props_data(props))],
+        ),
+        Literal::Map(kv_pairs) => node("Map", [("entries", entries_data(kv_pairs))]),
+        Literal::MapCons(kv_pairs, rest) => node(
+            "MapCons",
+            [
+                ("entries", entries_data(kv_pairs)),
+                ("rest", expr_data(rest)),
+            ],
+        ),
+    }
+}
+
+fn list_data(items: &TupleItems<Expression>) -> ValueRef {
+    val::list(items.iter().map(|item| expr_data(item)))
+}
+
+fn props_data(props: &StructProps) -> ValueRef {
+    val::list(
+        props
+            .iter()
+            .map(|(id, e)| val::tuple([val::string(id), expr_data(e)])),
+    )
+}
+
+fn entries_data(kv_pairs: &TupleItems<MapKVPair<Expression>>) -> ValueRef {
+    val::list(
+        kv_pairs
+            .iter()
+            .map(|kv| val::tuple([expr_data(&kv.key), expr_data(&kv.value)])),
+    )
+}
+
+fn node<const N: usize>(kind: &str, props: [(&str, ValueRef); N]) -> ValueRef {
+    val::object([("kind", val::symbol(kind))].into_iter().chain(props))
+}
+
+fn data_expr(data: &ValueRef) -> Result<Expression, NativeFnError> {
+    match NodeData::from_data(data) {
+        Some(node) => node.expr(),
+        None => splice_data(data).map(Node::unwrap_or_clone),
+    }
+}
+
+fn data_pattern(data: &ValueRef) -> Result<Pattern, NativeFnError> {
+    match NodeData::from_data(data) {
+        Some(node) => node.pattern(),
+        None => literal_pattern(data).ok_or_else(|| invalid_node(data)),
+    }
+}
+
+fn data_type(data: &ValueRef) -> Result<TypeExpression, NativeFnError> {
+    NodeData::expect(data)?.type_expr()
+}
+
+fn data_ast(data: &ValueRef) -> Result<AST, NativeFnError> {
+    match &**data {
+        Value::QuotedAST(ast) => Ok((**ast).clone()),
+        _ => NodeData::expect(data)?.ast(),
+    }
+}
+
+fn literal_pattern(value: &ValueRef) -> Option<Pattern> {
+    match &**value {
+        Value::Bool(b) => Some(Pattern::Bool(*b)),
+        Value::Number(num) => Some(Pattern::Number(*num)),
+        Value::String(string) => Some(Pattern::String(string.clone())),
+        Value::Symbol(id) => Some(Pattern::Symbol(id.clone())),
+        _ => None,
+    }
+}
+
+fn identifier(value: &ValueRef) -> Option<Identifier> {
+    match &**value {
+        Value::String(id) => Some(id.into()),
+        Value::Symbol(id) => Some(id.clone()),
+        _ => None,
+    }
+}
+
+type VariantParts = (
+    Vec<Node<Pattern>>,
+    Option<Node<Expression>>,
+    Node<Expression>,
+);
+
+/// A node object given either as an object or as a map with string or symbol keys,
+/// for building code from its properties.
+struct NodeData<'a> {
+    data: &'a ValueRef,
+    kind: String,
+    props: Object,
+}
+
+impl<'a> NodeData<'a> {
+    fn from_data(data: &'a ValueRef) -> Option<NodeData<'a>> {
+        let props = match &**data {
+            Value::Object(object) => object.clone(),
+            Value::Map(map) => map
+                .iter()
+                .map(|(key, value)| match &**key {
+                    Value::String(key) => Some((key.clone(), ValueRef::clone(value))),
+                    Value::Symbol(key) => Some((key.to_string(), ValueRef::clone(value))),
+                    _ => None,
+                })
+                .collect::<Option<Object>>()?,
+            _ => return None,
+        };
+        let kind = match props.get("kind").map(|kind| &**kind) {
+            Some(Value::Symbol(kind)) => kind.to_string(),
+            Some(Value::String(kind)) => kind.clone(),
+            _ => return None,
+        };
+        Some(NodeData { data, kind, props })
+    }
+
+    fn expect(data: &'a ValueRef) -> Result<NodeData<'a>, NativeFnError> {
+        NodeData::from_data(data).ok_or_else(|| invalid_node(data))
+    }
+
+    fn is_definition(&self) -> bool {
+        matches!(
+            self.kind.as_str(),
+            "RootComment" | "FnDef" | "ModuleDef" | "Use" | "TypeDef" | "FixityDef" | "MacroDef"
+        )
+    }
+
+    fn invalid(&self) -> NativeFnError {
+        invalid_node(self.data)
+    }
+
+    fn get(&self, prop: &str) -> Result<&ValueRef, NativeFnError> {
+        self.props.get(prop).ok_or_else(|| self.invalid())
+    }
+
+    /// An optional property, given as an option or left out.
+    fn get_opt(&self, prop: &str) -> Option<&ValueRef> {
+        match self.props.get(prop) {
+            Some(value) => match &**value {
+                Value::Option(value) => value.as_ref(),
+                _ => Some(value),
+            },
+            None => None,
+        }
+    }
+
+    fn id(&self, prop: &str) -> Result<Identifier, NativeFnError> {
+        identifier(self.get(prop)?).ok_or_else(|| self.invalid())
+    }
+
+    fn opt_id(&self, prop: &str) -> Result<Option<Identifier>, NativeFnError> {
+        self.get_opt(prop)
+            .map(|id| identifier(id).ok_or_else(|| self.invalid()))
+            .transpose()
+    }
+
+    fn ids(&self, prop: &str) -> Result<Vec<Identifier>, NativeFnError> {
+        self.items(prop)?
+            .iter()
+            .map(|id| identifier(id).ok_or_else(|| self.invalid()))
+            .collect()
+    }
+
+    fn number(&self, prop: &str) -> Result<Decimal, NativeFnError> {
+        match &**self.get(prop)? {
+            Value::Number(num) => Ok(*num),
+            _ => Err(self.invalid()),
+        }
+    }
+
+    fn items(&self, prop: &str) -> Result<Vec<ValueRef>, NativeFnError> {
+        match &**self.get(prop)? {
+            Value::List(items) => Ok(items.iter().map(ValueRef::clone).collect()),
+            _ => Err(self.invalid()),
+        }
+    }
+
+    fn pairs(&self, prop: &str) -> Result<Vec<(ValueRef, ValueRef)>, NativeFnError> {
+        self.items(prop)?
+            .iter()
+            .map(|item| match &**item {
+                Value::Tuple(2, pair) => Ok((ValueRef::clone(&pair[0]), ValueRef::clone(&pair[1]))),
+                _ => Err(self.invalid()),
+            })
+            .collect()
+    }
+
+    fn child(&self, prop: &str) -> Result<Node<Expression>, NativeFnError> {
+        data_expr(self.get(prop)?).map(Node::new)
+    }
+
+    fn opt_child(&self, prop: &str) -> Result<Option<Node<Expression>>, NativeFnError> {
+        self.get_opt(prop)
+            .map(|child| data_expr(child).map(Node::new))
+            .transpose()
+    }
+
+    fn children(&self, prop: &str) -> Result<Vec<Node<Expression>>, NativeFnError> {
+        self.items(prop)?
+            .iter()
+            .map(|item| data_expr(item).map(Node::new))
+            .collect()
+    }
+
+    fn child_pattern(&self, prop: &str) -> Result<Node<Pattern>, NativeFnError> {
+        data_pattern(self.get(prop)?).map(Node::new)
+    }
+
+    fn child_patterns(&self, prop: &str) -> Result<Vec<Node<Pattern>>, NativeFnError> {
+        self.items(prop)?
+            .iter()
+            .map(|item| data_pattern(item).map(Node::new))
+            .collect()
+    }
+
+    fn child_type(&self, prop: &str) -> Result<Node<TypeExpression>, NativeFnError> {
+        data_type(self.get(prop)?).map(Node::new)
+    }
+
+    fn child_types(&self, prop: &str) -> Result<Vec<Node<TypeExpression>>, NativeFnError> {
+        self.items(prop)?
+            .iter()
+            .map(|item| data_type(item).map(Node::new))
+            .collect()
+    }
+
+    fn struct_props(&self, prop: &str) -> Result<Rc<StructProps>, NativeFnError> {
+        let mut props = Vec::new();
+        for (id, value) in self.pairs(prop)? {
+            let id = identifier(&id).ok_or_else(|| self.invalid())?;
+            props.push((id, Node::new(data_expr(&value)?)))
+        }
+        Ok(Rc::new(StructProps::from(props)))
+    }
+
+    fn entries(&self, prop: &str) -> Result<TupleItems<MapKVPair<Expression>>, NativeFnError> {
+        let mut kv_pairs = Vec::new();
+        for (key, value) in self.pairs(prop)? {
+            kv_pairs.push(Node::new(MapKVPair::new(
+                Node::new(data_expr(&key)?),
+                Node::new(data_expr(&value)?),
+            )))
+        }
+        Ok(TupleItems::from(kv_pairs))
+    }
+
+    fn pattern_entries(&self, prop: &str) -> Result<TupleItems<MapKVPair<Pattern>>, NativeFnError> {
+        let mut kv_pairs = Vec::new();
+        for (key, value) in self.pairs(prop)? {
+            kv_pairs.push(Node::new(MapKVPair::new(
+                Node::new(data_pattern(&key)?),
+                Node::new(data_pattern(&value)?),
+            )))
+        }
+        Ok(TupleItems::from(kv_pairs))
+    }
+
+    /// The `variants` of a fn definition or lambda as `(args, guard, body)`.
+    fn variants(&self) -> Result<Vec<VariantParts>, NativeFnError> {
+        let mut variants = Vec::new();
+        for variant in self.items("variants")? {
+            let variant = NodeData::expect(&variant)?;
+            variants.push((
+                variant.child_patterns("args")?,
+                variant.opt_child("guard")?,
+                variant.child("body")?,
+            ))
+        }
+        Ok(variants)
+    }
+
+    fn fn_def_variants(&self) -> Result<FnDefVariants, NativeFnError> {
+        Ok(FnDefVariants::new(
+            self.variants()?
+                .into_iter()
+                .map(|(args, guard, body)| {
+                    FnDefVariant(
+                        FnDefArgs::new(args),
+                        guard,
+                        Rc::new(FnDefBody::rogato(body)),
+                    )
+                })
+                .collect::<Vec<_>>(),
+        ))
+    }
+
+    fn expr(&self) -> Result<Expression, NativeFnError> {
+        let expr = match self.kind.as_str() {
+            "Commented" => {
+                Expression::Commented(self.id("comment")?.to_string(), self.child("expr")?)
+            }
+            "Literal" => Node::unwrap_or_clone(splice_data(self.get("value")?)?),
+            "InterpolatedString" => {
+                let mut parts = Vec::new();
+                for part in self.items("parts")? {
+                    parts.push(match &*part {
+                        Value::String(s) => StringPart::Lit(s.clone()),
+                        _ => StringPart::Expr(Node::new(data_expr(&part)?)),
+                    })
+                }
+                Expression::Lit(Literal::InterpolatedString(InterpolatedString::new(parts)))
+            }
+            "Tuple" => Expression::Lit(Literal::Tuple(TupleItems::from(self.children("items")?))),
+            "List" => Expression::Lit(Literal::List(TupleItems::from(self.children("items")?))),
+            "ListCons" => {
+                Expression::Lit(Literal::ListCons(self.child("head")?, self.child("tail")?))
+            }
+            "Struct" => {
+                Expression::Lit(Literal::Struct(self.id("id")?, self.struct_props("props")?))
+            }
+            "StructUpdate" => Expression::Lit(Literal::StructUpdate(
+                self.child("base")?,
+                self.struct_props("props")?,
+            )),
+            "Map" => Expression::Lit(Literal::Map(self.entries("entries")?)),
+            "MapCons" => Expression::Lit(Literal::MapCons(
+                self.entries("entries")?,
+                self.child("rest")?,
+            )),
+            "FnCall" => Expression::FnCall(FnCall::new(
+                self.id("id")?,
+                FnCallArgs::new(self.children("args")?),
+            )),
+            "OpCall" => {
+                Expression::OpCall(self.id("op")?, self.child("left")?, self.child("right")?)
+            }
+            "Var" => Expression::Var(self.id("id")?.into()),
+            "ConstOrTypeRef" => Expression::ConstOrTypeRef(self.id("id")?),
+            "DBTypeRef" => Expression::DBTypeRef(self.id("id")?),
+            "PropFnRef" => Expression::PropFnRef(self.id("id")?),
+            "FnRef" => Expression::FnRef(self.id("id")?),
+            "Symbol" => Expression::Symbol(self.id("id")?),
+            "PropAccess" => Expression::PropAccess(self.child("expr")?, self.id("prop")?),
+            "EdgeProp" => Expression::EdgeProp(self.child("expr")?, self.id("edge")?),
+            "IfElse" => Expression::IfElse(IfElse::new(
+                self.child("condition")?,
+                self.child("then")?,
+                self.child("else")?,
+            )),
+            "Case" => {
+                let mut arms = Vec::new();
+                for (pattern, body) in self.pairs("arms")? {
+                    arms.push(CaseArm::new(
+                        Node::new(data_pattern(&pattern)?),
+                        Node::new(data_expr(&body)?),
+                    ))
+                }
+                Expression::Case(Case::new(self.child("scrutinee")?, arms))
+            }
+            "Let" => {
+                let mut bindings = Vec::new();
+                for (id, value) in self.pairs("bindings")? {
+                    let id = match &*id {
+                        Value::String(id) => id.as_str().into(),
+                        _ => return Err(self.invalid()),
+                    };
+                    bindings.push((id, Node::new(data_expr(&value)?)))
+                }
+                Expression::Let(LetExpression::new(
+                    LetBindings::new(bindings),
+                    self.child("body")?,
+                ))
+            }
+            "Lambda" => Expression::Lambda(Rc::new(Lambda::new(
+                self.variants()?
+                    .into_iter()
+                    .map(|(args, guard, body)| {
+                        Rc::new(LambdaVariant::guarded(LambdaArgs::new(args), guard, body))
+                    })
+                    .collect(),
+            ))),
+            "Query" => {
+                let mut bindings = Vec::new();
+                for binding in self.items("bindings")? {
+                    let binding = NodeData::expect(&binding)?;
+                    let ids = binding
+                        .ids("ids")?
+                        .into_iter()
+                        .map(VarIdentifier::new)
+                        .collect();
+                    let value = binding.child("value")?;
+                    bindings.push(match &**binding.get("negated")? {
+                        Value::Bool(true) => QueryBinding::new_negated(ids, value),
+                        Value::Bool(false) => QueryBinding::new(ids, value),
+                        _ => return Err(binding.invalid()),
+                    })
+                }
+                Expression::Query(Query::new(
+                    QueryBindings::new(bindings),
+                    QueryGuards::new(self.children("guards")?),
+                    self.child("production")?,
+                ))
+            }
+            "Quoted" => Expression::Quoted(self.child("expr")?),
+            "Unquoted" => Expression::Unquoted(self.child("expr")?),
+            "QuotedAST" => Expression::QuotedAST(Node::new(data_ast(self.get("ast")?)?)),
+            "UnquotedAST" => Expression::UnquotedAST(Node::new(data_ast(self.get("ast")?)?)),
+            "InlineFnDef" => Expression::InlineFnDef(FnDef::new_inline_with_variants(
+                self.id("id")?,
+                self.fn_def_variants()?,
+            )),
+            _ => return Err(self.invalid()),
+        };
+        Ok(expr)
+    }
+
+    fn pattern(&self) -> Result<Pattern, NativeFnError> {
+        let pattern = match self.kind.as_str() {
+            "Any" => Pattern::Any,
+            "EmptyList" => Pattern::EmptyList,
+            "ListCons" => {
+                Pattern::ListCons(self.child_pattern("head")?, self.child_pattern("tail")?)
+            }
+            "List" => Pattern::List(TupleItems::from(self.child_patterns("items")?)),
+            "Tuple" => {
+                let items = self.child_patterns("items")?;
+                Pattern::Tuple(items.len(), TupleItems::from(items))
+            }
+            "Map" => Pattern::Map(self.pattern_entries("entries")?),
+            "MapCons" => Pattern::MapCons(
+                self.pattern_entries("entries")?,
+                self.child_pattern("rest")?,
+            ),
+            "Var" => Pattern::Var(self.id("id")?.into()),
+            "Literal" => literal_pattern(self.get("value")?).ok_or_else(|| self.invalid())?,
+            "Symbol" => Pattern::Symbol(self.id("id")?),
+            "Constructor" => Pattern::Constructor(
+                self.id("id")?,
+                TupleItems::from(self.child_patterns("args")?),
+            ),
+            "Struct" => {
+                let mut props = Vec::new();
+                for (id, pattern) in self.pairs("props")? {
+                    let id = identifier(&id).ok_or_else(|| self.invalid())?;
+                    props.push((id, Node::new(data_pattern(&pattern)?)))
+                }
+                Pattern::Struct(self.opt_id("id")?, props)
+            }
+            "As" => Pattern::As(self.id("id")?.into(), self.child_pattern("pattern")?),
+            "Or" => Pattern::Or(TupleItems::from(self.child_patterns("alternatives")?)),
+            _ => return Err(self.invalid()),
+        };
+        Ok(pattern)
+    }
+
+    fn type_expr(&self) -> Result<TypeExpression, NativeFnError> {
+        let type_expr = match self.kind.as_str() {
+            "BoolType" => TypeExpression::BoolType,
+            "NumberType" => TypeExpression::NumberType,
+            "StringType" => TypeExpression::StringType,
+            "TypeRef" => TypeExpression::TypeRef(self.id("id")?),
+            "FunctionType" => TypeExpression::FunctionType(
+                LambdaArgs::new(
+                    self.items("args")?
+                        .iter()
+                        .map(data_type)
+                        .collect::<Result<_, _>>()?,
+                ),
+                self.child_type("result")?,
+            ),
+            "TupleType" => TypeExpression::TupleType(TupleItems::from(self.child_types("items")?)),
+            "ListType" => TypeExpression::ListType(self.child_type("item")?),
+            "StructType" => {
+                let mut props = Vec::new();
+                for (id, type_expr) in self.pairs("props")? {
+                    let id = identifier(&id).ok_or_else(|| self.invalid())?;
+                    props.push((id, Node::new(data_type(&type_expr)?)))
+                }
+                TypeExpression::StructType(StructTypeProperties::new(props))
+            }
+            "Union" => {
+                let mut variants = Vec::new();
+                for (id, params) in self.pairs("variants")? {
+                    let id = identifier(&id).ok_or_else(|| self.invalid())?;
+                    let params = match &*params {
+                        Value::List(params) => params
+                            .iter()
+                            .map(|param| data_type(param).map(Node::new))
+                            .collect::<Result<Vec<_>, _>>()?,
+                        _ => return Err(self.invalid()),
+                    };
+                    variants.push((id, params))
+                }
+                TypeExpression::Union(UnionTypeVariants::new(variants))
+            }
+            _ => return Err(self.invalid()),
+        };
+        Ok(type_expr)
+    }
+
+    fn ast(&self) -> Result<AST, NativeFnError> {
+        let ast = match self.kind.as_str() {
+            "RootComment" => AST::RootComment(self.id("comment")?.to_string()),
+            "FnDef" => AST::FnDef(FnDef::new_with_variants(
+                self.id("id")?,
+                self.fn_def_variants()?,
+            )),
+            "ModuleDef" => AST::ModuleDef(ModuleDef::new(
+                self.id("id")?,
+                ModuleExports::new(self.ids("exports")?),
+            )),
+            "Use" => {
+                let imports = match &**self.get("imports")? {
+                    Value::Symbol(all) if all == "all" => UseImports::All,
+                    _ => {
+                        let mut imports = Vec::new();
+                        for import in self.items("imports")? {
+                            let import = NodeData::expect(&import)?;
+                            let (id, alias) = (import.id("id")?, import.opt_id("alias")?);
+                            imports.push(match import.kind.as_str() {
+                                "Func" => UseImport::Func(id, alias),
+                                "Type" => UseImport::Type(id, alias),
+                                _ => return Err(import.invalid()),
+                            })
+                        }
+                        UseImports::Specific(imports)
+                    }
+                };
+                AST::Use(UseDef::new(self.id("id")?, self.opt_id("alias")?, imports))
+            }
+            "TypeDef" => AST::TypeDef(TypeDef::new(self.id("id")?, self.child_type("type")?)),
+            "FixityDef" => {
+                let associativity = match self.id("associativity")?.as_str() {
+                    "left" => Associativity::Left,
+                    "right" => Associativity::Right,
+                    "none" => Associativity::None,
+                    _ => return Err(self.invalid()),
+                };
+                let precedence = self
+                    .number("precedence")?
+                    .to_u8()
+                    .filter(|precedence| *precedence <= 9)
+                    .ok_or_else(|| self.invalid())?;
+                AST::FixityDef(FixityDef::new(
+                    self.id("id")?,
+                    Fixity::new(associativity, precedence),
+                ))
+            }
+            "MacroDef" => AST::MacroDef(MacroDef::new(
+                self.id("id")?,
+                FnDefArgs::new(self.child_patterns("args")?),
+                self.child("body")?,
+            )),
+            _ => return Err(self.invalid()),
+        };
+        Ok(ast)
+    }
+}
+
+fn splice_data(data: &ValueRef) -> Result<Node<Expression>, NativeFnError> {
+    splice_value(data)
+        .map_err(|e| NativeFnError::EvaluationFailed("Std.AST.fromData".into(), BoxedError::new(e)))
+}
+
+fn invalid_node(data: &ValueRef) -> NativeFnError {
+    NativeFnError::Unknown(
+        "Std.AST.fromData".into(),
+        format!("Invalid AST node: {data}"),
+    )
+}
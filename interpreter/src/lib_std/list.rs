@@ -5,7 +5,7 @@ use rogato_common::{
     native_fn::NativeFnError,
     val::{self, Value, ValueRef},
 };
-use std::{collections::HashMap, rc::Rc};
+use std::collections::HashMap;
 
 pub fn module() -> Module {
     let mut module = Module::new("Std.List");
@@ -57,23 +57,22 @@ pub fn module() -> Module {
                             match context.call_function(fn_id, &[ValueRef::clone(item)]) {
                                 Some(val) => result.push(ValueRef::clone(&val?)),
                                 None => {
-                                    return Err(NativeFnError::EvaluationFailed(
+                                    return Err(NativeFnError::FunctionRefInvalid(
+                                        "Std.List.map".into(),
                                         fn_id.clone(),
-                                        format!("FunctionRef invalid in ^map: ^{fn_id}"),
                                     ))
                                 }
                             }
                         }
                         Ok(val::list(result))
                     }
-                    (Value::List(items), Value::Lambda(lambda_ctx, lambda)) => {
+                    (
+                        Value::List(items),
+                        Value::Lambda(..) | Value::FnRef(..) | Value::Partial(..),
+                    ) => {
                         let mut result: Vec<ValueRef> = Vec::with_capacity(items.len());
                         for item in items.iter() {
-                            let val = context.call_lambda(
-                                Rc::clone(lambda_ctx),
-                                lambda,
-                                &[ValueRef::clone(item)],
-                            )?;
+                            let val = context.call_value(b, &[ValueRef::clone(item)])?;
                             result.push(val)
                         }
                         Ok(val::list(result))
@@ -158,7 +157,7 @@ pub fn module() -> Module {
                 (2, Some(a), Some(b)) => match (&**a, &**b) {
                     (Value::List(items), Value::Number(chunk_size)) => {
                         if chunk_size.is_zero() {
-                            return Err(NativeFnError::EvaluationFailed(
+                            return Err(NativeFnError::Unknown(
                                 "Std.List.inChunksOf".into(),
                                 "chunkSize must be greater than 0".into(),
                             ));
@@ -191,9 +190,9 @@ pub fn module() -> Module {
                             let key = match ctx.call_function(fn_id, &[ValueRef::clone(item)]) {
                                 Some(val) => val?,
                                 None => {
-                                    return Err(NativeFnError::EvaluationFailed(
+                                    return Err(NativeFnError::FunctionRefInvalid(
+                                        "Std.List.countByGroups".into(),
                                         fn_id.clone(),
-                                        format!("FunctionRef invalid in ^countByGroups: ^{fn_id}"),
                                     ))
                                 }
                             };
@@ -207,14 +206,13 @@ pub fn module() -> Module {
                                 .map(|(k, v)| (ValueRef::clone(k), val::number(*v))),
                         ))
                     }
-                    (Value::List(items), Value::Lambda(lambda_ctx, lambda)) => {
+                    (
+                        Value::List(items),
+                        Value::Lambda(..) | Value::FnRef(..) | Value::Partial(..),
+                    ) => {
                         let mut result: HashMap<ValueRef, usize> = HashMap::new();
                         for item in items.iter() {
-                            let key = ctx.call_lambda(
-                                Rc::clone(lambda_ctx),
-                                lambda,
-                                &[ValueRef::clone(item)],
-                            )?;
+                            let key = ctx.call_value(b, &[ValueRef::clone(item)])?;
                             let count = result.entry(key).or_insert(0);
                             *count += 1;
                         }
@@ -324,25 +322,22 @@ pub fn module() -> Module {
                                 match ctx.call_function(fn_id, &[result, ValueRef::clone(item)]) {
                                     Some(val) => ValueRef::clone(&val?),
                                     None => {
-                                        return Err(NativeFnError::EvaluationFailed(
+                                        return Err(NativeFnError::FunctionRefInvalid(
+                                            "Std.List.reduceRight".into(),
                                             fn_id.clone(),
-                                            format!(
-                                        "FunctionRef invalid in ^Std.List.reduceRight: ^{fn_id}"
-                                    ),
                                         ))
                                     }
                                 }
                         }
                         Ok(result)
                     }
-                    (Value::List(items), Value::Lambda(lambda_ctx, lambda)) => {
+                    (
+                        Value::List(items),
+                        Value::Lambda(..) | Value::FnRef(..) | Value::Partial(..),
+                    ) => {
                         let mut result = ValueRef::clone(initial);
                         for item in items.reverse().iter() {
-                            result = ctx.call_lambda(
-                                Rc::clone(lambda_ctx),
-                                lambda,
-                                &[result, ValueRef::clone(item)],
-                            )?;
+                            result = ctx.call_value(fn_val, &[result, ValueRef::clone(item)])?;
                         }
                         Ok(result)
                     }
@@ -393,25 +388,22 @@ pub fn module() -> Module {
                                     _ => return error,
                                 },
                                 None => {
-                                    return Err(NativeFnError::EvaluationFailed(
+                                    return Err(NativeFnError::FunctionRefInvalid(
+                                        "Std.List.flatMap".into(),
                                         fn_id.clone(),
-                                        format!(
-                                            "FunctionRef invalid in ^Std.List.flatMap: ^{fn_id}"
-                                        ),
                                     ))
                                 }
                             }
                         }
                         Ok(val::list(result))
                     }
-                    (Value::List(items), Value::Lambda(lambda_ctx, lambda)) => {
+                    (
+                        Value::List(items),
+                        Value::Lambda(..) | Value::FnRef(..) | Value::Partial(..),
+                    ) => {
                         let mut result: Vec<ValueRef> = Vec::new();
                         for item in items.iter() {
-                            match &*ctx.call_lambda(
-                                Rc::clone(lambda_ctx),
-                                lambda,
-                                &[ValueRef::clone(item)],
-                            )? {
+                            match &*ctx.call_value(fn_val, &[ValueRef::clone(item)])? {
                                 Value::List(items) => result.extend(items.iter().cloned()),
                                 _ => return error,
                             }
@@ -447,13 +439,12 @@ pub fn module() -> Module {
             let error = Err(invalid_args("Std.List.findIndex"));
             match (args.len(), args.get(0), args.get(1)) {
                 (2, Some(a), Some(item)) => match (&**a, &**item) {
-                    (Value::List(items), Value::Lambda(lambda_ctx, lambda)) => {
-                        for (index, item) in items.iter().enumerate() {
-                            match &*ctx.call_lambda(
-                                Rc::clone(lambda_ctx),
-                                lambda,
-                                &[ValueRef::clone(item)],
-                            )? {
+                    (
+                        Value::List(items),
+                        Value::Lambda(..) | Value::FnRef(..) | Value::Partial(..),
+                    ) => {
+                        for (index, list_item) in items.iter().enumerate() {
+                            match &*ctx.call_value(item, &[ValueRef::clone(list_item)])? {
                                 Value::Bool(true) => return Ok(val::number(index)),
                                 Value::Bool(false) => {}
                                 _ => continue,
@@ -484,15 +475,14 @@ pub fn module() -> Module {
             let error = Err(invalid_args("Std.List.findLastIndex"));
             match (args.len(), args.get(0), args.get(1)) {
                 (2, Some(a), Some(item)) => match (&**a, &**item) {
-                    (Value::List(items), Value::Lambda(lambda_ctx, lambda)) => {
+                    (
+                        Value::List(items),
+                        Value::Lambda(..) | Value::FnRef(..) | Value::Partial(..),
+                    ) => {
                         let mut index = items.len();
-                        for item in items.reverse().iter() {
+                        for list_item in items.reverse().iter() {
                             index -= 1;
-                            match &*ctx.call_lambda(
-                                Rc::clone(lambda_ctx),
-                                lambda,
-                                &[ValueRef::clone(item)],
-                            )? {
+                            match &*ctx.call_value(item, &[ValueRef::clone(list_item)])? {
                                 Value::Bool(true) => return Ok(val::number(index)),
                                 Value::Bool(false) => {}
                                 _ => continue,
@@ -1,5 +1,3 @@
-use std::rc::Rc;
-
 use crate::module::Module;
 use rogato_common::{
     ast::module_def::ModuleExports,
@@ -78,9 +76,12 @@ pub fn module() -> Module {
                 args.get(3),
             ) {
                 (4, Some(map), Some(key), Some(value), Some(func)) => match (&**map, &**func) {
-                    (Value::Map(map), Value::Lambda(lambda_ctx, lambda)) => match map.get(key) {
+                    (
+                        Value::Map(map),
+                        Value::Lambda(..) | Value::FnRef(..) | Value::Partial(..),
+                    ) => match map.get(key) {
                         Some(value) => {
-                            let value = ctx.call_lambda(Rc::clone(lambda_ctx), lambda, &[value])?;
+                            let value = ctx.call_value(func, &[value])?;
 
                             Ok(map.insert(ValueRef::clone(key), value).into())
                         }
@@ -172,9 +173,12 @@ pub fn module() -> Module {
 
             match (args.len(), args.get(0), args.get(1), args.get(2)) {
                 (3, Some(map), Some(key), Some(default_fn)) => match (&**map, &**default_fn) {
-                    (Value::Map(map), Value::Lambda(lambda_ctx, lambda)) => match map.get(key) {
+                    (
+                        Value::Map(map),
+                        Value::Lambda(..) | Value::FnRef(..) | Value::Partial(..),
+                    ) => match map.get(key) {
                         Some(value) => Ok(value),
-                        None => ctx.call_lambda(Rc::clone(lambda_ctx), lambda, &[]),
+                        None => ctx.call_value(default_fn, &[]),
                     },
                     (Value::Map(map), Value::Symbol(fn_id)) => match map.get(key) {
                         Some(value) => Ok(value),
@@ -197,15 +201,12 @@ pub fn module() -> Module {
 
         match (args.len(), args.get(0), args.get(1)) {
             (2, Some(map), Some(func)) => match (&**map, &**func) {
-                (Value::Map(map), Value::Lambda(lambda_ctx, lambda)) => {
+                (Value::Map(map), Value::Lambda(..) | Value::FnRef(..) | Value::Partial(..)) => {
                     let mut new_map = val::Map::new();
 
                     for (key, value) in map.iter() {
-                        let result = ctx.call_lambda(
-                            Rc::clone(lambda_ctx),
-                            lambda,
-                            &[ValueRef::clone(key), ValueRef::clone(value)],
-                        )?;
+                        let result =
+                            ctx.call_value(func, &[ValueRef::clone(key), ValueRef::clone(value)])?;
 
                         if result.is_truthy() {
                             new_map = new_map.insert(ValueRef::clone(key), ValueRef::clone(value));
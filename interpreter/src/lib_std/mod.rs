@@ -1,6 +1,8 @@
 use crate::{
     environment::{Environment, Imports},
     module::Module,
+    module_loader::ModuleLoader,
+    EvalContext, EvalError,
 };
 use rand::Rng;
 use rogato_common::{
@@ -8,17 +10,22 @@ use rogato_common::{
         expression::FnDefArgs,
         fn_def::{FnDefBody, FnDefVariant},
         module_def::ModuleExports,
+        Identifier, Node, Program,
     },
     native_fn::{NativeFn, NativeFnError},
     val::{self, List},
     val::{Value, ValueRef},
 };
+use rogato_parser::{parse_file, ParserContext};
 use rust_decimal::{prelude::ToPrimitive, Decimal};
 use rust_decimal_macros::dec;
+use std::cell::OnceCell;
 use std::fmt::Debug;
 use std::ops::Deref;
+use std::path::PathBuf;
 use std::rc::Rc;
 
+pub mod ast;
 pub mod list;
 pub mod map;
 pub mod math;
@@ -26,16 +33,23 @@ pub mod set;
 pub mod string;
 pub mod symbol;
 
-pub fn env() -> Environment {
+/// A new environment with the std lib loaded. Fails if the std lib sources can't be
+/// read, parsed or evaluated, e.g. when loaded from `STD_LIB_DIR_ENV_VAR`.
+pub fn env() -> Result<Environment, EvalError> {
     let mut env = Environment::new();
 
-    let std_mod = std_module();
+    let mut std_mod = std_module();
     let math_mod = math::module();
     let string_mod = string::module();
     let list_mod = list::module();
     let map_mod = map::module();
     let symbol_mod = symbol::module();
     let set_mod = set::module();
+    let ast_mod = ast::module();
+
+    if let Some(eval) = ast_mod.lookup_fn(&"eval".into()) {
+        std_mod.fn_alias("eval", eval);
+    }
 
     env.import(&std_mod, Imports::All);
     env.import(&math_mod, Imports::All);
@@ -46,6 +60,7 @@ pub fn env() -> Environment {
     env.alias_module(&map_mod, "Map");
     env.alias_module(&symbol_mod, "Symbol");
     env.alias_module(&set_mod, "Set");
+    env.alias_module(&ast_mod, "AST");
 
     env.define_module(std_mod);
     env.define_module(math_mod);
@@ -54,8 +69,101 @@ pub fn env() -> Environment {
     env.define_module(map_mod);
     env.define_module(symbol_mod);
     env.define_module(set_mod);
+    env.define_module(ast_mod);
+
+    load_std_lib(&env)?;
+
+    Ok(env)
+}
+
+/// Environment variable pointing to a directory with the std lib's `.roga` sources.
+/// If set, they're loaded from there instead of the versions embedded at build time,
+/// which is useful when working on the std lib itself.
+pub const STD_LIB_DIR_ENV_VAR: &str = "ROGATO_STD_LIB_DIR";
+
+/// The parts of the std lib written in rogātō, in load order.
+pub const STD_LIB_SOURCES: [(&str, &str); 4] = [
+    ("Std", include_str!("../../lib/Std.roga")),
+    ("Std.List", include_str!("../../lib/Std/List.roga")),
+    ("Std.Map", include_str!("../../lib/Std/Map.roga")),
+    ("Std.Result", include_str!("../../lib/Std/Result.roga")),
+];
+
+/// A parsed std lib source file.
+struct StdLibModule {
+    id: Identifier,
+    file_name: String,
+    program: Program,
+}
+
+impl StdLibModule {
+    fn load_failed<E: ToString>(&self, error: E) -> EvalError {
+        EvalError::ModuleLoadFailed(self.id.clone(), self.file_name.clone(), error.to_string())
+    }
+}
+
+/// The parsed std lib sources. Every new environment evaluates them, so they're only parsed
+/// once per thread.
+fn std_lib_modules() -> Result<Rc<[StdLibModule]>, EvalError> {
+    thread_local! {
+        static STD_LIB_MODULES: OnceCell<Result<Rc<[StdLibModule]>, EvalError>> =
+            const { OnceCell::new() };
+    }
+    STD_LIB_MODULES.with(|modules| modules.get_or_init(parse_std_lib).clone())
+}
 
-    env
+fn parse_std_lib() -> Result<Rc<[StdLibModule]>, EvalError> {
+    let std_lib_dir = std::env::var_os(STD_LIB_DIR_ENV_VAR).map(PathBuf::from);
+    let parser_ctx = ParserContext::new();
+    let mut modules = Vec::with_capacity(STD_LIB_SOURCES.len());
+
+    for (module_id, embedded_code) in STD_LIB_SOURCES {
+        let module_file = ModuleLoader::module_file_path(&module_id.into());
+        let (file_name, code) = match &std_lib_dir {
+            Some(dir) => {
+                let file_path = dir.join(module_file);
+                let file_name = file_path.display().to_string();
+                match std::fs::read_to_string(&file_path) {
+                    Ok(code) => (file_name, code),
+                    Err(e) => {
+                        return Err(EvalError::ModuleLoadFailed(
+                            module_id.into(),
+                            file_name,
+                            e.to_string(),
+                        ))
+                    }
+                }
+            }
+            None => (
+                PathBuf::from("lib").join(module_file).display().to_string(),
+                embedded_code.to_string(),
+            ),
+        };
+
+        let program = parse_file(&code, &file_name, &parser_ctx).map_err(|e| {
+            EvalError::ModuleLoadFailed(module_id.into(), file_name.clone(), e.to_string())
+        })?;
+        modules.push(StdLibModule {
+            id: module_id.into(),
+            file_name,
+            program,
+        });
+    }
+
+    Ok(modules.into())
+}
+
+/// Evaluates the std lib sources, each in its own scope, so their module definitions
+/// and imports don't leak into the given environment.
+fn load_std_lib(env: &Environment) -> Result<(), EvalError> {
+    let eval_ctx = EvalContext::from_env(env.clone());
+    for module in std_lib_modules()?.iter() {
+        eval_ctx
+            .with_child_env()
+            .evaluate_program(&module.program)
+            .map_err(|e| module.load_failed(e))?;
+    }
+    Ok(())
 }
 
 pub fn std_module() -> Module {
@@ -81,6 +189,7 @@ pub fn std_module() -> Module {
         "length".into(),
         "match".into(),
         "times".into(),
+        "eval".into(),
     ]));
 
     module.fn_def(
@@ -114,22 +223,17 @@ pub fn std_module() -> Module {
         match (args.len(), args.get(0), args.get(1)) {
             (1, Some(func), None) => Ok(ValueRef::clone(func)),
             (2, Some(func), Some(args)) => match (&**func, &**args) {
-                (Value::Lambda(lambda_ctx, lambda), Value::List(args)) => {
+                (Value::Lambda(..) | Value::FnRef(..) | Value::Partial(..), Value::List(args)) => {
                     let args: Vec<ValueRef> = args.iter().map(ValueRef::clone).collect();
-                    lambda_ctx
-                        .borrow_mut()
-                        .evaluate_lambda_call(lambda, &args)
-                        .map_err(|e| {
-                            NativeFnError::EvaluationFailed(func.to_string().into(), e.to_string())
-                        })
+                    ctx.call_value(func, &args)
                 }
                 (Value::Symbol(fn_id), Value::List(args)) => {
                     let args: Vec<ValueRef> = args.iter().map(ValueRef::clone).collect();
                     match ctx.call_function(fn_id, &args) {
                         Some(val) => Ok(ValueRef::clone(&val?)),
-                        None => Err(NativeFnError::EvaluationFailed(
+                        None => Err(NativeFnError::FunctionRefInvalid(
+                            "apply".into(),
                             fn_id.clone(),
-                            format!("FunctionRef invalid in ^apply: ^{fn_id}"),
                         )),
                     }
                 }
@@ -326,17 +430,16 @@ pub fn std_module() -> Module {
 
             match (args.len(), args.get(0), args.get(1)) {
                 (2, Some(val), Some(func)) => match &**func {
-                    Value::Lambda(lambda_ctx, lambda) => lambda_ctx
-                        .borrow_mut()
-                        .evaluate_lambda_call(lambda.as_ref(), &[ValueRef::clone(val)])
-                        .map_err(|e| e.into()),
+                    Value::Lambda(..) | Value::FnRef(..) | Value::Partial(..) => {
+                        ctx.call_value(func, &[ValueRef::clone(val)])
+                    }
 
                     Value::Symbol(fn_id) => {
                         match ctx.call_function(fn_id, &[ValueRef::clone(val)]) {
                             Some(val) => Ok(ValueRef::clone(&val?)),
-                            None => Err(NativeFnError::EvaluationFailed(
+                            None => Err(NativeFnError::FunctionRefInvalid(
+                                "match".into(),
                                 fn_id.clone(),
-                                format!("FunctionRef invalid in ^match: ^{fn_id}"),
                             )),
                         }
                     }
@@ -357,21 +460,22 @@ pub fn std_module() -> Module {
             match (args.len(), args.get(0), args.get(1)) {
                 (2, Some(count_val), Some(func)) => {
                     match (&**count_val, &**func) {
-                        (Value::Number(count), Value::Lambda(lambda_ctx, lambda)) => {
-                            let mut l_ctx = lambda_ctx.borrow_mut();
+                        (
+                            Value::Number(count),
+                            Value::Lambda(..) | Value::FnRef(..) | Value::Partial(..),
+                        ) => {
                             let count_i32 =
                                 count.to_i32().ok_or_else(|| invalid_args("times count"))?;
-                            let max_arg_count = lambda.max_arg_count();
+                            let takes_index = match &**func {
+                                Value::Lambda(_, lambda) => lambda.max_arg_count() > 0,
+                                Value::FnRef(f) => f.borrow().max_args() > 0,
+                                _ => true,
+                            };
                             for i in 0..count_i32 {
-                                let result = if max_arg_count > 0 {
-                                    l_ctx.evaluate_lambda_call(lambda.as_ref(), &[val::number(i)])
+                                if takes_index {
+                                    ctx.call_value(func, &[val::number(i)])?;
                                 } else {
-                                    l_ctx.evaluate_lambda_call(lambda.as_ref(), &[])
-                                };
-
-                                match result {
-                                    Ok(_) => continue,
-                                    Err(e) => return Err(e.into()),
+                                    ctx.call_value(func, &[])?;
                                 }
                             }
                         }
@@ -398,12 +502,7 @@ pub fn std_module() -> Module {
 
                                 match result {
                                     Ok(_) => continue,
-                                    Err(e) => {
-                                        return Err(NativeFnError::EvaluationFailed(
-                                            fn_id.clone(),
-                                            format!("FunctionRef invalid in ^match: ^{fn_id} - Failed with: {e:?}"),
-                                        ));
-                                    }
+                                    Err(e) => return Err(e),
                                 }
                             }
                         }
@@ -426,7 +525,8 @@ pub fn std_module() -> Module {
 
 pub fn op_fn(body: NativeFn) -> FnDefVariant {
     FnDefVariant(
-        FnDefArgs::new(vec![Rc::new("left".into()), Rc::new("right".into())]),
+        FnDefArgs::new(vec![Node::new("left".into()), Node::new("right".into())]),
+        None,
         Rc::new(FnDefBody::native(body)),
     )
 }
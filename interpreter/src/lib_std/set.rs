@@ -5,7 +5,6 @@ use rogato_common::{
     native_fn::NativeFnError,
     val::{self, Value, ValueRef},
 };
-use std::rc::Rc;
 
 pub fn module() -> Module {
     let mut module = Module::new("Std.Set");
@@ -69,32 +68,24 @@ pub fn module() -> Module {
                                         filtered_set = filtered_set.remove(item);
                                     }
                                 }
-                                Some(Err(error)) => {
-                                    return Err(NativeFnError::Unknown(
-                                        fn_id.clone(),
-                                        error.to_string(),
-                                    ))
-                                }
+                                Some(Err(error)) => return Err(error),
                                 None => {
-                                    return Err(NativeFnError::EvaluationFailed(
+                                    return Err(NativeFnError::FunctionRefInvalid(
+                                        "Std.Set.filter".into(),
                                         fn_id.clone(),
-                                        format!(
-                                            "FunctionRef invalid in ^Std.List.filter: ^{fn_id}"
-                                        ),
                                     ))
                                 }
                             }
                         }
                         Ok(filtered_set.into())
                     }
-                    (Value::Set(set), Value::Lambda(lambda_ctx, lambda)) => {
+                    (
+                        Value::Set(set),
+                        Value::Lambda(..) | Value::FnRef(..) | Value::Partial(..),
+                    ) => {
                         let mut filtered_set = set.clone();
                         for item in set.iter() {
-                            let val = context.call_lambda(
-                                Rc::clone(lambda_ctx),
-                                lambda,
-                                &[ValueRef::clone(item)],
-                            )?;
+                            let val = context.call_value(b, &[ValueRef::clone(item)])?;
                             if let Value::Bool(false) = &*val {
                                 filtered_set = filtered_set.remove(item);
                             }
@@ -224,23 +215,22 @@ pub fn module() -> Module {
                             match context.call_function(fn_id, &[ValueRef::clone(item)]) {
                                 Some(val) => result.push(ValueRef::clone(&val?)),
                                 None => {
-                                    return Err(NativeFnError::EvaluationFailed(
+                                    return Err(NativeFnError::FunctionRefInvalid(
+                                        "Std.Set.map".into(),
                                         fn_id.clone(),
-                                        format!("FunctionRef invalid in ^Std.List.map: ^{fn_id}"),
                                     ))
                                 }
                             }
                         }
                         Ok(val::set(result))
                     }
-                    (Value::Set(items), Value::Lambda(lambda_ctx, lambda)) => {
+                    (
+                        Value::Set(items),
+                        Value::Lambda(..) | Value::FnRef(..) | Value::Partial(..),
+                    ) => {
                         let mut result: Vec<ValueRef> = Vec::with_capacity(items.len());
                         for item in items.iter() {
-                            let val = context.call_lambda(
-                                Rc::clone(lambda_ctx),
-                                lambda,
-                                &[ValueRef::clone(item)],
-                            )?;
+                            let val = context.call_value(b, &[ValueRef::clone(item)])?;
                             result.push(val)
                         }
                         Ok(val::set(result))
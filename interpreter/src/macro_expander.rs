@@ -0,0 +1,338 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use crate::{quasi_quote::splice_value, EvalContext, EvalError, Evaluate};
+use rogato_common::{
+    ast::{
+        expression::{Expression, FnCall, LetExpression, MapKVPair, Query, TupleItems},
+        fn_def::FnDef,
+        pattern::Pattern,
+        rewriter::Rewriter,
+        visitor::Visitor,
+        walker::Walk,
+        Node, Program, Span, VarIdentifier, AST,
+    },
+    val::{self, ValueRef},
+};
+
+/// Max. number of nested expansions, e.g. for macros expanding to calls of other macros.
+pub const MAX_EXPANSION_DEPTH: usize = 32;
+
+/// Expands macro calls in parsed code before it gets evaluated.
+/// Macros get called with their arguments as quoted code and the quoted code they return
+/// replaces the call, after expanding any macro calls within it as well.
+/// Macro calls within quoted code are left as is until the quoted code gets evaluated.
+pub struct MacroExpander {
+    context: EvalContext,
+    quote_level: usize,
+    depth: usize,
+    expansions: usize,
+}
+
+impl MacroExpander {
+    /// Expands the macros visible in the given context.
+    /// Definitions encountered while expanding are evaluated in a child context,
+    /// so `module` and `use` statements don't affect the given context until the expanded
+    /// code gets evaluated.
+    pub fn new(context: &EvalContext) -> Self {
+        MacroExpander {
+            context: context.with_child_env(),
+            quote_level: 0,
+            depth: 0,
+            expansions: 0,
+        }
+    }
+
+    /// Expands the macros visible in the given context without affecting it,
+    /// e.g. to show the expansion of code without evaluating it.
+    /// Definitions encountered while expanding are evaluated in an isolated copy of the context.
+    pub fn isolated(context: &EvalContext) -> Self {
+        Self::new(&context.isolated())
+    }
+
+    /// Expands all macro calls within the given program. Each root definition gets evaluated
+    /// after being expanded, so macros can be called right after their definition or import
+    /// and can call any functions defined before them.
+    pub fn expand_program(&mut self, program: &Program) -> Result<Program, EvalError> {
+        let mut nodes = Vec::with_capacity(program.len());
+        for ast in program.iter() {
+            let expanded = Node::new(self.expand_root(ast)?).with_span(ast.span());
+            expanded.evaluate(&mut self.context)?;
+            nodes.push(expanded)
+        }
+        Ok(Program::new(nodes))
+    }
+
+    /// Expands all macro calls within the given root definition.
+    /// Macro definitions as well as `module` and `use` statements are kept as is.
+    pub fn expand_root(&mut self, ast: &Node<AST>) -> Result<AST, EvalError> {
+        match &**ast {
+            AST::MacroDef(_) | AST::ModuleDef(_) | AST::Use(_) => Ok((**ast).clone()),
+            _ => self.expand_ast(ast).map_err(|e| e.with_span(ast.span())),
+        }
+    }
+
+    pub fn expand_ast(&mut self, ast: &AST) -> Result<AST, EvalError> {
+        self.rewrite_ast(ast)
+    }
+
+    pub fn expand_expr(&mut self, expr: &Node<Expression>) -> Result<Node<Expression>, EvalError> {
+        self.rewrite_expr(expr)
+    }
+
+    fn expand_macro_call(
+        &mut self,
+        macro_fn: Rc<RefCell<FnDef>>,
+        fn_call: &FnCall,
+    ) -> Result<Node<Expression>, EvalError> {
+        if self.depth >= MAX_EXPANSION_DEPTH {
+            return Err(EvalError::MacroExpansionTooDeep(
+                fn_call.id.clone(),
+                MAX_EXPANSION_DEPTH,
+            ));
+        }
+
+        self.expansions += 1;
+        let macro_fn = Hygiene::new(self.expansions).rewrite_fn_def(&macro_fn.borrow())?;
+        let args: Vec<ValueRef> = fn_call
+            .args
+            .iter()
+            .map(|arg| val::quoted(Node::clone(arg)))
+            .collect();
+        let expansion = self
+            .context
+            .call_function_direct(macro_fn, &args)
+            .and_then(|code| splice_value(&code))
+            .map_err(|e| EvalError::MacroExpansionFailed(fn_call.id.clone(), Box::new(e)))?;
+
+        self.depth += 1;
+        let expanded = self.expand_expr(&expansion);
+        self.depth -= 1;
+        expanded
+    }
+
+    fn quoted<T, F: FnOnce(&mut Self) -> Result<T, EvalError>>(
+        &mut self,
+        quote_level: usize,
+        expand: F,
+    ) -> Result<T, EvalError> {
+        let outer_level = self.quote_level;
+        self.quote_level = quote_level;
+        let expanded = expand(self);
+        self.quote_level = outer_level;
+        expanded
+    }
+}
+
+impl Rewriter for MacroExpander {
+    type Error = EvalError;
+
+    fn rewrite_node(&mut self, expr: &Expression) -> Result<Option<Node<Expression>>, EvalError> {
+        let expanded = match expr {
+            Expression::FnCall(fn_call) if self.quote_level == 0 => {
+                match self.context.lookup_macro(&fn_call.id) {
+                    Some(macro_fn) => self.expand_macro_call(macro_fn, fn_call)?,
+                    None => return Ok(None),
+                }
+            }
+            Expression::Quoted(e) => Node::new(Expression::Quoted(
+                self.quoted(self.quote_level + 1, |m| m.expand_expr(e))?,
+            )),
+            Expression::QuotedAST(ast) => Node::new(Expression::QuotedAST(
+                self.quoted(self.quote_level + 1, |m| m.rewrite_sub_ast(ast))?,
+            )),
+            Expression::Unquoted(e) if self.quote_level > 0 => Node::new(Expression::Unquoted(
+                self.quoted(self.quote_level - 1, |m| m.expand_expr(e))?,
+            )),
+            Expression::UnquotedAST(ast) if self.quote_level > 0 => {
+                Node::new(Expression::UnquotedAST(
+                    self.quoted(self.quote_level - 1, |m| m.rewrite_sub_ast(ast))?,
+                ))
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(expanded))
+    }
+
+    fn spanned_error(&self, error: EvalError, span: &Span) -> EvalError {
+        error.with_span(Some(span))
+    }
+}
+
+/// Makes a macro hygienic for a single expansion: variables bound within the quoted code
+/// of the macro's body get renamed to names that can't be written in source code,
+/// so they neither capture nor shadow any variables of the code passed to the macro.
+/// Code spliced in via unquotes is left as is.
+struct Hygiene {
+    expansion: usize,
+}
+
+impl Hygiene {
+    fn new(expansion: usize) -> Self {
+        Hygiene { expansion }
+    }
+}
+
+impl Rewriter for Hygiene {
+    type Error = EvalError;
+
+    fn rewrite_node(&mut self, expr: &Expression) -> Result<Option<Node<Expression>>, EvalError> {
+        match expr {
+            Expression::Quoted(code) => {
+                let mut bound_vars = BoundVars::default();
+                code.walk(&mut bound_vars);
+                let renames = bound_vars
+                    .ids
+                    .into_iter()
+                    .map(|id| {
+                        let renamed = VarIdentifier::new(format!("{id}#{}", self.expansion));
+                        (id, renamed)
+                    })
+                    .collect();
+                let renamed = Rename { renames }.rewrite_expr(code)?;
+                Ok(Some(Node::new(Expression::Quoted(renamed))))
+            }
+            Expression::QuotedAST(_) => Ok(Some(Node::new(expr.clone()))),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Collects the variables bound within a piece of code, except for those in unquoted
+/// or nested quoted code.
+#[derive(Default)]
+struct BoundVars {
+    ids: HashSet<VarIdentifier>,
+}
+
+impl Visitor<()> for BoundVars {
+    fn let_(&mut self, let_expr: &LetExpression) {
+        for (id, _) in let_expr.bindings.iter() {
+            self.ids.insert(id.clone());
+        }
+    }
+
+    fn query(&mut self, query: &Query) {
+        for binding in query.bindings().iter() {
+            self.ids.extend(binding.ids().iter().cloned());
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Var(id) | Pattern::As(id, _) => {
+                self.ids.insert(id.clone());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Renames variables within a piece of code, except for those in unquoted or nested quoted code.
+struct Rename {
+    renames: HashMap<VarIdentifier, VarIdentifier>,
+}
+
+impl Rename {
+    fn renamed(&self, id: &VarIdentifier) -> VarIdentifier {
+        self.renames.get(id).cloned().unwrap_or_else(|| id.clone())
+    }
+
+    fn rename_pattern(&self, pattern: &Node<Pattern>) -> Node<Pattern> {
+        let renamed = match &**pattern {
+            Pattern::Var(id) => Pattern::Var(self.renamed(id)),
+            Pattern::As(id, p) => Pattern::As(self.renamed(id), self.rename_pattern(p)),
+            Pattern::ListCons(head, tail) => {
+                Pattern::ListCons(self.rename_pattern(head), self.rename_pattern(tail))
+            }
+            Pattern::List(items) => Pattern::List(self.rename_patterns(items)),
+            Pattern::Tuple(len, items) => Pattern::Tuple(*len, self.rename_patterns(items)),
+            Pattern::Map(kv_pairs) => Pattern::Map(self.rename_kv_patterns(kv_pairs)),
+            Pattern::MapCons(kv_pairs, rest) => {
+                Pattern::MapCons(self.rename_kv_patterns(kv_pairs), self.rename_pattern(rest))
+            }
+            Pattern::Constructor(id, params) => {
+                Pattern::Constructor(id.clone(), self.rename_patterns(params))
+            }
+            Pattern::Struct(id, props) => Pattern::Struct(
+                id.clone(),
+                props
+                    .iter()
+                    .map(|(prop, p)| (prop.clone(), self.rename_pattern(p)))
+                    .collect(),
+            ),
+            Pattern::Or(alternatives) => Pattern::Or(self.rename_patterns(alternatives)),
+            Pattern::Any
+            | Pattern::EmptyList
+            | Pattern::Bool(_)
+            | Pattern::Number(_)
+            | Pattern::String(_)
+            | Pattern::Symbol(_) => return Node::clone(pattern),
+        };
+        Node::new(renamed).with_span(pattern.span())
+    }
+
+    fn rename_patterns(&self, patterns: &TupleItems<Pattern>) -> TupleItems<Pattern> {
+        TupleItems::from(
+            patterns
+                .iter()
+                .map(|p| self.rename_pattern(p))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    fn rename_kv_patterns(
+        &self,
+        kv_pairs: &TupleItems<MapKVPair<Pattern>>,
+    ) -> TupleItems<MapKVPair<Pattern>> {
+        TupleItems::from(
+            kv_pairs
+                .iter()
+                .map(|kv_pair| {
+                    Node::new(MapKVPair::new(
+                        Node::clone(&kv_pair.key),
+                        self.rename_pattern(&kv_pair.value),
+                    ))
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
+impl Rewriter for Rename {
+    type Error = EvalError;
+
+    fn rewrite_node(&mut self, expr: &Expression) -> Result<Option<Node<Expression>>, EvalError> {
+        match expr {
+            Expression::Var(id) => Ok(Some(Node::new(Expression::Var(self.renamed(id))))),
+            // calls of lambdas bound to a variable
+            Expression::FnCall(fn_call) => {
+                let id = VarIdentifier::new(fn_call.id.clone());
+                match self.renames.get(&id) {
+                    Some(renamed) => {
+                        let fn_call = FnCall::new(renamed.into(), fn_call.args.clone());
+                        self.rewrite_sub_exprs(&Expression::FnCall(fn_call))
+                            .map(|renamed| Some(Node::new(renamed)))
+                    }
+                    None => Ok(None),
+                }
+            }
+            Expression::Quoted(_)
+            | Expression::QuotedAST(_)
+            | Expression::Unquoted(_)
+            | Expression::UnquotedAST(_) => Ok(Some(Node::new(expr.clone()))),
+            _ => Ok(None),
+        }
+    }
+
+    fn rewrite_binding(&mut self, id: &VarIdentifier) -> Result<VarIdentifier, EvalError> {
+        Ok(self.renamed(id))
+    }
+
+    fn rewrite_pattern(&mut self, pattern: &Node<Pattern>) -> Result<Node<Pattern>, EvalError> {
+        Ok(self.rename_pattern(pattern))
+    }
+}
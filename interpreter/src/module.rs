@@ -4,20 +4,26 @@ use std::rc::Rc;
 use std::{collections::HashMap, fmt::Display};
 
 use crate::ValueRef;
-use rogato_common::ast::fn_def::{FnDefArgs, FnDefBody, FnDefVariant};
+use rogato_common::ast::fixity::{Fixity, FixityDef};
+use rogato_common::ast::fn_def::{FnDefArgs, FnDefBody, FnDefVariant, FnDefVariants};
+use rogato_common::ast::macro_def::MacroDef;
 use rogato_common::ast::module_def::ModuleExports;
 use rogato_common::ast::pattern::Pattern;
 use rogato_common::ast::{fn_def::FnDef, type_expression::TypeDef};
-use rogato_common::ast::{Identifier, VarIdentifier};
-use rogato_common::native_fn::NativeFn;
+use rogato_common::ast::{Identifier, Node, VarIdentifier};
+use rogato_common::native_fn::{NativeFn, NativeFnContext, NativeFnError};
+use rogato_common::val;
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 struct State {
     id: Identifier,
     fn_defs: HashMap<Identifier, Rc<RefCell<FnDef>>>,
+    macro_defs: HashMap<Identifier, Rc<RefCell<FnDef>>>,
     type_defs: HashMap<Identifier, Rc<TypeDef>>,
     constants: HashMap<Identifier, ValueRef>,
     exports: HashSet<Identifier>,
+    constructors: HashMap<Identifier, Identifier>,
+    fixities: HashMap<Identifier, Fixity>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -30,9 +36,12 @@ impl Module {
         let state = State {
             id: id.into(),
             fn_defs: HashMap::new(),
+            macro_defs: HashMap::new(),
             type_defs: HashMap::new(),
             constants: HashMap::new(),
             exports: HashSet::new(),
+            constructors: HashMap::new(),
+            fixities: HashMap::new(),
         };
         Module {
             state: Rc::new(RefCell::new(state)),
@@ -44,6 +53,31 @@ impl Module {
         state.id.clone()
     }
 
+    /// A copy of this module that can be changed without affecting this one.
+    /// Fns shared with other modules, e.g. via `fn_alias`, are copied once into `fn_copies`,
+    /// so copies of those modules keep sharing them.
+    pub fn copy(
+        &self,
+        fn_copies: &mut HashMap<*const RefCell<FnDef>, Rc<RefCell<FnDef>>>,
+    ) -> Module {
+        let mut state = self.state.borrow().clone();
+        for fn_def in state.fn_defs.values_mut() {
+            *fn_def = Rc::clone(
+                fn_copies
+                    .entry(Rc::as_ptr(fn_def))
+                    .or_insert_with(|| Rc::new(RefCell::new(fn_def.borrow().clone()))),
+            );
+        }
+        Module {
+            state: Rc::new(RefCell::new(state)),
+        }
+    }
+
+    /// The fully qualified id of a definition in this module, e.g. `Foo.Bar.Pt`.
+    pub fn qualified_id(&self, id: &Identifier) -> Identifier {
+        format!("{}.{id}", self.id()).into()
+    }
+
     pub fn export(&mut self, exports: &ModuleExports) {
         let mut state = self.state.borrow_mut();
         for id in exports.iter() {
@@ -51,19 +85,43 @@ impl Module {
         }
     }
 
+    /// Whether the given definition is visible outside of this module.
+    /// The constructors of an exported union type are exported along with it.
+    pub fn is_exported(&self, id: &Identifier) -> bool {
+        let state = self.state.borrow();
+        state.exports.contains(id)
+            || state
+                .constructors
+                .get(id)
+                .map(|type_id| state.exports.contains(type_id))
+                .unwrap_or(false)
+    }
+
+    /// Whether this module defines a fn, macro, type or constant with the given id.
+    pub fn has_definition(&self, id: &Identifier) -> bool {
+        let state = self.state.borrow();
+        state.fn_defs.contains_key(id)
+            || state.macro_defs.contains_key(id)
+            || state.type_defs.contains_key(id)
+            || state.constants.contains_key(id)
+    }
+
     pub fn fn_def<ID: Into<Identifier>>(&mut self, id: ID, fn_variant: FnDefVariant) {
         let id: Identifier = id.into();
         if self.has_fn_defined(&id) {
-            let FnDefVariant(args, body) = fn_variant;
             self.state
                 .borrow()
                 .fn_defs
                 .get(&id)
-                .map(|f| f.borrow_mut().add_variant(args, body))
+                .map(|f| f.borrow_mut().push_variant(fn_variant))
                 .unwrap_or_else(|| eprintln!("EvalContext::define_fn_variant failed for: {id}"))
         } else {
-            let FnDefVariant(args, body) = fn_variant;
-            let fn_def = FnDef::new(id.clone(), args, body);
+            // native fns run in their caller's scope, e.g. to look up fns passed in by name
+            let is_native = matches!(*fn_variant.2, FnDefBody::NativeFn(_));
+            let fn_def = FnDef::new_with_variants(id.clone(), FnDefVariants::new([fn_variant]));
+            if !is_native {
+                fn_def.borrow_mut().set_module_id(self.id());
+            }
             self.state.borrow_mut().fn_defs.insert(id, fn_def);
         }
     }
@@ -73,12 +131,17 @@ impl Module {
 
         let args = FnDefArgs::new(
             args.iter()
-                .map(|a| Rc::new(Pattern::Var((*a).into())))
+                .map(|a| Node::new(Pattern::Var((*a).into())))
                 .collect(),
         );
         let body = Rc::new(FnDefBody::native(fn_body));
 
-        self.fn_def(id, FnDefVariant(args, body));
+        self.fn_def(id, FnDefVariant(args, None, body));
+    }
+
+    /// Makes a function defined in another module available under the given id as well.
+    pub fn fn_alias<ID: Into<Identifier>>(&mut self, id: ID, fn_def: Rc<RefCell<FnDef>>) {
+        self.state.borrow_mut().fn_defs.insert(id.into(), fn_def);
     }
 
     fn has_fn_defined(&self, id: &Identifier) -> bool {
@@ -90,6 +153,24 @@ impl Module {
         state.fn_defs.get(id).cloned()
     }
 
+    /// Defines a macro as a function to be called with quoted code during macro expansion.
+    /// Unlike functions, redefining a macro replaces it.
+    pub fn macro_def(&mut self, macro_def: &MacroDef) {
+        let fn_def = FnDef::new(
+            macro_def.id().clone(),
+            macro_def.args().clone(),
+            Rc::new(FnDefBody::rogato(Node::clone(macro_def.body()))),
+        );
+        fn_def.borrow_mut().set_module_id(self.id());
+        let mut state = self.state.borrow_mut();
+        state.macro_defs.insert(macro_def.id().clone(), fn_def);
+    }
+
+    pub fn lookup_macro(&self, id: &Identifier) -> Option<Rc<RefCell<FnDef>>> {
+        let state = self.state.borrow();
+        state.macro_defs.get(id).cloned()
+    }
+
     pub fn type_def(&mut self, id: Identifier, type_def: Rc<TypeDef>) {
         let mut state = self.state.borrow_mut();
         state.type_defs.insert(id, type_def);
@@ -109,6 +190,67 @@ impl Module {
         let state = self.state.borrow();
         state.constants.get(id).cloned()
     }
+
+    /// Declares the fixity of an operator. Fixities are exported along with the module,
+    /// so code importing it parses the operator accordingly.
+    pub fn fixity_def(&mut self, fixity_def: &FixityDef) {
+        let mut state = self.state.borrow_mut();
+        state
+            .fixities
+            .insert(fixity_def.id().clone(), fixity_def.fixity());
+    }
+
+    pub fn fixity_defs(&self) -> Vec<FixityDef> {
+        let state = self.state.borrow();
+        state
+            .fixities
+            .iter()
+            .map(|(id, fixity)| FixityDef::new(id.clone(), *fixity))
+            .collect()
+    }
+
+    /// Defines the constructor of a variant of the given union type.
+    /// Constructors without parameters are defined as constants holding the variant value,
+    /// all others as native functions of the given arity.
+    pub fn constructor_def(&mut self, type_id: &Identifier, id: &Identifier, arity: usize) {
+        self.state
+            .borrow_mut()
+            .constructors
+            .insert(id.clone(), type_id.clone());
+
+        if arity == 0 {
+            let type_id = self.qualified_id(type_id);
+            self.const_def(id, val::variant(type_id, id.clone(), []));
+            return;
+        }
+
+        let args = FnDefArgs::new((0..arity).map(|_| Node::new(Pattern::Any)).collect());
+        let body = Rc::new(FnDefBody::native(construct_variant));
+        let fn_def = FnDef::new(id.clone(), args, body);
+        // called within this module, so the constructor's type is looked up here
+        fn_def.borrow_mut().set_module_id(self.id());
+        self.state.borrow_mut().fn_defs.insert(id.clone(), fn_def);
+    }
+
+    /// Returns the qualified id of the union type the given variant constructor belongs to.
+    pub fn lookup_constructor(&self, id: &Identifier) -> Option<Identifier> {
+        let state = self.state.borrow();
+        state
+            .constructors
+            .get(id)
+            .map(|type_id| format!("{}.{type_id}", state.id).into())
+    }
+}
+
+fn construct_variant(
+    ctx: &mut dyn NativeFnContext,
+    args: &[ValueRef],
+) -> Result<ValueRef, NativeFnError> {
+    let id = ctx.current_func_id();
+    let type_id = ctx
+        .lookup_constructor_type(&id)
+        .ok_or_else(|| NativeFnError::InvalidArguments(id.clone()))?;
+    Ok(val::variant(type_id, id, args.to_vec()))
 }
 
 impl Display for Module {
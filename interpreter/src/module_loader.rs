@@ -0,0 +1,67 @@
+use std::path::{Path, PathBuf};
+
+use rogato_common::ast::Identifier;
+
+use crate::EvalError;
+
+pub const SOURCE_FILE_EXTENSION: &str = "roga";
+
+/// Resolves module ids like `Foo.Bar` to `Foo/Bar.roga` source files in a list of search paths
+/// and keeps track of the modules currently being loaded to detect import cycles.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct ModuleLoader {
+    search_paths: Vec<PathBuf>,
+    loading: Vec<Identifier>,
+}
+
+impl ModuleLoader {
+    pub fn new<P: Into<PathBuf>, I: IntoIterator<Item = P>>(search_paths: I) -> Self {
+        Self {
+            search_paths: search_paths.into_iter().map(Into::into).collect(),
+            loading: Vec::new(),
+        }
+    }
+
+    pub fn add_search_path<P: Into<PathBuf>>(&mut self, path: P) {
+        let path = path.into();
+        if !self.search_paths.contains(&path) {
+            self.search_paths.push(path);
+        }
+    }
+
+    pub fn search_paths(&self) -> std::slice::Iter<'_, PathBuf> {
+        self.search_paths.iter()
+    }
+
+    /// The source file path of a module relative to a search path, e.g. `Std.List` -> `Std/List.roga`.
+    pub fn module_file_path(module_id: &Identifier) -> PathBuf {
+        let mut path: PathBuf = module_id.split('.').collect();
+        path.set_extension(SOURCE_FILE_EXTENSION);
+        path
+    }
+
+    /// Returns the first existing source file for the given module id in the search paths.
+    pub fn find_module_file(&self, module_id: &Identifier) -> Option<PathBuf> {
+        let file_path = Self::module_file_path(module_id);
+        self.search_paths
+            .iter()
+            .map(|search_path| search_path.join(&file_path))
+            .find(|path| Path::is_file(path))
+    }
+
+    /// Marks the given module as being loaded.
+    /// Fails with the chain of imports if the module is already being loaded further up.
+    pub fn start_loading(&mut self, module_id: &Identifier) -> Result<(), EvalError> {
+        if let Some(index) = self.loading.iter().position(|id| id == module_id) {
+            let mut cycle = self.loading[index..].to_vec();
+            cycle.push(module_id.clone());
+            return Err(EvalError::ImportCycle(cycle));
+        }
+        self.loading.push(module_id.clone());
+        Ok(())
+    }
+
+    pub fn finish_loading(&mut self, module_id: &Identifier) {
+        self.loading.retain(|id| id != module_id);
+    }
+}
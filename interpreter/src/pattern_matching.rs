@@ -1,10 +1,8 @@
-use std::rc::Rc;
-
 use crate::{EvalContext, Identifier};
 use rogato_common::{
-    ast::pattern::Pattern,
+    ast::{fn_def::FnDefArgs, pattern::Pattern, Node, Span},
     flame_guard,
-    val::{Map, Value, ValueRef},
+    val::{Map, Object, Value, ValueRef},
 };
 use thiserror::Error;
 
@@ -44,11 +42,30 @@ pub enum PatternMatchingError {
     #[error("Unknown PatternMatchingError in {0} : {1}")]
     Unknown(FuncId, String),
 
-    #[error("Failed to match pattern in {0} : {1} with value: {2}")]
-    MatchFailed(FuncId, Pattern, ValueRef),
+    #[error("No variant of fn {0} matched values: {2:?}{variants}", variants = display_attempted(.0, .1))]
+    NoFnVariantMatched(FuncId, Vec<FnDefArgs>, Vec<ValueRef>),
+
+    #[error("{1} at {0}")]
+    Spanned(Span, Box<PatternMatchingError>),
+}
+
+impl PatternMatchingError {
+    /// Attaches the given source span, unless the error already points to a more specific location.
+    pub fn with_span(self, span: Option<&Span>) -> PatternMatchingError {
+        match span {
+            Some(span) if self.span().is_none() => {
+                PatternMatchingError::Spanned(span.clone(), Box::new(self))
+            }
+            _ => self,
+        }
+    }
 
-    #[error("Failed to match fn {0} variant for pattern: {1:?} with value: {2:?}")]
-    NoFnVariantMatched(FuncId, Option<Rc<Pattern>>, Vec<ValueRef>),
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            PatternMatchingError::Spanned(span, _) => Some(span),
+            _ => None,
+        }
+    }
 }
 
 pub trait PatternMatching {
@@ -59,6 +76,18 @@ pub trait PatternMatching {
     ) -> Result<PatternMatch, PatternMatchingError>;
 }
 
+impl PatternMatching for Node<Pattern> {
+    fn pattern_match(
+        &self,
+        context: &mut EvalContext,
+        value: ValueRef,
+    ) -> Result<PatternMatch, PatternMatchingError> {
+        (**self)
+            .pattern_match(context, value)
+            .map_err(|e| e.with_span(self.span()))
+    }
+}
+
 impl PatternMatching for Pattern {
     #[cfg_attr(feature = "flame_it", flame)]
     fn pattern_match(
@@ -88,8 +117,15 @@ impl PatternMatching for Pattern {
                     return Ok(PatternMatch::TryNextPattern);
                 }
 
-                head.pattern_match(context, list.head().unwrap())?;
-                tail.pattern_match(context, list.tail().into())?;
+                if head
+                    .pattern_match(context, list.head().unwrap())?
+                    .match_failed()
+                    || tail
+                        .pattern_match(context, list.tail().into())?
+                        .match_failed()
+                {
+                    return Ok(PatternMatch::TryNextPattern);
+                }
 
                 Ok(PatternMatch::Matched(value))
             }
@@ -211,11 +247,156 @@ impl PatternMatching for Pattern {
                 }
             }
 
-            (_, _) => Err(PatternMatchingError::MatchFailed(
-                context.current_func_id(),
-                self.clone(),
-                ValueRef::clone(&value),
-            )),
+            (Pattern::Constructor(id_p, params_p), Value::Variant(type_id, id, params)) => {
+                let constructor = context.lookup_constructor(id_p);
+                if constructor.as_ref() != Some(&(type_id.clone(), id.clone()))
+                    || params_p.len() != params.len()
+                {
+                    return Ok(PatternMatch::TryNextPattern);
+                }
+
+                for (pat, val) in params_p.iter().zip(params.iter()) {
+                    if pat
+                        .pattern_match(context, ValueRef::clone(val))?
+                        .match_failed()
+                    {
+                        return Ok(PatternMatch::TryNextPattern);
+                    }
+                }
+
+                Ok(PatternMatch::Matched(value))
+            }
+
+            // Option values are matched by the `Some x` and `None` constructor patterns
+            (Pattern::Constructor(id_p, params_p), Value::Option(opt))
+                if matches!(id_p.as_str(), "Some" | "None") =>
+            {
+                match (id_p.as_str(), params_p.iter().next(), opt) {
+                    ("None", None, None) => Ok(PatternMatch::Matched(value)),
+                    ("Some", Some(pat), Some(val)) if params_p.len() == 1 => {
+                        match pat.pattern_match(context, ValueRef::clone(val))? {
+                            PatternMatch::Matched(_) => Ok(PatternMatch::Matched(value)),
+                            PatternMatch::TryNextPattern => Ok(PatternMatch::TryNextPattern),
+                        }
+                    }
+                    _ => Ok(PatternMatch::TryNextPattern),
+                }
+            }
+
+            (Pattern::Symbol(pat), Value::Symbol(symbol)) => {
+                if pat == symbol {
+                    Ok(PatternMatch::Matched(value))
+                } else {
+                    Ok(PatternMatch::TryNextPattern)
+                }
+            }
+
+            (Pattern::Struct(Some(id_p), _), Value::Struct(struct_id, _))
+                if context
+                    .lookup_qualified_type(id_p)
+                    .map(|(type_id, _)| type_id)
+                    .as_ref()
+                    != Some(struct_id) =>
+            {
+                Ok(PatternMatch::TryNextPattern)
+            }
+
+            (Pattern::Struct(Some(_), _), Value::Object(_)) => Ok(PatternMatch::TryNextPattern),
+
+            (Pattern::Struct(_id, props_p), Value::Object(object) | Value::Struct(_, object)) => {
+                match_props(self, props_p, object, context, value)
+            }
+
+            // on objects and structs, `{name: n}` matches the property `name` just like `_{name: n}`
+            (Pattern::Map(kv_pairs_p), Value::Object(object) | Value::Struct(_, object))
+                if !kv_pairs_p.is_empty() =>
+            {
+                let mut props_p = Vec::with_capacity(kv_pairs_p.len());
+                for kv_pair_p in kv_pairs_p.iter() {
+                    let (key_p, val_p) = kv_pair_p.pair();
+                    let prop = match &*key_p {
+                        Pattern::Var(id) => Identifier::from(id.as_str()),
+                        Pattern::String(prop) => Identifier::from(prop.as_str()),
+                        _ => return Ok(PatternMatch::TryNextPattern),
+                    };
+                    props_p.push((prop, val_p))
+                }
+                let props_pattern = Pattern::Struct(None, props_p.clone());
+                match_props(&props_pattern, &props_p, object, context, value)
+            }
+
+            (Pattern::As(id, pattern), _) => {
+                match pattern.pattern_match(context, ValueRef::clone(&value))? {
+                    PatternMatch::Matched(_) => {
+                        context.define_var(id, ValueRef::clone(&value));
+                        Ok(PatternMatch::Matched(value))
+                    }
+                    PatternMatch::TryNextPattern => Ok(PatternMatch::TryNextPattern),
+                }
+            }
+
+            (Pattern::Or(alternatives), _) => {
+                for alternative in alternatives.iter() {
+                    let mut alternative_ctx = context.with_child_env();
+                    if alternative
+                        .pattern_match(&mut alternative_ctx, ValueRef::clone(&value))?
+                        .matched()
+                    {
+                        define_vars(alternative, &alternative_ctx, context);
+                        return Ok(PatternMatch::Matched(value));
+                    }
+                }
+
+                Ok(PatternMatch::TryNextPattern)
+            }
+
+            // a pattern of a different shape or type than the value just doesn't match,
+            // so the next variant or case arm gets a chance to match it instead
+            (_, _) => Ok(PatternMatch::TryNextPattern),
         }
     }
 }
+
+/// Defines the variables bound by a pattern that matched within a child context in the given
+/// context, so patterns that fail halfway through don't leave any of their bindings behind.
+/// Matches the given property patterns against the properties of an object or struct,
+/// binding the variables of the given pattern if all of them match.
+fn match_props(
+    pattern: &Pattern,
+    props_p: &[(Identifier, Node<Pattern>)],
+    object: &Object,
+    context: &mut EvalContext,
+    value: ValueRef,
+) -> Result<PatternMatch, PatternMatchingError> {
+    let mut props_ctx = context.with_child_env();
+    for (prop, pat) in props_p.iter() {
+        let matched = match object.get(prop) {
+            Some(val) => pat
+                .pattern_match(&mut props_ctx, ValueRef::clone(val))?
+                .matched(),
+            None => false,
+        };
+        if !matched {
+            return Ok(PatternMatch::TryNextPattern);
+        }
+    }
+
+    define_vars(pattern, &props_ctx, context);
+    Ok(PatternMatch::Matched(value))
+}
+
+fn define_vars(pattern: &Pattern, matched_ctx: &EvalContext, context: &mut EvalContext) {
+    for id in pattern.var_ids() {
+        if let Some(value) = matched_ctx.lookup_var(&id) {
+            context.define_var(&id, value)
+        }
+    }
+}
+
+/// Lists each attempted variant's argument patterns on its own line, e.g. `\n    f [] 0`.
+fn display_attempted(func_id: &FuncId, variants: &[FnDefArgs]) -> String {
+    variants
+        .iter()
+        .map(|args| format!("\n    {func_id} {args}"))
+        .collect()
+}
@@ -0,0 +1,137 @@
+use std::rc::Rc;
+
+use crate::{EvalContext, EvalError, Evaluate};
+use rogato_common::{
+    ast::{
+        expression::{Expression, FnCall, FnCallArgs, Literal, MapKVPair, StructProps, TupleItems},
+        rewriter::Rewriter,
+        Node, Span, AST,
+    },
+    val::{Value, ValueRef},
+};
+
+/// Expands quasi-quoted code: walks a quoted expression or definition, evaluates every
+/// unquoted `~x` within it in the current environment and splices the resulting values back in.
+/// Nested quotes increase the quote level, so `~x` inside of `^(... ^(~x))` is left as is
+/// until the inner quote gets evaluated.
+pub struct QuasiQuote<'a> {
+    context: &'a mut EvalContext,
+    level: usize,
+}
+
+impl<'a> QuasiQuote<'a> {
+    pub fn new(context: &'a mut EvalContext) -> Self {
+        Self { context, level: 1 }
+    }
+
+    pub fn expand_expr(&mut self, expr: &Node<Expression>) -> Result<Node<Expression>, EvalError> {
+        self.rewrite_expr(expr)
+    }
+
+    pub fn expand_ast(&mut self, ast: &AST) -> Result<AST, EvalError> {
+        self.rewrite_ast(ast)
+    }
+
+    fn nested<T, F: FnOnce(&mut Self) -> Result<T, EvalError>>(
+        &mut self,
+        level: usize,
+        expand: F,
+    ) -> Result<T, EvalError> {
+        let outer_level = self.level;
+        self.level = level;
+        let expanded = expand(self);
+        self.level = outer_level;
+        expanded
+    }
+}
+
+impl Rewriter for QuasiQuote<'_> {
+    type Error = EvalError;
+
+    fn rewrite_node(&mut self, expr: &Expression) -> Result<Option<Node<Expression>>, EvalError> {
+        let expanded = match expr {
+            Expression::Quoted(e) => Node::new(Expression::Quoted(
+                self.nested(self.level + 1, |q| q.expand_expr(e))?,
+            )),
+            Expression::QuotedAST(ast) => Node::new(Expression::QuotedAST(
+                self.nested(self.level + 1, |q| q.rewrite_sub_ast(ast))?,
+            )),
+            Expression::Unquoted(e) if self.level == 1 => {
+                let value = e.evaluate(self.context)?;
+                splice_value(&value)?
+            }
+            Expression::UnquotedAST(ast) if self.level == 1 => {
+                let value = ast.evaluate(self.context)?;
+                splice_value(&value)?
+            }
+            Expression::Unquoted(e) => Node::new(Expression::Unquoted(
+                self.nested(self.level - 1, |q| q.expand_expr(e))?,
+            )),
+            Expression::UnquotedAST(ast) => Node::new(Expression::UnquotedAST(
+                self.nested(self.level - 1, |q| q.rewrite_sub_ast(ast))?,
+            )),
+            _ => return Ok(None),
+        };
+        Ok(Some(expanded))
+    }
+
+    fn spanned_error(&self, error: EvalError, span: &Span) -> EvalError {
+        error.with_span(Some(span))
+    }
+}
+
+/// Turns an unquoted value back into code to be spliced into a quoted expression.
+/// Quoted values are spliced in as is, other values as their literal expressions.
+pub fn splice_value(value: &ValueRef) -> Result<Node<Expression>, EvalError> {
+    let expr = match &**value {
+        Value::Quoted(expr) => return Ok(Node::clone(expr)),
+        Value::QuotedAST(ast) => match &**ast {
+            AST::FnDef(fn_def) => Expression::InlineFnDef(Rc::clone(fn_def)),
+            _ => Expression::QuotedAST(Node::clone(ast)),
+        },
+        Value::Bool(b) => Expression::Lit(Literal::Bool(*b)),
+        Value::Number(num) => Expression::Lit(Literal::Number(*num)),
+        Value::String(string) => Expression::Lit(Literal::String(string.clone())),
+        Value::Symbol(id) => Expression::Symbol(id.clone()),
+        Value::Tuple(_, items) => Expression::Lit(Literal::Tuple(TupleItems::from(splice_values(
+            items.iter(),
+        )?))),
+        Value::List(items) => Expression::Lit(Literal::List(TupleItems::from(splice_values(
+            items.iter(),
+        )?))),
+        Value::Map(map) => {
+            let mut kv_pairs = Vec::with_capacity(map.len());
+            for (key, val) in map.iter() {
+                kv_pairs.push(Node::new(MapKVPair::new(
+                    splice_value(key)?,
+                    splice_value(val)?,
+                )))
+            }
+            Expression::Lit(Literal::Map(TupleItems::from(kv_pairs)))
+        }
+        Value::Struct(id, object) => {
+            let mut props = Vec::with_capacity(object.len());
+            for (prop, val) in object.sorted_properties() {
+                props.push((prop.into(), splice_value(val)?))
+            }
+            Expression::Lit(Literal::Struct(
+                id.clone(),
+                Rc::new(StructProps::from(props)),
+            ))
+        }
+        Value::Variant(_, id, args) if args.is_empty() => Expression::ConstOrTypeRef(id.clone()),
+        Value::Variant(_, id, args) => Expression::FnCall(FnCall::new(
+            id.clone(),
+            FnCallArgs::new(splice_values(args.iter())?),
+        )),
+        Value::Lambda(_, lambda) => Expression::Lambda(Rc::clone(lambda)),
+        _ => return Err(EvalError::UnquoteNotSpliceable(ValueRef::clone(value))),
+    };
+    Ok(Node::new(expr))
+}
+
+fn splice_values<'v, I: Iterator<Item = &'v ValueRef>>(
+    values: I,
+) -> Result<Vec<Node<Expression>>, EvalError> {
+    values.map(splice_value).collect()
+}
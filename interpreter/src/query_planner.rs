@@ -1,10 +1,9 @@
-use std::rc::Rc;
-
-use crate::{EvalContext, EvalError, Evaluate, ValueRef};
+use crate::{call_stack::CallFrame, EvalContext, EvalError, Evaluate, ValueRef};
 
 use rogato_common::ast::{
     expression::{Expression, Query},
     query::QueryBinding,
+    Node, Span,
 };
 use thiserror::Error;
 
@@ -24,22 +23,59 @@ pub enum QueryError {
     GuardFailed(Box<EvalError>),
 
     #[error("Query guard condition was false: {0}")]
-    GuardConditionFalse(Rc<Expression>),
+    GuardConditionFalse(Node<Expression>),
 
     #[error("Query guard condition was None: {0}")]
-    GuardConditionNone(Rc<Expression>),
+    GuardConditionNone(Node<Expression>),
 
     #[error("Query binding failed: {0:?}")]
     BindingFailed(QueryBindingError),
 
     #[error("Query production failed: {0}")]
     ProductionFailed(Box<EvalError>),
+
+    #[error("{1} at {0}")]
+    Spanned(Span, Box<QueryError>),
+}
+
+impl QueryError {
+    pub fn with_span(self, span: Option<&Span>) -> QueryError {
+        match span {
+            Some(span) if self.span().is_none() => {
+                QueryError::Spanned(span.clone(), Box::new(self))
+            }
+            _ => self,
+        }
+    }
+
+    /// The evaluation error the query failed with, if any.
+    pub fn eval_error(&self) -> Option<&EvalError> {
+        match self {
+            QueryError::UnknownEvalError(e)
+            | QueryError::GuardFailed(e)
+            | QueryError::ProductionFailed(e)
+            | QueryError::BindingFailed(QueryBindingError::BindingFailedWith(_, e)) => Some(e),
+            QueryError::Spanned(_, e) => e.eval_error(),
+            _ => None,
+        }
+    }
+
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            QueryError::Spanned(span, _) => Some(span),
+            QueryError::UnknownEvalError(e)
+            | QueryError::GuardFailed(e)
+            | QueryError::ProductionFailed(e) => e.span(),
+            _ => None,
+        }
+    }
 }
 
 impl From<EvalError> for QueryError {
     fn from(e: EvalError) -> Self {
         match e {
             EvalError::QueryFailed(qfe) => qfe,
+            EvalError::Spanned(span, e) => QueryError::from(*e).with_span(Some(&span)),
             _ => Self::UnknownEvalError(Box::new(e)),
         }
     }
@@ -67,17 +103,23 @@ impl QueryPlanner {
         // - return query production if all guards hold
 
         for binding in query.bindings().iter() {
-            match self.attempt_binding(binding, &mut query_ctx) {
+            let frame = CallFrame::Query("binding", binding.val());
+            match query_ctx.in_frame(frame, |ctx| self.attempt_binding(binding, ctx)) {
                 Ok(_) => {}
-                Err(e) => return Err(QueryError::BindingFailed(e)),
+                Err(e) => return Err(QueryError::BindingFailed(e).with_span(binding.val().span())),
             }
         }
 
         query.guards().evaluate(&mut query_ctx)?;
 
-        query
-            .production()
-            .evaluate(&mut query_ctx)
+        let frame = CallFrame::Query("production", Node::clone(query.production()));
+        query_ctx
+            .in_frame(frame, |ctx| {
+                query
+                    .production()
+                    .evaluate(ctx)
+                    .map_err(|e| ctx.with_call_trace(e))
+            })
             .map_err(|e| QueryError::ProductionFailed(Box::new(e)))
     }
 
@@ -105,7 +147,7 @@ impl QueryPlanner {
             }
             Err(e) => Err(QueryBindingError::BindingFailedWith(
                 binding.clone(),
-                Box::new(e),
+                Box::new(context.with_call_trace(e)),
             )),
         }
     }
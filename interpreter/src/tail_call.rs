@@ -0,0 +1,77 @@
+use std::{cell::RefCell, rc::Rc};
+
+use rogato_common::{
+    ast::{
+        fn_def::FnDef,
+        lambda::{Lambda, LambdaClosureContext},
+    },
+    val::ValueRef,
+};
+
+use crate::{
+    call_stack::{CallArgs, CallFrame},
+    EvalContext, EvalError,
+};
+
+/// A call in tail position that's left for the caller to perform, so that chains of tail calls
+/// run in a loop (see `EvalContext::perform_call`) instead of growing the stack.
+#[derive(Clone)]
+pub enum TailCall {
+    /// A function call along with the context it was made from.
+    Fn(EvalContext, Rc<RefCell<FnDef>>, Vec<ValueRef>),
+    /// A lambda call along with the lambda's closure context.
+    Lambda(EvalContext, Rc<Lambda>, Vec<ValueRef>),
+}
+
+impl TailCall {
+    /// A lambda call, if the lambda's closure context is an `EvalContext`.
+    pub fn lambda(
+        lambda_ctx: &Rc<RefCell<dyn LambdaClosureContext>>,
+        lambda: &Rc<Lambda>,
+        args: Vec<ValueRef>,
+    ) -> Option<TailCall> {
+        lambda_ctx
+            .borrow()
+            .as_any()
+            .downcast_ref::<EvalContext>()
+            .map(|closure_ctx| TailCall::Lambda(closure_ctx.clone(), Rc::clone(lambda), args))
+    }
+
+    pub fn frame(&self) -> CallFrame {
+        match self {
+            TailCall::Fn(_, func, args) => {
+                let func = func.borrow();
+                if func.is_native() {
+                    CallFrame::NativeFn(func.id().clone(), CallArgs::new(args))
+                } else {
+                    CallFrame::Fn(func.id().clone(), CallArgs::new(args))
+                }
+            }
+            TailCall::Lambda(_, lambda, args) => {
+                CallFrame::Lambda(Rc::clone(lambda), CallArgs::new(args))
+            }
+        }
+    }
+}
+
+/// The result of evaluating an expression in tail position.
+pub enum Tail {
+    Value(ValueRef),
+    Call(TailCall),
+}
+
+impl Tail {
+    /// Performs the tail call, if any, from within the given context.
+    pub fn resolve(self, context: &mut EvalContext) -> Result<ValueRef, EvalError> {
+        match self {
+            Tail::Value(val) => Ok(val),
+            Tail::Call(call) => context.perform_call(call),
+        }
+    }
+}
+
+/// Evaluation of expressions that can contain calls in tail position, like a function's body.
+/// Instead of performing these calls, they're returned as a `Tail::Call`.
+pub trait EvaluateTail {
+    fn evaluate_tail(&self, context: &mut EvalContext) -> Result<Tail, EvalError>;
+}
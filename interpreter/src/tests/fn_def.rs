@@ -1,6 +1,13 @@
-use rogato_common::ast::{
-    fn_def::{FnDef, FnDefArgs, FnDefBody},
-    helpers::{any_p, empty_list_p, fn_call, list_cons_p, number_lit, op_call, var, var_p},
+use crate::EvalContext;
+use rogato_common::{
+    ast::{
+        fn_def::{FnDef, FnDefArgs, FnDefBody},
+        helpers::{
+            any_p, bool_lit, empty_list_p, fn_call, list_cons_p, number_lit, number_p, op_call,
+            var, var_p,
+        },
+    },
+    val,
 };
 
 #[test]
@@ -11,8 +18,6 @@ fn tail_recursive_fn_defs() {
         FnDefBody::RogatoFn(var("acc")).into(),
     );
 
-    assert!(!rec_fn_def.borrow().is_tail_recursive());
-
     rec_fn_def.borrow_mut().add_variant(
         FnDefArgs::new(vec![var_p("acc"), list_cons_p(any_p(), var_p("rest"))]),
         FnDefBody::RogatoFn(fn_call(
@@ -22,5 +27,53 @@ fn tail_recursive_fn_defs() {
         .into(),
     );
 
-    assert!(rec_fn_def.borrow().is_tail_recursive());
+    let mut eval_ctx = EvalContext::new();
+    for variant in rec_fn_def.borrow().variants_iter() {
+        eval_ctx.define_fn(rec_fn_def.borrow().id(), variant.clone());
+    }
+
+    let list = val::list((0..100_000).map(val::number).collect::<Vec<_>>());
+    assert_eq!(
+        eval_ctx.call_function_direct(rec_fn_def, &[val::number(0), list]),
+        Ok(val::number(100_000))
+    );
+}
+
+#[test]
+fn mutually_tail_recursive_fn_defs() {
+    let is_even = FnDef::new(
+        "isEven",
+        FnDefArgs::new(vec![number_p(0)]),
+        FnDefBody::RogatoFn(bool_lit(true)).into(),
+    );
+    is_even.borrow_mut().add_variant(
+        FnDefArgs::new(vec![var_p("n")]),
+        FnDefBody::RogatoFn(fn_call("isOdd", [op_call("-", var("n"), number_lit(1))])).into(),
+    );
+
+    let is_odd = FnDef::new(
+        "isOdd",
+        FnDefArgs::new(vec![number_p(0)]),
+        FnDefBody::RogatoFn(bool_lit(false)).into(),
+    );
+    is_odd.borrow_mut().add_variant(
+        FnDefArgs::new(vec![var_p("n")]),
+        FnDefBody::RogatoFn(fn_call("isEven", [op_call("-", var("n"), number_lit(1))])).into(),
+    );
+
+    let mut eval_ctx = EvalContext::new();
+    for fn_def in [&is_even, &is_odd] {
+        for variant in fn_def.borrow().variants_iter() {
+            eval_ctx.define_fn(fn_def.borrow().id(), variant.clone());
+        }
+    }
+
+    assert_eq!(
+        eval_ctx.call_function_direct(is_even.clone(), &[val::number(100_000)]),
+        Ok(val::bool(true))
+    );
+    assert_eq!(
+        eval_ctx.call_function_direct(is_odd, &[val::number(100_000)]),
+        Ok(val::bool(false))
+    );
 }
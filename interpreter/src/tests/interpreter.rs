@@ -1,6 +1,12 @@
-use crate::{EvalContext, Evaluate};
-use rogato_common::val::{self};
-use rogato_parser::{parse_expr, ParserContext};
+use std::error::Error;
+
+use crate::{macro_expander::MacroExpander, EvalContext, EvalError, Evaluate, NativeFnError};
+use rogato_common::{
+    ast::{type_expression::TypeExpression, Node},
+    error::BoxedError,
+    val,
+};
+use rogato_parser::{parse, parse_expr, ParserContext};
 
 #[test]
 fn basic_arithmetic() {
@@ -18,6 +24,12 @@ fn basic_arithmetic() {
         ("500 % 28", val::number(24)),
         ("10 ^ 2", val::number(100)),
         ("10 ^ 10", val::number(10000000000i64)),
+        ("1 + 2 * 3", val::number(7)),
+        ("(1 + 2) * 3", val::number(9)),
+        ("10 - 5 - 2", val::number(3)),
+        ("2 ^ 3 ^ 2", val::number(512)),
+        ("100 / 10 / 5", val::number(2)),
+        ("1 + 2 * 3 == 7", val::bool(true)),
     ];
 
     let mut eval_ctx = EvalContext::new();
@@ -54,6 +66,9 @@ fn string_literals() {
         "  ",
         "Hello, World!",
         "My name is John Connor",
+        "She said \"hi\"\n",
+        "tab\tand\\backslash",
+        "\u{1F600}",
     ];
 
     for string_lit in string_literals.iter() {
@@ -65,6 +80,32 @@ fn string_literals() {
     }
 }
 
+#[test]
+fn string_interpolation() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let expressions_and_values = [
+        (r#""${1 + 2}""#, "3"),
+        (
+            r#"let name = "World" in "Hello, ${name}!""#,
+            "Hello, World!",
+        ),
+        (
+            r#"let x = 2, y = 3 in "${x} * ${y} = ${x * y}""#,
+            "2 * 3 = 6",
+        ),
+        (r#"let s = ^foo in "symbol: ${s}""#, "symbol: ^foo"),
+        (r#""nested ${"<${1}>"} \${1}""#, "nested <1> ${1}"),
+        ("\"\"\"multi\nline ${true}\"\"\"", "multi\nline true"),
+    ];
+
+    for (code, value) in expressions_and_values.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val::string(value)));
+    }
+}
+
 #[test]
 fn let_expressions() {
     let mut eval_ctx = EvalContext::new();
@@ -157,21 +198,21 @@ fn equality() {
     let parser_ctx = ParserContext::new();
 
     for (left, right) in not_equal.iter() {
-        let code = format!("{left} == {right}");
+        let code = format!("({left}) == {right}");
         let ast = parse_expr(code.as_str(), &parser_ctx).unwrap();
         assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val::bool(false)));
 
-        let code = format!("{left} != {right}");
+        let code = format!("({left}) != {right}");
         let ast = parse_expr(code.as_str(), &parser_ctx).unwrap();
         assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val::bool(true)));
     }
 
     for (left, right) in equal.iter() {
-        let code = format!("{left} == {right}");
+        let code = format!("({left}) == {right}");
         let ast = parse_expr(code.as_str(), &parser_ctx).unwrap();
         assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val::bool(true)));
 
-        let code = format!("{left} != {right}");
+        let code = format!("({left}) != {right}");
         let ast = parse_expr(code.as_str(), &parser_ctx).unwrap();
         assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val::bool(false)));
     }
@@ -512,3 +553,1656 @@ fn patterns() {
         assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()));
     }
 }
+
+#[test]
+fn error_spans() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let ast = parse_expr("let x = 1 in\n  {x, undefined_fn x}", &parser_ctx).unwrap();
+    let error = ast.evaluate(&mut eval_ctx).unwrap_err();
+    assert_eq!(error.span().map(|s| s.to_string()), Some("2:7".into()));
+    match error {
+        EvalError::Spanned(_, error) => {
+            assert_eq!(*error, EvalError::FunctionNotDefined("undefined_fn".into()))
+        }
+        _ => panic!("Expected spanned error but got: {error}"),
+    }
+
+    // code passed to a macro keeps its span when the macro gets expanded
+    let program = parse(
+        "macro double expr = ^(let tmp = ~expr in tmp + tmp)",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+    let ast = parse_expr("let x = 1 in\n  double (undefined_fn x)", &parser_ctx).unwrap();
+    let error = eval_ctx.evaluate_expression(&ast).unwrap_err();
+    assert_eq!(error.span().map(|s| s.to_string()), Some("2:11".into()));
+
+    // failed pattern matches point to the call that failed
+    let program = parse("let one 1 = 1", &parser_ctx).unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+    let ast = parse_expr("let x = 2 in\n  {x, one x}", &parser_ctx).unwrap();
+    let error = eval_ctx.evaluate_expression(&ast).unwrap_err();
+    assert_eq!(error.span().map(|s| s.to_string()), Some("2:7".into()));
+    match error.without_call_trace() {
+        EvalError::PatternMatchFailed(id, error) => {
+            assert_eq!(id, "one");
+            assert_eq!(error.span().map(|s| s.to_string()), Some("2:7".into()));
+            assert!(error.to_string().ends_with(" at 2:7"), "{error}");
+        }
+        error => panic!("Expected pattern match failure but got: {error}"),
+    }
+}
+
+#[test]
+fn union_types() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "type Maybe
+           = Just val
+           | Nothing
+
+         let withDefault (Just val) _ =
+           val
+         let withDefault Nothing default =
+           default
+
+         let mapMaybe (Just val) f =
+           Just (f val)
+         let mapMaybe Nothing _ =
+           Nothing",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let code_with_vals = [
+        (
+            "Just 1",
+            val::variant("Std.Maybe", "Just", [val::number(1)]),
+        ),
+        ("Nothing", val::variant("Std.Maybe", "Nothing", [])),
+        (
+            "Just (Just ^foo)",
+            val::variant(
+                "Std.Maybe",
+                "Just",
+                [val::variant("Std.Maybe", "Just", [val::symbol("foo")])],
+            ),
+        ),
+        ("withDefault (Just 1) 2", val::number(1)),
+        ("withDefault Nothing 2", val::number(2)),
+        (
+            "mapMaybe (Just 1) (x -> x * 3)",
+            val::variant("Std.Maybe", "Just", [val::number(3)]),
+        ),
+        (
+            "mapMaybe Nothing (x -> x * 3)",
+            val::variant("Std.Maybe", "Nothing", []),
+        ),
+        ("(Just 1) == (Just 1)", val::bool(true)),
+        ("(Just 1) == (Just 2)", val::bool(false)),
+        ("toString (Just (Just 1))", val::string("Just (Just 1)")),
+    ];
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()), "{code}");
+    }
+
+    let code_with_errors = [
+        (
+            "Just",
+            EvalError::ConstructorArityMismatch("Just".into(), 1, 0),
+        ),
+        (
+            "Just 1 2",
+            EvalError::ConstructorArityMismatch("Just".into(), 1, 2),
+        ),
+        (
+            "Nothing 1",
+            EvalError::ConstructorArityMismatch("Nothing".into(), 0, 1),
+        ),
+    ];
+
+    for (code, error) in code_with_errors.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        let result = ast.evaluate(&mut eval_ctx);
+        assert_eq!(
+            result.as_ref().map_err(|e| e.root_cause()),
+            Err(error),
+            "{code}"
+        );
+    }
+}
+
+#[test]
+fn case_expressions() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "type Maybe
+           = Just val
+           | Nothing
+
+         let describe x =
+           case x of
+             (Just 0) -> \"just zero\"
+             (Just n) -> \"just ${n}\"
+             Nothing -> \"nothing\"
+
+         let head xs =
+           case xs of
+             [] -> Nothing
+             [x :: _] -> Just x
+
+         let sum xs =
+           case xs of
+             [] -> 0
+             [x :: rest] -> x + (sum rest)",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let code_with_vals = [
+        ("describe (Just 0)", val::string("just zero")),
+        ("describe (Just 5)", val::string("just 5")),
+        ("describe Nothing", val::string("nothing")),
+        ("head []", val::variant("Std.Maybe", "Nothing", [])),
+        (
+            "head [^a, ^b]",
+            val::variant("Std.Maybe", "Just", [val::symbol("a")]),
+        ),
+        ("sum [1, 2, 3, 4]", val::number(10)),
+        ("case {1, 2} of {a, b} -> a + b", val::number(3)),
+        ("let x = 1 in case 2 of x -> x", val::number(2)),
+        (
+            "let x = 1 in {(case 2 of y -> y), x}",
+            val::tuple([val::number(2), val::number(1)]),
+        ),
+    ];
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()), "{code}");
+    }
+
+    let ast = parse_expr("case Just 42 of Nothing -> 0", &parser_ctx).unwrap();
+    let error = ast.evaluate(&mut eval_ctx).unwrap_err();
+    assert_eq!(error.span().map(|s| s.to_string()), Some("1:1".into()));
+    match error {
+        EvalError::Spanned(_, error) => assert_eq!(
+            *error,
+            EvalError::CaseNoArmMatched(val::variant("Std.Maybe", "Just", [val::number(42)]))
+        ),
+        _ => panic!("Expected spanned error but got: {error}"),
+    }
+}
+
+#[test]
+fn guards() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "let classify n when n < 0 =
+           ^negative
+         let classify 0 =
+           ^zero
+         let classify n when n > 100 =
+           ^large
+         let classify _ =
+           ^positive
+
+         let countDown n acc when n > 0 =
+           countDown (n - 1) (acc + n)
+         let countDown _ acc =
+           acc
+
+         let sign n =
+           let s x when x < 0 = -1
+               s x = 1
+           in s n",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let code_with_vals = [
+        ("classify -5", val::symbol("negative")),
+        ("classify 0", val::symbol("zero")),
+        ("classify 1000", val::symbol("large")),
+        ("classify 7", val::symbol("positive")),
+        ("countDown 100 0", val::number(5050)),
+        ("sign -3", val::number(-1)),
+        ("sign 3", val::number(1)),
+        (
+            "let f = (x when x > 10 -> ^big, x when x > 5 -> ^medium, _ -> ^small) in
+             {f 20, f 7, f 1}",
+            val::tuple([
+                val::symbol("big"),
+                val::symbol("medium"),
+                val::symbol("small"),
+            ]),
+        ),
+        (
+            "let f = ({a, b} when a == b -> ^same, _ -> ^different) in
+             {f {1, 1}, f {1, 2}}",
+            val::tuple([val::symbol("same"), val::symbol("different")]),
+        ),
+    ];
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()), "{code}");
+    }
+
+    let program = parse("let notBool n when n = n", &parser_ctx).unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+    let ast = parse_expr("notBool 1", &parser_ctx).unwrap();
+    let error = ast
+        .evaluate(&mut eval_ctx)
+        .unwrap_err()
+        .without_call_trace();
+    match error {
+        EvalError::Spanned(_, error) => {
+            assert_eq!(*error, EvalError::GuardNotBool(val::number(1)))
+        }
+        _ => panic!("Expected spanned error but got: {error}"),
+    }
+}
+
+#[test]
+fn rich_patterns() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "let flip ^on = ^off
+         let flip ^off = ^on
+
+         type Person :: {
+           name :: String
+           age :: Int
+         }
+
+         let greet Person{name: n, age: 0} = \"Welcome, baby ${n}\"
+         let greet p@Person{name: n} = {n, p}
+
+         let single {k: v} = {k, v}
+
+         let varName _{kind: ^Var, id: id} = id
+         let varName _ = ^none
+
+         let nameOf p@{name: n} = {n, p}
+         let nameOf {id: i} = i
+         let nameOf _ = ^none
+
+         let pick ({x, 0} | {0, x}) = x
+
+         let isSmall (1 | 2 | 3) = true
+         let isSmall _ = false
+
+         let root n =
+           case sqrt n of
+             (Some r) -> r
+             None -> ^imaginary
+
+         let firstTwice all@[x :: _] = {x, all}",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let code_with_vals = [
+        ("flip ^on", val::symbol("off")),
+        ("flip (flip ^on)", val::symbol("on")),
+        (
+            "greet Person{name: \"Ann\", age: 0}",
+            val::string("Welcome, baby Ann"),
+        ),
+        (
+            "greet Person{name: \"Bob\", age: 42}",
+            val::tuple([
+                val::string("Bob"),
+                val::struct_val(
+                    "Std.Person",
+                    [("name", val::string("Bob")), ("age", val::number(42))],
+                ),
+            ]),
+        ),
+        ("varName (Std.AST.toData ^(x))", val::string("x")),
+        ("varName (Std.AST.toData ^(f x))", val::symbol("none")),
+        ("varName Person{name: \"Ann\", age: 0}", val::symbol("none")),
+        (
+            "case Person{name: \"Ann\", age: 0} of _{name: n} -> n",
+            val::string("Ann"),
+        ),
+        (
+            "nameOf Person{name: \"Ann\", age: 0}",
+            val::tuple([
+                val::string("Ann"),
+                val::struct_val(
+                    "Std.Person",
+                    [("name", val::string("Ann")), ("age", val::number(0))],
+                ),
+            ]),
+        ),
+        ("nameOf (Std.AST.toData ^(x))", val::string("x")),
+        ("nameOf 42", val::symbol("none")),
+        (
+            "single {^a: 1}",
+            val::tuple([val::symbol("a"), val::number(1)]),
+        ),
+        ("pick {0, 5}", val::number(5)),
+        ("pick {7, 0}", val::number(7)),
+        ("isSmall 2", val::bool(true)),
+        ("isSmall 4", val::bool(false)),
+        ("root 16", val::number(4)),
+        ("root -1", val::symbol("imaginary")),
+        (
+            "firstTwice [1, 2]",
+            val::tuple([val::number(1), val::list([val::number(1), val::number(2)])]),
+        ),
+        (
+            "case ^b of
+               ^a | ^b -> ^first
+               _ -> ^second",
+            val::symbol("first"),
+        ),
+    ];
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()), "{code}");
+    }
+}
+
+#[test]
+fn mismatched_pattern_types() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "let describe [] = \"empty list\"
+         let describe 0 = \"zero\"
+         let describe {a, b} = \"pair\"
+         let describe ^sym = \"symbol\"
+         let describe [x :: _] = \"non-empty list\"
+
+         let onlyLists [] = 0
+         let onlyLists [x] = 1",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let code_with_vals = [
+        ("describe []", val::string("empty list")),
+        ("describe 0", val::string("zero")),
+        ("describe {1, 2}", val::string("pair")),
+        ("describe ^sym", val::string("symbol")),
+        ("describe [1, 2]", val::string("non-empty list")),
+        (
+            "case 1 of
+               [] -> ^list
+               \"1\" -> ^string
+               1 -> ^number",
+            val::symbol("number"),
+        ),
+    ];
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()), "{code}");
+    }
+
+    let ast = parse_expr("onlyLists 42", &parser_ctx).unwrap();
+    let error = ast.evaluate(&mut eval_ctx).unwrap_err();
+    assert_eq!(
+        error
+            .to_string()
+            .lines()
+            .skip(1)
+            .take(2)
+            .collect::<Vec<_>>(),
+        vec!["    onlyLists []", "    onlyLists [ x ] at 1:1"],
+        "{error}"
+    );
+}
+
+#[test]
+fn module_exports() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "module Secret { reveal, Shape, makePt }
+
+         let reveal x = double x
+         let double x = x * 2
+
+         type Pt :: {
+           x :: Int
+         }
+
+         let makePt x = Pt{x: x}
+
+         type Shape = Circle r | Square s
+         type Token = Token val
+
+         let area (Square s) = s * s
+         let inside = double 21
+
+         module Other { }
+
+         use Secret { reveal, double, Circle, Square }",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let code_with_vals = [
+        ("reveal 2", val::number(4)),
+        ("Secret.reveal 3", val::number(6)),
+        (
+            "Circle 1",
+            val::variant("Secret.Shape", "Circle", [val::number(1)]),
+        ),
+        (
+            "Secret.Square 2",
+            val::variant("Secret.Shape", "Square", [val::number(2)]),
+        ),
+    ];
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()), "{code}");
+    }
+
+    let private_access = [
+        ("double 2", "double"),
+        ("Secret.double 2", "double"),
+        ("Secret.area (Square 2)", "area"),
+        ("Secret.Token 1", "Token"),
+        ("Secret.inside", "inside"),
+        ("Secret.Pt{x: 1}", "Pt"),
+        ("{(Secret.makePt 0) | x: 1}", "Pt"),
+    ];
+
+    for (code, id) in private_access.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        let error = match ast.evaluate(&mut eval_ctx).unwrap_err() {
+            EvalError::Spanned(_, error) => *error,
+            error => error,
+        };
+        assert_eq!(
+            error,
+            EvalError::PrivateDefinition("Secret".into(), (*id).into()),
+            "{code}"
+        );
+    }
+
+    // code within the module itself has full access
+    let program = parse("module Secret { }", &parser_ctx).unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+    let ast = parse_expr("double inside", &parser_ctx).unwrap();
+    assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val::number(84)));
+}
+
+#[test]
+fn use_aliases() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "module Shapes { Shape, area, perimeter }
+
+         type Shape = Circle r | Square s
+
+         let area (Square s) = s * s
+         let perimeter (Square s) = s * 4
+
+         module Main { }
+
+         use Shapes as S
+         use Shapes { area as size, Type Shape as Form, Square }
+         use Std.List { * }",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let code_with_vals = [
+        ("S.area (Square 2)", val::number(4)),
+        ("S.perimeter (S.Square 2)", val::number(8)),
+        ("size (Square 3)", val::number(9)),
+        (
+            "reverse [1, 2, 3]",
+            val::list([val::number(3), val::number(2), val::number(1)]),
+        ),
+    ];
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()), "{code}");
+    }
+
+    let form = parse_expr("Form", &parser_ctx)
+        .unwrap()
+        .evaluate(&mut eval_ctx)
+        .unwrap();
+    assert!(form.to_string().contains("Shape"), "{form}");
+}
+
+#[test]
+fn constructors_are_matched_by_type() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "module Chess { Piece, isKing }
+
+         type Piece = King Int | Pawn Int
+
+         let isKing (King _) = true
+         let isKing _ = false
+
+         module Cards { Card }
+
+         type Card = King Int | Ace Int
+
+         module Main { }
+
+         use Chess as C
+         use Chess { isKing }
+         use Cards as K",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let code_with_vals = [
+        ("isKing (C.King 1)", val::bool(true)),
+        ("isKing (K.King 1)", val::bool(false)),
+        ("(C.King 1) == (K.King 1)", val::bool(false)),
+        (
+            "C.King 1",
+            val::variant("Chess.Piece", "King", [val::number(1)]),
+        ),
+        (
+            "K.King 1",
+            val::variant("Cards.Card", "King", [val::number(1)]),
+        ),
+    ];
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()), "{code}");
+    }
+}
+
+#[test]
+fn typed_structs() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "type Person :: {
+           name :: String
+           age :: Int
+           tags :: [String]
+         }
+
+         type Point :: {
+           x :: Int
+           y :: Int
+         }
+
+         type Location :: {
+           lat :: Lattitude
+           long :: Int
+         }
+
+         type Box :: {
+           item :: val
+         }
+
+         type Shape = Circle Int | Empty
+
+         let name Person{name: n} = n
+         let name Point{x: x} = x
+         let name _ = ^unknown",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let code_with_vals = [
+        (
+            "Person{name: \"Ann\", age: 42, tags: [\"admin\"]}",
+            val::struct_val(
+                "Std.Person",
+                [
+                    ("name", val::string("Ann")),
+                    ("age", val::number(42)),
+                    ("tags", val::list([val::string("admin")])),
+                ],
+            ),
+        ),
+        (
+            "name Person{name: \"Ann\", age: 42, tags: []}",
+            val::string("Ann"),
+        ),
+        ("name Point{x: 1, y: 2}", val::number(1)),
+        ("name 42", val::symbol("unknown")),
+        (
+            "Box{item: [1]}",
+            val::struct_val("Std.Box", [("item", val::list([val::number(1)]))]),
+        ),
+    ];
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()), "{code}");
+    }
+
+    let point = val::struct_val("Point", [("x", val::number(1)), ("y", val::number(2))]);
+    assert_ne!(
+        point,
+        val::object([("x", val::number(1)), ("y", val::number(2))])
+    );
+    assert_eq!(point.to_string(), "Point{ x: 1, y: 2 }");
+
+    let code_with_errors = [
+        (
+            "Animal{name: \"Rex\"}",
+            EvalError::StructTypeNotFound("Animal".into()),
+        ),
+        ("Shape{size: 1}", EvalError::NotAStructType("Shape".into())),
+        (
+            "Location{lat: 1, long: 2}",
+            EvalError::TypeNotFound("Lattitude".into()),
+        ),
+        (
+            "Point{x: 1}",
+            EvalError::StructPropertyMissing("Point".into(), "y".into()),
+        ),
+        (
+            "Point{x: 1, y: 2, z: 3}",
+            EvalError::StructPropertyUnknown("Point".into(), "z".into()),
+        ),
+        (
+            "Point{x: 1, y: \"2\"}",
+            EvalError::StructPropertyTypeMismatch(
+                "Point".into(),
+                "y".into(),
+                Node::new(TypeExpression::NumberType),
+                val::string("2"),
+            ),
+        ),
+        (
+            "Person{name: \"Ann\", age: 42, tags: [1]}",
+            EvalError::StructPropertyTypeMismatch(
+                "Person".into(),
+                "tags".into(),
+                Node::new(TypeExpression::ListType(Node::new(
+                    TypeExpression::StringType,
+                ))),
+                val::list([val::number(1)]),
+            ),
+        ),
+    ];
+
+    for (code, error) in code_with_errors.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        match ast.evaluate(&mut eval_ctx) {
+            Err(EvalError::Spanned(_, e)) => assert_eq!(*e, *error, "{code}"),
+            result => assert_eq!(result, Err(error.clone()), "{code}"),
+        }
+    }
+}
+
+#[test]
+fn structs_across_modules() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "module Geo { Pt, origin }
+
+         type Pt :: {
+           x :: Int
+           y :: Int
+         }
+
+         let origin = Pt{x: 0, y: 0}
+
+         module Main { }
+
+         use Geo { origin }
+
+         type Pt :: {
+           x :: Int
+           y :: Int
+         }
+
+         type Holder :: {
+           pt :: Geo.Pt
+         }
+
+         let local = Pt{x: 0, y: 0}
+
+         let isLocal Pt{x: _} = true
+         let isLocal _ = false",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    // struct values keep the qualified id of their type once they leave its module
+    let geo_origin = val::struct_val("Geo.Pt", [("x", val::number(0)), ("y", val::number(0))]);
+    let code_with_vals = [
+        ("origin", geo_origin.clone()),
+        (
+            "local",
+            val::struct_val("Main.Pt", [("x", val::number(0)), ("y", val::number(0))]),
+        ),
+        ("origin == local", val::bool(false)),
+        ("isLocal local", val::bool(true)),
+        ("isLocal origin", val::bool(false)),
+        (
+            "Holder{pt: origin}",
+            val::struct_val("Main.Holder", [("pt", geo_origin)]),
+        ),
+    ];
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()), "{code}");
+    }
+
+    let ast = parse_expr("Holder{pt: local}", &parser_ctx).unwrap();
+    let error = ast.evaluate(&mut eval_ctx).unwrap_err();
+    assert!(
+        matches!(error.root_cause(), EvalError::StructPropertyTypeMismatch(type_id, prop, _, _) if type_id == "Holder" && prop == "pt"),
+        "{error:?}"
+    );
+}
+
+#[test]
+fn property_access() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "type Location :: {
+           lat :: Int
+           long :: Int
+         }
+
+         type City :: {
+           name :: String
+           location :: Location
+         }
+
+         let osna = City{name: \"Osnabrück\", location: Location{lat: 52, long: 8}}
+
+         let isNorth c = c.location.lat > 50",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let code_with_vals = [
+        ("osna.name", val::string("Osnabrück")),
+        ("osna.location.lat", val::number(52)),
+        ("isNorth osna", val::bool(true)),
+        (
+            "Std.List.map [osna] .location.long",
+            val::list([val::number(8)]),
+        ),
+        (
+            "Std.List.map [osna, osna] .name",
+            val::list([val::string("Osnabrück"), val::string("Osnabrück")]),
+        ),
+    ];
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()), "{code}");
+    }
+
+    let ast = parse_expr("osna.population", &parser_ctx).unwrap();
+    let error = ast.evaluate(&mut eval_ctx).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .starts_with("Property population not found in: Std.City{"),
+        "{error}"
+    );
+
+    let ast = parse_expr("let n = 1 in n.value", &parser_ctx).unwrap();
+    let error = ast.evaluate(&mut eval_ctx).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .starts_with("Property value not found in: 1"),
+        "{error}"
+    );
+}
+
+#[test]
+fn struct_updates() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "type Person :: {
+           name :: String
+           age :: Int
+         }
+
+         let ann = Person{name: \"Ann\", age: 41}
+
+         let birthday p = { p | age: p.age + 1 }",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let code_with_vals = [
+        (
+            "birthday ann",
+            val::struct_val(
+                "Std.Person",
+                [("name", val::string("Ann")), ("age", val::number(42))],
+            ),
+        ),
+        (
+            "{ann | name: \"Bob\", age: 1}",
+            val::struct_val(
+                "Std.Person",
+                [("name", val::string("Bob")), ("age", val::number(1))],
+            ),
+        ),
+        ("ann.age", val::number(41)),
+    ];
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()), "{code}");
+    }
+
+    let code_with_errors = [
+        (
+            "{ann | height: 180}",
+            EvalError::StructPropertyUnknown("Person".into(), "height".into()),
+        ),
+        (
+            "{ann | age: \"old\"}",
+            EvalError::StructPropertyTypeMismatch(
+                "Person".into(),
+                "age".into(),
+                Node::new(TypeExpression::NumberType),
+                val::string("old"),
+            ),
+        ),
+        (
+            "{(1 + 2) | age: 3}",
+            EvalError::StructUpdateInvalidValue(val::number(3)),
+        ),
+    ];
+
+    for (code, error) in code_with_errors.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        match ast.evaluate(&mut eval_ctx) {
+            Err(EvalError::Spanned(_, e)) => assert_eq!(*e, *error, "{code}"),
+            result => assert_eq!(result, Err(error.clone()), "{code}"),
+        }
+    }
+}
+
+#[test]
+fn struct_updates_across_modules() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "module Geo { Pt, origin }
+
+         type Pt :: {
+           x :: Int
+           y :: Int
+         }
+
+         let origin = Pt{x: 0, y: 0}
+
+         module Main { }
+
+         use Geo { origin }
+
+         type Pt :: {
+           x :: String
+         }",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    // updates are checked against the value's own type, even if another one has the same name here
+    let ast = parse_expr("{origin | x: 2}", &parser_ctx).unwrap();
+    assert_eq!(
+        ast.evaluate(&mut eval_ctx),
+        Ok(val::struct_val(
+            "Geo.Pt",
+            [("x", val::number(2)), ("y", val::number(0))]
+        ))
+    );
+
+    let ast = parse_expr("{origin | x: \"2\"}", &parser_ctx).unwrap();
+    let error = ast.evaluate(&mut eval_ctx).unwrap_err();
+    assert!(
+        matches!(error.root_cause(), EvalError::StructPropertyTypeMismatch(type_id, prop, _, _) if type_id == "Pt" && prop == "x"),
+        "{error:?}"
+    );
+}
+
+#[test]
+fn call_traces() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "let half x = x / 2
+         let fail x = half (unknown x)
+         let failAll xs = Std.List.map xs (x -> fail x)
+         let safe x = x + 1
+         let failMany a b c d e f = fail a",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let ast = parse_expr("failAll [1, 2]", &parser_ctx).unwrap();
+    let error = ast.evaluate(&mut eval_ctx).unwrap_err();
+    let trace = error.call_trace().expect("Expected a call trace");
+    let frames: Vec<String> = trace.frames().map(|frame| frame.to_string()).collect();
+    assert_eq!(
+        frames,
+        [
+            "fn fail 1",
+            "lambda ( x -> (fail x) ) 1",
+            "native fn map [ 1, 2 ] ( x -> (fail x) )",
+            "fn failAll [ 1, 2 ]",
+        ]
+    );
+
+    let report = error.report();
+    assert!(report.starts_with(&error.to_string()), "{report}");
+    assert!(
+        report.ends_with(
+            "Call stack (most recent call first):
+    at fn fail 1
+    at lambda ( x -> (fail x) ) 1
+    at native fn map [ 1, 2 ] ( x -> (fail x) )
+    at fn failAll [ 1, 2 ]"
+        ),
+        "{report}"
+    );
+
+    // the trace of a failed call doesn't stick around for later calls
+    let ast = parse_expr("safe 1", &parser_ctx).unwrap();
+    assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val::number(2)));
+    let ast = parse_expr("unknown 1", &parser_ctx).unwrap();
+    assert_eq!(ast.evaluate(&mut eval_ctx).unwrap_err().call_trace(), None);
+
+    // frames only keep the first few arguments
+    let ast = parse_expr("failMany 1 2 3 4 5 6", &parser_ctx).unwrap();
+    let error = ast.evaluate(&mut eval_ctx).unwrap_err();
+    let trace = error.call_trace().expect("Expected a call trace");
+    let frames: Vec<String> = trace.frames().map(|frame| frame.to_string()).collect();
+    assert_eq!(frames, ["fn fail 1", "fn failMany 1 2 3 4 … (2 more)"]);
+}
+
+#[test]
+fn errors_chained_through_native_fns() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse("let one 1 = 1", &parser_ctx).unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    for code in [
+        "Std.List.map [1, 2] ^one",
+        "Std.List.map [1, 2] (x -> one x)",
+    ] {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        let error = ast.evaluate(&mut eval_ctx).unwrap_err();
+        assert!(
+            matches!(error.root_cause(), EvalError::PatternMatchFailed(id, _) if id == "one"),
+            "{code}: {error:?}"
+        );
+        // each error in the chain is reported once
+        let report = error.report();
+        let root_cause = error.root_cause().to_string();
+        assert_eq!(report.matches(&root_cause).count(), 1, "{report}");
+        assert!(
+            report.contains(&format!("\nCaused by: {root_cause}")),
+            "{report}"
+        );
+
+        let error = match error.without_call_trace() {
+            EvalError::Spanned(_, e) => *e,
+            e => e,
+        };
+        let native_fn_error = error.source().expect("Expected a NativeFnError source");
+        assert!(
+            matches!(
+                native_fn_error.downcast_ref(),
+                Some(NativeFnError::ValueCallFailed(..))
+            ),
+            "{code}: {native_fn_error:?}"
+        );
+        let eval_error = native_fn_error
+            .source()
+            .and_then(|e| e.downcast_ref::<EvalError>())
+            .expect("Expected an EvalError source");
+        assert_eq!(eval_error.root_cause(), error.root_cause(), "{code}");
+    }
+
+    // errors of evaluated code are attributed to the native fn that evaluated it
+    for (code, fn_id) in [
+        ("Std.AST.eval ^(one 2)", "eval"),
+        ("Std.AST.evalFresh ^(one 2)", "evalFresh"),
+    ] {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        let error = match ast
+            .evaluate(&mut eval_ctx)
+            .unwrap_err()
+            .without_call_trace()
+        {
+            EvalError::Spanned(_, e) => *e,
+            e => e,
+        };
+        assert!(
+            matches!(&error, EvalError::NativeFnFailed(NativeFnError::EvaluationFailed(id, _)) if id == fn_id),
+            "{code}: {error:?}"
+        );
+    }
+
+    // boxed errors compare by the wrapped errors, not by their messages
+    let boxed = |e| NativeFnError::EvaluationFailed("f".into(), BoxedError::new(e));
+    let fn_not_defined = EvalError::FunctionNotDefined("one".into());
+    assert_eq!(boxed(fn_not_defined.clone()), boxed(fn_not_defined.clone()));
+    assert_ne!(
+        boxed(fn_not_defined),
+        boxed(EvalError::FunctionNotDefined("two".into()))
+    );
+    #[derive(thiserror::Error, Debug, PartialEq)]
+    #[error("Unknown error: one")]
+    struct OtherError;
+    let eval_error = BoxedError::new(EvalError::Unknown("one".into()));
+    assert_eq!(
+        eval_error.to_string(),
+        BoxedError::new(OtherError).to_string()
+    );
+    assert_ne!(eval_error, BoxedError::new(OtherError));
+}
+
+#[test]
+fn quasi_quotes() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "let x = 1 + 2
+         let names = [\"a\", \"b\"]
+         let inner = ^(y + 1)",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let code_with_quoted = [
+        ("^(foo bar)", "foo bar"),
+        ("^(foo ~x bar)", "foo 3 bar"),
+        ("^(foo ~(x * 10) ~names)", "foo 30 [\"a\", \"b\"]"),
+        ("^(~inner * 2)", "(y + 1) * 2"),
+        ("^(foo ~({x, ^bar}))", "foo {3, ^bar}"),
+        ("^(a ^(b ~x))", "a ^(b ~x)"),
+        ("^(a ^(b ~(~x)))", "a ^(b ~(3))"),
+    ];
+
+    for (code, quoted) in code_with_quoted.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        let expected = parse_expr(quoted, &parser_ctx).unwrap();
+        assert_eq!(
+            ast.evaluate(&mut eval_ctx),
+            Ok(val::quoted(expected)),
+            "{code}"
+        );
+    }
+
+    let ast = parse_expr("^(let f y = y + ~x)", &parser_ctx).unwrap();
+    let quoted = ast.evaluate(&mut eval_ctx).unwrap();
+    assert_eq!(quoted.to_string(), "^(let f y =\n    (y + 3))");
+
+    let ast = parse_expr("~x", &parser_ctx).unwrap();
+    let error = ast.evaluate(&mut eval_ctx).unwrap_err();
+    assert!(
+        matches!(error.root_cause(), EvalError::UnquoteOutsideQuote(_)),
+        "{error:?}"
+    );
+    assert_eq!(
+        error.root_cause().to_string(),
+        "Unquote used outside of a quoted expression: ~(x)"
+    );
+
+    let ast = parse_expr("^(foo ~(Std.Set.from [1]))", &parser_ctx).unwrap();
+    let error = ast.evaluate(&mut eval_ctx).unwrap_err();
+    assert!(
+        matches!(error.root_cause(), EvalError::UnquoteNotSpliceable(_)),
+        "{error:?}"
+    );
+}
+
+#[test]
+fn macro_expansion() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "macro unless cond body = ^(if ~cond then ^none else ~body)
+         macro double expr = ^(let tmp = ~expr in tmp + tmp)
+         macro swap pair = ^(case ~pair of
+           {a, b} -> {b, a})
+         macro twice f = ^(let g = ~f in (x -> g (g x)))
+
+         let tmp = 10",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let code_with_vals = [
+        ("unless false 1", val::number(1)),
+        ("unless true 1", val::symbol("none")),
+        (
+            "swap {1, \"two\"}",
+            val::tuple([val::string("two"), val::number(1)]),
+        ),
+        ("double 21", val::number(42)),
+        ("double (double 3)", val::number(12)),
+        (
+            "unless (double 2 > 5) (swap {1, 2})",
+            val::tuple([val::number(2), val::number(1)]),
+        ),
+        ("let h = (twice (y -> y * 3)) in h 2", val::number(18)),
+        ("Std.eval ^(double 4)", val::number(8)),
+        // expanded code can't capture or shadow variables at the call site
+        ("double tmp", val::number(20)),
+        ("let tmp = 1 in double (tmp + 1)", val::number(4)),
+        (
+            "let a = 1, b = 2 in swap {b, a}",
+            val::tuple([val::number(1), val::number(2)]),
+        ),
+        (
+            "let x = 5, g = 2, h = (twice (y -> y + x * g)) in h 1",
+            val::number(21),
+        ),
+    ];
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(
+            eval_ctx.evaluate_expression(&ast),
+            Ok(val.clone()),
+            "{code}"
+        );
+    }
+
+    let ast = parse_expr("unless x (double y)", &parser_ctx).unwrap();
+    let expanded = MacroExpander::new(&eval_ctx).expand_expr(&ast).unwrap();
+    assert_eq!(
+        expanded.to_string(),
+        "if x then\n    ^none\nelse\n    let\n        tmp#2 = y\n    in\n        (tmp#2 + tmp#2)"
+    );
+
+    // macro calls within quoted code only get expanded once the code is evaluated
+    let ast = parse_expr("^(unless x ~(double 2))", &parser_ctx).unwrap();
+    let expanded = MacroExpander::new(&eval_ctx).expand_expr(&ast).unwrap();
+    let expected = parse_expr("^(unless x ~(let tmp = 2 in tmp + tmp))", &parser_ctx).unwrap();
+    assert_eq!(
+        expanded.to_string(),
+        expected.to_string().replace("tmp", "tmp#1")
+    );
+}
+
+#[test]
+fn macro_definitions_in_programs() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "let before = 1
+
+         macro inc x = ^(~x + 1)
+
+         let after = inc 1",
+        &parser_ctx,
+    )
+    .unwrap();
+    let expanded = MacroExpander::new(&EvalContext::new())
+        .expand_program(&program)
+        .unwrap();
+    assert_eq!(
+        expanded.to_string(),
+        parse(
+            "let before = 1
+
+             macro inc x = ^(~x + 1)
+
+             let after = 1 + 1",
+            &parser_ctx
+        )
+        .unwrap()
+        .to_string()
+    );
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let ast = parse_expr("after", &parser_ctx).unwrap();
+    assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val::number(2)));
+
+    // redefining a macro replaces it
+    let program = parse("macro inc x = ^(~x + 10)\nlet again = inc 1", &parser_ctx).unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+    let ast = parse_expr("{after, again, inc 2}", &parser_ctx).unwrap();
+    assert_eq!(
+        eval_ctx.evaluate_expression(&ast),
+        Ok(val::tuple([
+            val::number(2),
+            val::number(11),
+            val::number(12)
+        ]))
+    );
+}
+
+#[test]
+fn isolated_macro_expansion() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse("let f 1 = 1", &parser_ctx).unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let definitions = "let f x = 2
+         let g x = x
+         macro twice x = ^(~x + ~x)";
+    let program = parse(&format!("{definitions}\n let h = twice (g 1)"), &parser_ctx).unwrap();
+    let expanded = MacroExpander::isolated(&eval_ctx)
+        .expand_program(&program)
+        .unwrap();
+    assert_eq!(
+        expanded.to_string(),
+        parse(&format!("{definitions}\n let h = g 1 + g 1"), &parser_ctx)
+            .unwrap()
+            .to_string()
+    );
+
+    // none of the expanded definitions are left behind, including the new variant of `f`
+    assert!(eval_ctx.lookup_macro(&"twice".into()).is_none());
+    for code in ["g", "h", "f 2"] {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert!(eval_ctx.evaluate_expression(&ast).is_err(), "{code}");
+    }
+    let ast = parse_expr("f 1", &parser_ctx).unwrap();
+    assert_eq!(eval_ctx.evaluate_expression(&ast), Ok(val::number(1)));
+}
+
+#[test]
+fn macros_calling_functions() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    // definitions get evaluated in order, so macros can call the functions defined before
+    // their first use in the same file, but not those defined after it
+    let program = parse(
+        "let negated expr = ^(0 - ~expr)
+         macro negate expr = negated expr
+         let minusOne = negate 1
+
+         macro later expr = laterHelper expr
+         let tooEarly = later 1
+         let laterHelper expr = expr",
+        &parser_ctx,
+    )
+    .unwrap();
+    let error = eval_ctx.evaluate_program(&program).unwrap_err();
+    assert!(
+        matches!(error.root_cause(), EvalError::FunctionNotDefined(id) if id == "laterHelper"),
+        "{error:?}"
+    );
+
+    let ast = parse_expr("minusOne", &parser_ctx).unwrap();
+    assert_eq!(eval_ctx.evaluate_expression(&ast), Ok(val::number(-1)));
+}
+
+#[test]
+fn macro_expansion_errors() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "macro unless cond body = ^(if ~cond then ^none else ~body)
+         macro forever expr = ^(forever ~expr)
+         macro broken expr = Std.Set.from [1]",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let ast = parse_expr("unless false 1", &parser_ctx).unwrap();
+    let error = ast.evaluate(&mut eval_ctx).unwrap_err();
+    assert!(
+        matches!(error.root_cause(), EvalError::MacroNotExpanded(id) if id == "unless"),
+        "{error:?}"
+    );
+
+    let ast = parse_expr("forever 1", &parser_ctx).unwrap();
+    let error = MacroExpander::new(&eval_ctx).expand_expr(&ast).unwrap_err();
+    assert!(
+        matches!(error.root_cause(), EvalError::MacroExpansionTooDeep(id, _) if id == "forever"),
+        "{error:?}"
+    );
+
+    let ast = parse_expr("broken 1", &parser_ctx).unwrap();
+    let error = MacroExpander::new(&eval_ctx).expand_expr(&ast).unwrap_err();
+    assert!(
+        matches!(&error, EvalError::Spanned(_, e) if matches!(&**e, EvalError::MacroExpansionFailed(id, _) if id == "broken")),
+        "{error:?}"
+    );
+    assert!(
+        matches!(error.root_cause(), EvalError::UnquoteNotSpliceable(_)),
+        "{error:?}"
+    );
+}
+
+#[test]
+fn tail_calls() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "let fold [] acc f = acc
+         let fold [x :: xs] acc f = fold xs (f acc x) f
+
+         let sumTo n acc =
+           if (n == 0) then
+             acc
+           else
+             (sumTo (n - 1) (acc + n))
+
+         let countDown n =
+           case n of
+             0 -> 0
+             n -> letDown n
+
+         let letDown n =
+           let
+             m = n - 1
+           in
+             countDown m
+
+         let isEven 0 = true
+         let isEven n = isOdd (n - 1)
+         let isOdd 0 = false
+         let isOdd n = isEven (n - 1)
+
+         let viaLambda n =
+           let
+             step = (m -> viaLambda (m - 1))
+           in
+             if (n == 0) then
+               ^done
+             else
+               (step n)
+
+         let applyTimes 0 f x = x
+         let applyTimes n f x = f (applyTimes (n - 1) f x)",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let code_with_vals = [
+        (
+            "fold (Std.range 100000) 0 (acc x -> acc + x)",
+            val::number(4_999_950_000i64),
+        ),
+        ("sumTo 50000 0", val::number(1_250_025_000i64)),
+        ("countDown 50000", val::number(0)),
+        ("isEven 50001", val::bool(false)),
+        ("viaLambda 50000", val::symbol("done")),
+        (
+            "Std.List.all (Std.range 50000) (x -> x >= 0)",
+            val::bool(true),
+        ),
+        (
+            "Std.List.countBy (Std.range 50000) (x -> 1)",
+            val::number(50000),
+        ),
+        (
+            "Std.List.length (Std.List.map (Std.range 50000) (x -> x))",
+            val::number(50000),
+        ),
+        // calls that aren't in tail position still work as before
+        ("applyTimes 40 (x -> x + 2) 0", val::number(80)),
+    ];
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()), "{code}");
+    }
+}
+
+#[test]
+fn tail_calls_fold_a_million_items() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "let fold [] acc f = acc
+         let fold [x :: xs] acc f = fold xs (f acc x) f",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let ast = parse_expr("fold (Std.range 1000000) 0 (acc x -> acc + x)", &parser_ctx).unwrap();
+    assert_eq!(
+        ast.evaluate(&mut eval_ctx),
+        Ok(val::number(499_999_500_000i64))
+    );
+}
+
+#[test]
+fn tail_call_traces() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "let fail x = half (unknown x)
+         let half x = x / 2
+         let failVia 0 = fail 0
+         let failVia n = failVia (n - 1)",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    // tail calls reuse a single frame on top of the initial call's frame
+    let ast = parse_expr("failVia 3", &parser_ctx).unwrap();
+    let error = ast.evaluate(&mut eval_ctx).unwrap_err();
+    let trace = error.call_trace().expect("Expected a call trace");
+    let frames: Vec<String> = trace.frames().map(|frame| frame.to_string()).collect();
+    assert_eq!(frames, ["fn fail 0", "fn failVia 3"]);
+}
+
+#[test]
+fn partial_application() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "let add a b = a + b
+         let add3 a b c = a + b + c
+         let adder x = (y -> x + y)
+         let mul = (a b -> a * b)
+         let inc = (add 1)
+         let triple = (mul 3)
+         let area w h = w * h
+         let area s = s * s
+         let scale x y z = x * y * z
+         let scale x y = x * y",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let code_with_vals = [
+        ("inc 41", val::number(42)),
+        ("triple 4", val::number(12)),
+        (
+            "Std.List.map [1, 2, 3] (add 10)",
+            val::list([11, 12, 13].map(val::number)),
+        ),
+        (
+            "[1, 2, 3] |> Std.List.map (add 1)",
+            val::list([2, 3, 4].map(val::number)),
+        ),
+        (
+            "Std.List.map [1, 2] triple",
+            val::list([3, 6].map(val::number)),
+        ),
+        ("Std.apply (add3 1 2) [3]", val::number(6)),
+        ("Std.match 2 (add 3)", val::number(5)),
+        (
+            "Std.List.filter [1, 2, 3] (Std.List.contains [2, 3])",
+            val::list([2, 3].map(val::number)),
+        ),
+        ("let add1 = (add3 1) in add1 2 3", val::number(6)),
+        (
+            "let add1 = (add3 1), add12 = (add1 2) in add12 3",
+            val::number(6),
+        ),
+        // over-applied calls apply the result to the remaining arguments
+        ("adder 1 2", val::number(3)),
+        ("let f = (x -> (y -> x * y)) in f 6 7", val::number(42)),
+        // calls only match variants taking as many arguments as were given
+        ("area 3", val::number(9)),
+        ("area 2 3", val::number(6)),
+        ("scale 2 3", val::number(6)),
+        ("scale 2 3 4", val::number(24)),
+        (
+            "Std.List.map [1, 2] (scale 2)",
+            val::list([2, 4].map(val::number)),
+        ),
+        ("let s = (scale 2) in s 3 4", val::number(24)),
+        ("Std.range 3", val::list([0, 1, 2].map(val::number))),
+    ];
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()), "{code}");
+    }
+
+    let ast = parse_expr("add 1", &parser_ctx).unwrap();
+    assert_eq!(ast.evaluate(&mut eval_ctx).unwrap().to_string(), "(add 1)");
+
+    let ast = parse_expr("add 1 2 3", &parser_ctx).unwrap();
+    let error = ast.evaluate(&mut eval_ctx).unwrap_err();
+    assert_eq!(
+        error.root_cause(),
+        &EvalError::ValueNotCallable(val::number(3))
+    );
+}
+
+#[test]
+fn fn_refs() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse(
+        "module Secret { doubler, same }
+
+         let doubler = &double
+         let double x = x * 2
+         let same x = x
+
+         module Other { }
+
+         let add a b = a + b
+         let inc x = x + 1
+         let answer = 42
+         let same x = x",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let code_with_vals = [
+        ("let f = &inc in f 1", val::number(2)),
+        ("let f = (inc) in f 1", val::number(2)),
+        ("answer", val::number(42)),
+        ("&inc == inc", val::bool(true)),
+        ("&inc == &add", val::bool(false)),
+        // functions with the same definition in different modules are different functions
+        ("&same == &Secret.same", val::bool(false)),
+        (
+            "Std.List.map [1, 2] &inc",
+            val::list([2, 3].map(val::number)),
+        ),
+        (
+            "Std.List.map [1, 2] inc",
+            val::list([2, 3].map(val::number)),
+        ),
+        (
+            "let map = &Std.List.map in map [1, 2] (add 10)",
+            val::list([11, 12].map(val::number)),
+        ),
+        ("Std.List.reduce [1, 2, 3] 0 &add", val::number(6)),
+        ("Std.apply &add [1, 2]", val::number(3)),
+        ("Std.match 1 &inc", val::number(2)),
+        ("let f = &add in Std.match 1 (f 2)", val::number(3)),
+        // references are resolved where they're created, so they can escape their module
+        ("let f = (Secret.doubler) in f 21", val::number(42)),
+    ];
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()), "{code}");
+    }
+
+    let ast = parse_expr("&Std.List.map", &parser_ctx).unwrap();
+    assert_eq!(ast.evaluate(&mut eval_ctx).unwrap().to_string(), "&map");
+
+    // calling a function with no arguments doesn't turn it into a reference
+    let ast = parse_expr("Std.Map.getOrElse {} 1 inc", &parser_ctx).unwrap();
+    assert_eq!(
+        ast.evaluate(&mut eval_ctx).unwrap_err().root_cause(),
+        &EvalError::FunctionArityMismatch("inc".into(), 1, 0)
+    );
+
+    let ast = parse_expr("&unknown", &parser_ctx).unwrap();
+    assert_eq!(
+        ast.evaluate(&mut eval_ctx).unwrap_err().root_cause(),
+        &EvalError::FunctionNotDefined("unknown".into())
+    );
+}
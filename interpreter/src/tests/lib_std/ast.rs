@@ -0,0 +1,173 @@
+use crate::{lib_std::ast, EvalContext, EvalError, Evaluate, NativeFnError};
+use rogato_common::val::{self};
+use rogato_parser::{parse, parse_expr, ParserContext};
+
+#[test]
+fn std_ast() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    let program = parse("let x = 5", &parser_ctx).unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let code_with_vals = [
+        ("Std.eval ^(x * 2)", val::number(10)),
+        ("eval ^(1 + 2)", val::number(3)),
+        ("AST.eval ^(1 + 2)", val::number(3)),
+        ("AST.evalFresh ^(1 + 2)", val::number(3)),
+        (
+            "AST.toData ^(x * 2)",
+            val::object([
+                ("kind", val::symbol("OpCall")),
+                ("op", val::string("*")),
+                (
+                    "left",
+                    val::object([("kind", val::symbol("Var")), ("id", val::string("x"))]),
+                ),
+                (
+                    "right",
+                    val::object([("kind", val::symbol("Literal")), ("value", val::number(2))]),
+                ),
+            ]),
+        ),
+        (
+            "AST.toData ^(let double a = a * 2)",
+            val::object([
+                ("kind", val::symbol("FnDef")),
+                ("id", val::string("double")),
+                (
+                    "variants",
+                    val::list([val::object([
+                        ("kind", val::symbol("Variant")),
+                        (
+                            "args",
+                            val::list([val::object([
+                                ("kind", val::symbol("Var")),
+                                ("id", val::string("a")),
+                            ])]),
+                        ),
+                        ("guard", val::none()),
+                        (
+                            "body",
+                            val::object([
+                                ("kind", val::symbol("OpCall")),
+                                ("op", val::string("*")),
+                                (
+                                    "left",
+                                    val::object([
+                                        ("kind", val::symbol("Var")),
+                                        ("id", val::string("a")),
+                                    ]),
+                                ),
+                                (
+                                    "right",
+                                    val::object([
+                                        ("kind", val::symbol("Literal")),
+                                        ("value", val::number(2)),
+                                    ]),
+                                ),
+                            ]),
+                        ),
+                    ])]),
+                ),
+            ]),
+        ),
+        (
+            "let data = (AST.toData ^(case x of [a :: _] -> a)) in data.arms",
+            val::list([val::tuple([
+                val::object([
+                    ("kind", val::symbol("ListCons")),
+                    (
+                        "head",
+                        val::object([("kind", val::symbol("Var")), ("id", val::string("a"))]),
+                    ),
+                    ("tail", val::object([("kind", val::symbol("Any"))])),
+                ]),
+                val::object([("kind", val::symbol("Var")), ("id", val::string("a"))]),
+            ])]),
+        ),
+        ("AST.show ^(x * 2)", val::string("(x * 2)")),
+        (
+            "AST.show {\"kind\": ^FnCall, \"id\": \"foo\", \"args\": [1, \"two\", ^(bar x)]}",
+            val::string("(foo 1 \"two\" (bar x))"),
+        ),
+        (
+            "eval (AST.fromData {^kind: ^OpCall, ^op: \"+\", ^left: 1, ^right: ^(x * 2)})",
+            val::number(11),
+        ),
+    ];
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()), "{code}");
+    }
+
+    let round_trips = [
+        "foo [1, x] {^a: 2} \"s\" {1, 2}",
+        "if (x > 1) then [x :: rest] else \"x is ${x}\"",
+        "a.b.c",
+        "Point{x: 1, y: x}",
+        "case x of\n  1 -> ^one\n  _ -> ^other",
+        "(a -> a + x)",
+        "^(foo ~x)",
+        "(a when a > 1 -> a, _ -> 0)",
+        "case x of\n  [a :: rest] -> a\n  Point{x: 1, y: y} -> y\n  1 | 2 -> ^small\n  all@[_ :: _] -> all\n  (Some a) -> a\n  None -> 0",
+        "? p <- Person\n? q <!- friends p\n! (p.age > 18)\n!> {p, q}",
+    ];
+
+    for code in round_trips.iter() {
+        let ast = parse_expr(&format!("AST.fromData (AST.toData ^({code}))"), &parser_ctx).unwrap();
+        let expected = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(
+            ast.evaluate(&mut eval_ctx),
+            Ok(val::quoted(expected)),
+            "{code}"
+        );
+    }
+
+    let ast_round_trips = [
+        "let double a = a * 2",
+        "let fact n when n > 0 = n * (fact (n - 1))",
+        "type Shape = Circle Int | Rect Int Int | Empty",
+        "type Person :: {\n  name :: String, tags :: [String]\n}",
+        "module Geo {area, Shape}",
+        "use Std.List as L { map as lmap, Type Shape as S }",
+        "infixl 6 <+>",
+        "macro unless cond body = ^(if ~cond then ^none else ~body)",
+    ];
+
+    for code in ast_round_trips.iter() {
+        let program = parse(code, &parser_ctx).unwrap();
+        let def = program.iter().next().unwrap();
+        assert_eq!(
+            ast::from_data(&ast::ast_data(def)),
+            Ok(val::quoted_ast(def.clone())),
+            "{code}"
+        );
+    }
+
+    let ast = parse_expr("AST.eval ^(let double a = a * 2)", &parser_ctx).unwrap();
+    ast.evaluate(&mut eval_ctx).unwrap();
+    let ast = parse_expr("double x", &parser_ctx).unwrap();
+    assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val::number(10)));
+
+    let ast = parse_expr("AST.evalFresh ^(double x)", &parser_ctx).unwrap();
+    let error = ast.evaluate(&mut eval_ctx).unwrap_err();
+    assert!(
+        matches!(
+            error.root_cause(),
+            EvalError::FunctionNotDefined(_) | EvalError::VarNotDefined(_)
+        ),
+        "{error:?}"
+    );
+
+    let ast = parse_expr("AST.fromData {^kind: ^Unknown}", &parser_ctx).unwrap();
+    let error = ast.evaluate(&mut eval_ctx).unwrap_err();
+    assert!(
+        matches!(
+            error.root_cause(),
+            EvalError::NativeFnFailed(NativeFnError::Unknown(..))
+        ),
+        "{error:?}"
+    );
+}
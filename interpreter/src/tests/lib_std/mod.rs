@@ -1,3 +1,6 @@
+#[cfg(test)]
+pub mod ast;
+
 #[cfg(test)]
 pub mod list;
 
@@ -7,6 +10,9 @@ pub mod map;
 #[cfg(test)]
 pub mod math;
 
+#[cfg(test)]
+pub mod result;
+
 #[cfg(test)]
 pub mod string;
 
@@ -76,3 +82,28 @@ fn std() {
         assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()));
     }
 }
+
+#[test]
+fn embedded_std_lib() {
+    let code_with_vals = [
+        ("Std.not true", val::bool(false)),
+        (
+            "Std.List.reverseAppend [1, 2] [3]",
+            val::list([val::number(2), val::number(1), val::number(3)]),
+        ),
+        (
+            "List.sortBy [3, 1, 2] (a b -> a < b)",
+            val::list([val::number(1), val::number(2), val::number(3)]),
+        ),
+        ("Std.Map.isEmpty {}", val::bool(true)),
+        ("Std.Result.isErr (Std.Result.Ok 1)", val::bool(false)),
+    ];
+
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()), "{code}");
+    }
+}
@@ -0,0 +1,56 @@
+use crate::{tests::use_std, EvalContext, Evaluate};
+use rogato_common::val::{self};
+use rogato_parser::{parse_expr, ParserContext};
+
+#[test]
+fn std_result() {
+    let code_with_vals = [
+        (
+            "Ok 1",
+            val::variant("Std.Result.Result", "Ok", [val::number(1)]),
+        ),
+        (
+            "Err ^failed",
+            val::variant("Std.Result.Result", "Err", [val::symbol("failed")]),
+        ),
+        ("isOk (Ok 1)", val::bool(true)),
+        ("isOk (Err 1)", val::bool(false)),
+        ("isErr (Ok 1)", val::bool(false)),
+        ("isErr (Err 1)", val::bool(true)),
+        (
+            "map (Ok 1) (x -> x + 1)",
+            val::variant("Std.Result.Result", "Ok", [val::number(2)]),
+        ),
+        (
+            "map (Err 1) (x -> x + 1)",
+            val::variant("Std.Result.Result", "Err", [val::number(1)]),
+        ),
+        (
+            "mapErr (Err 1) (x -> x * 10)",
+            val::variant("Std.Result.Result", "Err", [val::number(10)]),
+        ),
+        (
+            "mapErr (Ok 1) (x -> x * 10)",
+            val::variant("Std.Result.Result", "Ok", [val::number(1)]),
+        ),
+        (
+            "Std.Result.map (Ok 1) (x -> Ok x)",
+            val::variant(
+                "Std.Result.Result",
+                "Ok",
+                [val::variant("Std.Result.Result", "Ok", [val::number(1)])],
+            ),
+        ),
+    ];
+
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+
+    use_std("Std", &mut eval_ctx);
+    use_std("Std.Result.Result", &mut eval_ctx);
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()));
+    }
+}
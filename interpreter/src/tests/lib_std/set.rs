@@ -1,4 +1,4 @@
-use crate::{tests::parse_eval_std, EvalContext, Evaluate};
+use crate::{tests::use_std, EvalContext, Evaluate};
 use rogato_common::val::{self};
 use rogato_parser::{parse_expr, ParserContext};
 
@@ -191,7 +191,7 @@ fn std_set_module() {
     let mut eval_ctx = EvalContext::new();
     let parser_ctx = ParserContext::new();
 
-    parse_eval_std("List", &parser_ctx, &mut eval_ctx);
+    use_std("List", &mut eval_ctx);
 
     for (code, val) in code_with_vals.iter() {
         let ast = parse_expr(code, &parser_ctx).unwrap();
@@ -1,6 +1,5 @@
-use crate::{EvalContext, Evaluate};
-use rogato_parser::{parse, ParserContext};
-use std::{env, fs::File, io::Read, path::Path};
+use crate::{environment::Imports, EvalContext};
+use rogato_common::ast::Identifier;
 
 #[cfg(test)]
 pub mod fn_def;
@@ -8,27 +7,19 @@ pub mod fn_def;
 pub mod interpreter;
 #[cfg(test)]
 pub mod lib_std;
+#[cfg(test)]
+pub mod module_loader;
 
-fn parse_eval_std(std_mod_name: &str, parser_ctx: &ParserContext, eval_ctx: &mut EvalContext) {
-    let curr_dir = env::current_dir().unwrap();
-    let root_path = curr_dir
-        .as_path()
-        .parent()
-        .expect("Parent directory expected to be root");
-    let file_name = if std_mod_name == "Std" {
-        "lib/Std.roga".into()
+/// Brings the given std lib module into scope, as if the test code was written inside of it.
+fn use_std(std_mod_name: &str, eval_ctx: &mut EvalContext) {
+    let module_id: Identifier = if std_mod_name == "Std" {
+        "Std".into()
     } else {
-        format!("lib/Std/{std_mod_name}.roga")
+        format!("Std.{std_mod_name}").into()
     };
-    let file_path = root_path.join(Path::new(file_name.as_str()));
-    let mut file = File::open(&file_path)
-        .unwrap_or_else(|_| panic!("Std lib file should exist: {file_path:?}"));
-
-    let mut buf = String::new();
-    file.read_to_string(&mut buf).unwrap();
 
-    parse(buf.as_str(), parser_ctx)
-        .unwrap_or_else(|_| panic!("Expected file to parse: {file_name}"))
-        .evaluate(eval_ctx)
-        .unwrap_or_else(|_| panic!("Expected file to evaluate: {file_name}"));
+    eval_ctx
+        .import(&module_id, Imports::All)
+        .unwrap_or_else(|_| panic!("Std lib module should be loaded: {module_id}"));
+    eval_ctx.set_current_module(module_id);
 }
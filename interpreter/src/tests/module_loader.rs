@@ -0,0 +1,262 @@
+use crate::{module_loader::ModuleLoader, EvalContext, EvalError, Evaluate};
+use rogato_common::val;
+use rogato_parser::{parse, parse_expr, ParserContext};
+use std::{env, fs, path::PathBuf};
+
+fn std_lib_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("lib")
+}
+
+/// Writes the given module sources into a fresh temporary directory, returning its path.
+fn module_dir(name: &str, modules: &[(&str, &str)]) -> PathBuf {
+    let dir = env::temp_dir().join(format!("rogato-{name}-{}", uuid::Uuid::new_v4()));
+    for (module_id, code) in modules.iter() {
+        let file_path = dir.join(ModuleLoader::module_file_path(&(*module_id).into()));
+        fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+        fs::write(file_path, code).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn module_file_paths() {
+    assert_eq!(
+        ModuleLoader::module_file_path(&"Std".into()),
+        PathBuf::from("Std.roga")
+    );
+    assert_eq!(
+        ModuleLoader::module_file_path(&"Std.List".into()),
+        PathBuf::from("Std").join("List.roga")
+    );
+    assert_eq!(
+        ModuleLoader::module_file_path(&"Foo.Bar.Baz".into()),
+        PathBuf::from("Foo").join("Bar").join("Baz.roga")
+    );
+}
+
+#[test]
+fn use_loads_module_from_search_paths() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+    eval_ctx.add_search_path(std_lib_dir());
+
+    let program = parse("use Std.Result { isOk, isErr, Ok, Err }", &parser_ctx).unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let code_with_vals = [
+        ("isOk (Ok 1)", val::bool(true)),
+        ("isOk (Err 1)", val::bool(false)),
+        ("Std.Result.isOk (Ok 1)", val::bool(true)),
+    ];
+
+    for (code, val) in code_with_vals.iter() {
+        let ast = parse_expr(code, &parser_ctx).unwrap();
+        assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val.clone()), "{code}");
+    }
+}
+
+#[test]
+fn modules_are_loaded_once() {
+    let dir = module_dir(
+        "load-once",
+        &[
+            (
+                "Counter",
+                "module Counter { count }\n\nlet count x = x + 1\n",
+            ),
+            (
+                "Counter.Use",
+                "module Counter.Use { }\n\nuse Counter { count }\n",
+            ),
+        ],
+    );
+
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+    eval_ctx.add_search_path(&dir);
+
+    let program = parse(
+        "use Counter\nuse Counter.Use\nuse Counter { count }",
+        &parser_ctx,
+    )
+    .unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let module = eval_ctx.lookup_module(&"Counter".into()).unwrap();
+    let count = module.lookup_fn(&"count".into()).unwrap();
+    assert_eq!(count.borrow().variants_iter().count(), 1);
+
+    let ast = parse_expr("count 1", &parser_ctx).unwrap();
+    assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val::number(2)));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn failed_modules_are_not_defined() {
+    let dir = module_dir(
+        "failed",
+        &[(
+            "Broken",
+            "module Broken { one }\n\nlet one = 1\n\nuse Missing.Dep\n",
+        )],
+    );
+
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+    eval_ctx.add_search_path(&dir);
+
+    let program = parse("use Broken", &parser_ctx).unwrap();
+    assert!(eval_ctx.evaluate_program(&program).is_err());
+    assert!(eval_ctx.lookup_module(&"Broken".into()).is_none());
+
+    // once fixed, the module is loaded from scratch
+    let file_path = dir.join(ModuleLoader::module_file_path(&"Broken".into()));
+    fs::write(file_path, "module Broken { one }\n\nlet one 1 = 1\n").unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+    let module = eval_ctx.lookup_module(&"Broken".into()).unwrap();
+    let one = module.lookup_fn(&"one".into()).unwrap();
+    assert_eq!(one.borrow().variants_iter().count(), 1);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn modules_must_be_declared_by_their_file() {
+    let dir = module_dir(
+        "mismatch",
+        &[
+            (
+                "Foo.Bar",
+                "let bar = 1
+
+module Foo.Other { }
+",
+            ),
+            (
+                "Foo.Baz",
+                "module Foo.Other { }
+
+let baz = 2
+",
+            ),
+        ],
+    );
+
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+    eval_ctx.add_search_path(&dir);
+
+    for module_id in ["Foo.Bar", "Foo.Baz"] {
+        let program = parse(&format!("use {module_id}"), &parser_ctx).unwrap();
+        let error = eval_ctx.evaluate_program(&program).unwrap_err();
+        assert!(
+            matches!(error.root_cause(), EvalError::ModuleLoadFailed(id, _, _) if id == module_id),
+            "{error:?}"
+        );
+        assert!(eval_ctx.lookup_module(&module_id.into()).is_none());
+    }
+
+    // nothing from either file ends up in the importing module or anywhere else
+    assert!(eval_ctx.lookup_module(&"Foo.Other".into()).is_none());
+    for name in ["bar", "baz"] {
+        let ast = parse_expr(name, &parser_ctx).unwrap();
+        assert!(eval_ctx.evaluate_expression(&ast).is_err(), "{name}");
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn import_cycles_are_detected() {
+    let dir = module_dir(
+        "cycle",
+        &[
+            (
+                "Cycle.A",
+                "module Cycle.A { a }\n\nuse Cycle.B\n\nlet a = 1\n",
+            ),
+            (
+                "Cycle.B",
+                "module Cycle.B { b }\n\nuse Cycle.C\n\nlet b = 2\n",
+            ),
+            (
+                "Cycle.C",
+                "module Cycle.C { c }\n\nuse Cycle.A\n\nlet c = 3\n",
+            ),
+        ],
+    );
+
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+    eval_ctx.add_search_path(&dir);
+
+    let program = parse("use Cycle.A", &parser_ctx).unwrap();
+    let error = eval_ctx.evaluate_program(&program).unwrap_err();
+
+    let cycle = EvalError::ImportCycle(vec![
+        "Cycle.A".into(),
+        "Cycle.B".into(),
+        "Cycle.C".into(),
+        "Cycle.A".into(),
+    ]);
+    assert!(error.to_string().starts_with(&cycle.to_string()), "{error}");
+    assert_eq!(
+        cycle.to_string(),
+        "Import cycle detected: Cycle.A -> Cycle.B -> Cycle.C -> Cycle.A"
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn missing_modules_fail_to_import() {
+    let mut eval_ctx = EvalContext::new();
+    let parser_ctx = ParserContext::new();
+    eval_ctx.add_search_path(std_lib_dir());
+
+    let program = parse("use Std.DoesNotExist", &parser_ctx).unwrap();
+    let error = eval_ctx.evaluate_program(&program).unwrap_err();
+
+    assert!(
+        error
+            .to_string()
+            .starts_with("Import failed, module not found for: Std.DoesNotExist"),
+        "{error}"
+    );
+}
+
+#[test]
+fn fixities_are_imported_with_modules() {
+    let dir = module_dir(
+        "fixities",
+        &[
+            ("Ops", "module Ops { }\n\ninfixr 6 -\n"),
+            (
+                "Calc",
+                "module Calc { diff }\n\nuse Ops\n\nlet diff = 10 - 5 - 2\n",
+            ),
+        ],
+    );
+
+    let mut eval_ctx = EvalContext::new();
+    eval_ctx.add_search_path(&dir);
+
+    let code = "use Calc { diff }\nuse Ops\n\nlet rdiff = 10 - 5 - 2\n";
+    let parser_ctx = ParserContext::new();
+    eval_ctx.import_fixities(code, &parser_ctx).unwrap();
+    let program = parse(code, &parser_ctx).unwrap();
+    eval_ctx.evaluate_program(&program).unwrap();
+
+    let ast = parse_expr("{diff, rdiff}", &parser_ctx).unwrap();
+    assert_eq!(
+        ast.evaluate(&mut eval_ctx),
+        Ok(val::tuple([val::number(7), val::number(7)]))
+    );
+
+    // code that doesn't import the module isn't affected by its fixities
+    let ast = parse_expr("10 - 5 - 2", &ParserContext::new()).unwrap();
+    assert_eq!(ast.evaluate(&mut eval_ctx), Ok(val::number(3)));
+
+    fs::remove_dir_all(dir).unwrap();
+}
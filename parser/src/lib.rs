@@ -1,24 +1,94 @@
 pub mod parser;
-pub use parser::{parse, parse_ast, parse_expr};
+pub use parser::{
+    parse, parse_ast, parse_expr, parse_file, parse_fixity_defs, parse_use_defs,
+    parse_with_recovery,
+};
 
-use rogato_common::ast::NodeFactory;
+use peg::error::ExpectedSet;
+use rogato_common::ast::{
+    fixity::{Fixity, FixityDef},
+    Identifier, NodeFactory, Span,
+};
+use std::{cell::RefCell, collections::HashMap, fmt::Display, rc::Rc};
 
 pub type ParseError = peg::error::ParseError<peg::str::LineCol>;
 
+/// A single parse error collected while parsing in recovery mode.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseDiagnostic {
+    span: Span,
+    expected: ExpectedSet,
+}
+
+impl ParseDiagnostic {
+    pub fn new(span: Span, expected: ExpectedSet) -> ParseDiagnostic {
+        ParseDiagnostic { span, expected }
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    pub fn expected(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.expected.tokens()
+    }
+}
+
+impl Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "error at {}: expected {}",
+            self.span, self.expected
+        ))
+    }
+}
+
+impl std::error::Error for ParseDiagnostic {}
+
 #[cfg(test)]
 pub mod tests;
 
 pub struct ParserContext {
     node_factory: NodeFactory,
+    fixities: RefCell<HashMap<Identifier, Fixity>>,
 }
 
 impl ParserContext {
     pub fn new() -> ParserContext {
         ParserContext {
             node_factory: NodeFactory::new(),
+            fixities: RefCell::new(HashMap::new()),
         }
     }
 
+    /// Declares the fixity of an operator for all code parsed with this context from now on.
+    pub fn declare_fixity<ID: Into<Identifier>>(&self, op: ID, fixity: Fixity) {
+        self.fixities.borrow_mut().insert(op.into(), fixity);
+    }
+
+    /// Runs the given parse with the fixity definitions declared in addition to the
+    /// ones already declared, which are restored afterwards.
+    pub fn with_fixities<T, F: FnOnce() -> T>(&self, fixity_defs: Vec<FixityDef>, parse: F) -> T {
+        let declared = self.fixities.borrow().clone();
+        for fixity_def in fixity_defs {
+            self.declare_fixity(fixity_def.id().clone(), fixity_def.fixity());
+        }
+        let result = parse();
+        *self.fixities.borrow_mut() = declared;
+        result
+    }
+
+    /// Returns the declared fixity of an operator, falling back to the builtin
+    /// and then the default fixity.
+    pub fn fixity(&self, op: &str) -> Fixity {
+        self.fixities
+            .borrow()
+            .get(op)
+            .copied()
+            .or_else(|| Fixity::builtin(op))
+            .unwrap_or(Fixity::DEFAULT)
+    }
+
     pub fn node_factory(&mut self) -> &NodeFactory {
         &self.node_factory
     }
@@ -33,3 +103,49 @@ impl Default for ParserContext {
         ParserContext::new()
     }
 }
+
+/// Maps byte offsets within the parsed source code to line and column based [`Span`]s.
+pub struct SourceMap<'a> {
+    code: &'a str,
+    source: Option<Rc<str>>,
+    line_starts: Rc<[usize]>,
+    offset: usize,
+}
+
+impl<'a> SourceMap<'a> {
+    pub fn new(code: &'a str, source: Option<&str>) -> SourceMap<'a> {
+        let line_starts = std::iter::once(0)
+            .chain(code.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        SourceMap {
+            code,
+            source: source.map(Rc::from),
+            line_starts,
+            offset: 0,
+        }
+    }
+
+    /// Returns a source map for parsing the part of the code starting at `offset`.
+    pub fn at_offset(&self, offset: usize) -> SourceMap<'a> {
+        SourceMap {
+            code: self.code,
+            source: self.source.clone(),
+            line_starts: Rc::clone(&self.line_starts),
+            offset,
+        }
+    }
+
+    pub fn span(&self, start: usize, end: usize) -> Span {
+        let (start, end) = (self.offset + start, self.offset + end);
+        // surrounding whitespace consumed by a rule is not part of the node itself
+        let code = &self.code[start..end];
+        let trimmed = code.trim_start();
+        let start = start + (code.len() - trimmed.len());
+        let end = start + trimmed.trim_end().len();
+        let line = self
+            .line_starts
+            .partition_point(|&line_start| line_start <= start);
+        let column = self.code[self.line_starts[line - 1]..start].chars().count() + 1;
+        Span::new(self.source.clone(), start, end, line, column)
+    }
+}
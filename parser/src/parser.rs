@@ -1,29 +1,32 @@
 extern crate peg;
 
-use super::ParserContext;
+use super::{ParseDiagnostic, ParserContext, SourceMap};
 use peg::{error::ParseError, parser, str::LineCol};
 use rogato_common::ast::{
     expression::{
-        Expression, FnCall, FnCallArgs, FnDefArgs, Lambda, LambdaArgs, LambdaVariant, LetBindings,
-        LetExpression, Literal, Query, QueryBinding, QueryBindings, QueryGuards, StructProps,
-        TupleItems,
+        Case, CaseArm, Expression, FnCall, FnCallArgs, FnDefArgs, InterpolatedString, Lambda,
+        LambdaArgs, LambdaVariant, LetBindings, LetExpression, Literal, Query, QueryBinding,
+        QueryBindings, QueryGuards, StringPart, StructProps, TupleItems,
     },
-    fn_def::{FnDef, FnDefBody},
+    fixity::{Associativity, Fixity, FixityDef},
+    fn_def::{FnDef, FnDefBody, FnDefVariant, FnDefVariants},
     if_else::IfElse,
     literal::MapKVPair,
+    macro_def::MacroDef,
     module_def::{ModuleDef, ModuleExports},
     pattern::Pattern,
-    type_expression::{StructTypeProperties, TypeDef, TypeExpression},
-    Identifier, Program, VarIdentifier, AST,
+    type_expression::{StructTypeProperties, TypeDef, TypeExpression, UnionTypeVariants},
+    use_def::{UseDef, UseImport, UseImports},
+    Identifier, Node, Program, VarIdentifier, AST,
 };
 use rust_decimal::prelude::*;
 use rust_decimal::Decimal;
 use smol_str::SmolStr;
-use std::rc::Rc;
+use std::{iter::Peekable, rc::Rc, vec::IntoIter};
 
 parser! {
 /// Doc comment
-grammar parser(context: &ParserContext) for str {
+grammar parser(context: &ParserContext, source: &SourceMap) for str {
     rule traced<T>(e: rule<T>) -> T =
     &(input:$([_]*) {
         #[cfg(feature = "trace")]
@@ -38,6 +41,11 @@ grammar parser(context: &ParserContext) for str {
     pub rule traced_program() -> Program
         = traced(<program()>)
 
+    rule spanned(e: rule<Node<Expression>>) -> Node<Expression>
+        = start:position!() e:e() end:position!() {
+            e.with_span(Some(&source.span(start, end)))
+        }
+
     pub rule program() -> Program
         = _ defs:(program_root_def())* _ {
             Program::new(defs)
@@ -46,20 +54,64 @@ grammar parser(context: &ParserContext) for str {
             Program::new(vec![])
         }
 
-    pub rule program_root_def() -> Rc<AST>
-        = _ def:root_def() {
-            Rc::new(def)
+    // A program in which every root definition that fails to parse is skipped up to the next
+    // line starting a root definition, and returned as the byte range it spans instead.
+    pub rule recovered_program() -> Vec<Result<Node<AST>, (usize, usize)>>
+        = defs:(recovered_root_def())* _ {
+            defs
+        }
+
+    rule recovered_root_def() -> Result<Node<AST>, (usize, usize)>
+        = def:program_root_def() &(_ (root_def_keyword() / ![_])) {
+            Ok(def)
+        }
+        / _ start:position!() skipped_code() (!root_def_start() skipped_code())* _ end:position!() {
+            Err((start, end))
+        }
+
+    rule skipped_code()
+        = string_parts() / comment() / [_]
+
+    rule root_def_start()
+        = "\n"+ root_def_keyword()
+
+    rule root_def_keyword()
+        = "let " / "macro " / "type " / "module " / "use " / "infix" / "//"
+
+    pub rule program_root_def() -> Node<AST>
+        = _ start:position!() def:root_def() end:position!() {
+            Node::new(def).with_span(Some(&source.span(start, end)))
         }
 
     pub rule root_def() -> AST
         = fn_def()
+        / macro_def()
         / type_def()
+        / fixity_def()
         / module_def()
-        / use_stmt()
+        / use_def()
         / c:comment() {
             AST::RootComment(c)
         }
 
+    // All fixity and `use` definitions at the start of a line, skipping any other code
+    // (incl. string literals and comments) in between.
+    pub rule line_start_defs() -> Vec<AST>
+        = first:line_start_def()? rest:(line_start_def_scan())* {
+            first.into_iter().chain(rest.into_iter().flatten()).collect()
+        }
+
+    rule line_start_def() -> AST
+        = [' ' | '\t']* def:(fixity_def() / use_def()) {
+            def
+        }
+
+    rule line_start_def_scan() -> Option<AST>
+        = "\n" def:line_start_def() { Some(def) }
+        / comment() { None }
+        / string_parts() { None }
+        / [_] { None }
+
     rule module_def() -> AST
         = "module " _ id:identifier() _ exports:module_exports() _ {
             AST::ModuleDef(ModuleDef::new(id, ModuleExports::new(exports)))
@@ -83,231 +135,332 @@ grammar parser(context: &ParserContext) for str {
             id
         }
 
-    rule use_stmt() -> AST
-        = "use" s() id:struct_identifier() s() "{" _ imports:(identifier() ** (_ "," _)) _ "}" {
-            AST::Use(id, imports)
+    rule use_def() -> AST
+        = "use" s() id:struct_identifier() alias:type_alias()? imports:(s() "{" _ i:use_imports() _ "}" { i })? {
+            let imports = imports.unwrap_or(UseImports::Specific(vec![]));
+            AST::Use(UseDef::new(id, alias, imports))
+        }
+
+    rule use_imports() -> UseImports
+        = "*" {
+            UseImports::All
         }
-        / "use" s() id:struct_identifier() {
-            AST::Use(id, vec![])
+        / imports:(use_import() ** (_ "," _)) {
+            UseImports::Specific(imports)
+        }
+
+    rule use_import() -> UseImport
+        = "Type" s() id:struct_identifier() alias:type_alias()? {
+            UseImport::Type(id, alias)
+        }
+        / id:identifier() alias:(s() "as" s() alias:identifier() { alias })? {
+            UseImport::Func(id, alias)
+        }
+
+    rule type_alias() -> Identifier
+        = s() "as" s() alias:struct_identifier() {
+            alias
         }
 
     rule fn_def() -> AST
-        = _ "let " _ id:identifier() _ args:(pattern() ** s()) _ "=" _ body:(expression()) _ {
-            AST::FnDef(FnDef::new(id, FnDefArgs::new(args), Rc::new(FnDefBody::rogato(Rc::new(body)))))
+        = _ "let " _ id:identifier() _ args:(fn_def_arg() ** s()) _ guard:fn_guard()? "=" _ body:(expression()) _ {
+            let variant = FnDefVariant(FnDefArgs::new(args), guard, Rc::new(FnDefBody::rogato(body)));
+            AST::FnDef(FnDef::new_with_variants(id, FnDefVariants::new([variant])))
+        }
+
+    rule macro_def() -> AST
+        = _ "macro " _ id:identifier() _ args:(fn_def_arg() ++ s()) _ "=" _ body:(expression()) _ {
+            AST::MacroDef(MacroDef::new(id, FnDefArgs::new(args), body))
+        }
+
+    rule fn_def_arg() -> Node<Pattern>
+        = !keyword() p:pattern() {
+            p
+        }
+
+    rule fn_guard() -> Node<Expression>
+        = "when" s() guard:op_expr() _ {
+            guard
         }
 
     rule list_sep()
         = (s()? "," _)
         / (_ "," s()?)
 
-    rule pattern() -> Rc<Pattern>
-        = "(" _ p:pattern() _ ")" {
+    rule pattern() -> Node<Pattern>
+        = start:position!() p:unspanned_pattern() end:position!() {
+            p.with_span(Some(&source.span(start, end)))
+        }
+
+    rule or_pattern() -> Node<Pattern>
+        = start:position!() first:pattern() rest:(_ "|" !['|' | '>'] _ p:pattern() { p })* end:position!() {?
+            if rest.is_empty() {
+                return Ok(first);
+            }
+            let var_ids = first.var_ids();
+            if rest.iter().any(|p| p.var_ids() != var_ids) {
+                return Err("or-pattern alternatives binding the same variables");
+            }
+            let mut alternatives = rest;
+            alternatives.insert(0, first);
+            Ok(Node::new(Pattern::Or(TupleItems::from(alternatives)))
+                .with_span(Some(&source.span(start, end))))
+        }
+
+    rule unspanned_pattern() -> Node<Pattern>
+        = "(" _ p:or_pattern() _ ")" {
             p
         }
         / n:number_lit() {
-            Rc::new(Pattern::Number(n))
+            Node::new(Pattern::Number(n))
         }
         / b:bool_lit() {
-            Rc::new(Pattern::Bool(b))
+            Node::new(Pattern::Bool(b))
         }
         / s:string_lit() {
-            Rc::new(Pattern::String(s))
+            Node::new(Pattern::String(s))
+        }
+        / "_" "{" _ props:(prop_pattern() ++ list_sep()) _ "}" {
+            Node::new(Pattern::Struct(None, props))
         }
         / "_" {
-            Rc::new(Pattern::Any)
+            Node::new(Pattern::Any)
         }
         / "[" _ "]" {
-            Rc::new(Pattern::EmptyList)
+            Node::new(Pattern::EmptyList)
         }
         / "[" _ head:pattern() _ "::" _ tail:pattern() _ "]" {
-            Rc::new(Pattern::ListCons(head,tail))
+            Node::new(Pattern::ListCons(head,tail))
         }
         / "[" _ items:(pattern() ** list_sep()) _ "]" {
-            Rc::new(Pattern::List(TupleItems::from(items)))
+            Node::new(Pattern::List(TupleItems::from(items)))
+        }
+        / id:struct_identifier() "{" _ props:(prop_pattern() ++ list_sep()) _ "}" {
+            Node::new(Pattern::Struct(Some(id), props))
         }
         / "{" _ tail:pattern() _ "::" _ items:(kv_pattern() ** list_sep()) _ "}" {
-            Rc::new(Pattern::MapCons(TupleItems::from(items), tail))
+            Node::new(Pattern::MapCons(TupleItems::from(items), tail))
         }
         / "{" _ items:(kv_pattern() ** list_sep()) _ "}" {
-            Rc::new(Pattern::Map(TupleItems::from(items)))
+            Node::new(Pattern::Map(TupleItems::from(items)))
         }
         / "{" _ items:(pattern() ** list_sep()) _ "}" {
-            Rc::new(Pattern::Tuple(items.len(), TupleItems::from(items)))
+            Node::new(Pattern::Tuple(items.len(), TupleItems::from(items)))
+        }
+        / id:$(['a'..='z'] ['a'..='z' | 'A'..='Z' | '-' | '_' | '0'..='9']*) "@" p:pattern() {
+            Node::new(Pattern::As(VarIdentifier::new(id), p))
         }
         / id:variable_identifier() {
             if id == "_" {
-                Rc::new(Pattern::Any)
+                Node::new(Pattern::Any)
             } else {
-                Rc::new(Pattern::Var(VarIdentifier::new(id)))
+                Node::new(Pattern::Var(VarIdentifier::new(id)))
             }
         }
         / "^" id:symbol_identifier() {
-            Rc::new(Pattern::Symbol(id))
+            Node::new(Pattern::Symbol(id))
+        }
+        / "(" _ id:struct_identifier() params:(s() p:pattern() { p })+ _ ")" {
+            Node::new(Pattern::Constructor(id, TupleItems::from(params)))
+        }
+        / id:struct_identifier() {
+            Node::new(Pattern::Constructor(id, TupleItems::from(vec![])))
+        }
+
+    rule prop_pattern() -> (Identifier, Node<Pattern>)
+        = id:identifier() _ ":" _ p:pattern() {
+            (id, p)
         }
 
-    rule kv_pattern() -> Rc<MapKVPair<Pattern>>
+    rule kv_pattern() -> Node<MapKVPair<Pattern>>
         = key:pattern() _ ":" _ val:pattern() {
-            Rc::new(MapKVPair::new(key, val))
+            Node::new(MapKVPair::new(key, val))
+        }
+
+    rule fixity_def() -> AST
+        = _ assoc:fixity_associativity() s() precedence:$(['0'..='9']) s() id:operator() {
+            AST::FixityDef(FixityDef::new(id, Fixity::new(assoc, precedence.parse().unwrap())))
         }
 
+    rule fixity_associativity() -> Associativity
+        = "infixl" { Associativity::Left }
+        / "infixr" { Associativity::Right }
+        / "infix" { Associativity::None }
+
     rule type_def() -> AST
         = _ "type " _ id:type_identifier() _ "::" _ t_expr:type_expr() {
-            AST::TypeDef(TypeDef::new(id, Rc::new(t_expr)))
+            AST::TypeDef(TypeDef::new(id, t_expr))
+        }
+        / _ "type " _ id:type_identifier() _ "=" _ t_expr:union_type() {
+            AST::TypeDef(TypeDef::new(id, t_expr))
+        }
+
+    rule union_type() -> Node<TypeExpression>
+        = start:position!() variants:(union_variant() ++ (_ "|" _)) end:position!() {
+            Node::new(TypeExpression::Union(UnionTypeVariants::new(variants)))
+                .with_span(Some(&source.span(start, end)))
+        }
+
+    rule union_variant() -> (Identifier, Vec<Node<TypeExpression>>)
+        = id:struct_identifier() params:(s() t:type_expr() { t })* {
+            (id, params)
         }
 
-    rule type_expr() -> TypeExpression
-        = "Bool" { TypeExpression::BoolType }
-        / "Int" { TypeExpression::NumberType }
-        / "String" { TypeExpression::StringType }
+    rule type_expr() -> Node<TypeExpression>
+        = start:position!() t:unspanned_type_expr() end:position!() {
+            t.with_span(Some(&source.span(start, end)))
+        }
+
+    rule unspanned_type_expr() -> Node<TypeExpression>
+        = "Bool" { Node::new(TypeExpression::BoolType) }
+        / "Int" { Node::new(TypeExpression::NumberType) }
+        / "String" { Node::new(TypeExpression::StringType) }
         / tuple_type()
         / list_type()
         / function_type()
         / struct_type()
         / id:identifier() {
-            TypeExpression::TypeRef(id)
+            Node::new(TypeExpression::TypeRef(id))
         }
 
-    rule tuple_type_item() -> TypeExpression
+    rule tuple_type_item() -> Node<TypeExpression>
         = type_expr()
 
-    rule additional_tuple_type_item() -> TypeExpression
+    rule additional_tuple_type_item() -> Node<TypeExpression>
         = " "* "," _ item:tuple_type_item() {
             item
         }
 
-    rule tuple_type() -> TypeExpression
+    rule tuple_type() -> Node<TypeExpression>
         = "{" _ first:tuple_type_item() rest:(additional_tuple_type_item())+ _ ("," _)? "}" {
-            TypeExpression::TupleType(TupleItems::new(first, rest))
+            let mut items = rest;
+            items.insert(0, first);
+            Node::new(TypeExpression::TupleType(TupleItems::from(items)))
         }
 
-    rule list_type() -> TypeExpression
+    rule list_type() -> Node<TypeExpression>
         = "[" _ type_expr:type_expr() _ "]" {
-            TypeExpression::ListType(Rc::new(type_expr))
+            Node::new(TypeExpression::ListType(type_expr))
         }
 
-    rule function_type() -> TypeExpression
+    rule function_type() -> Node<TypeExpression>
         = "(" _ arg_types:(tuple_type_item())+ " "+ "->" return_type:type_expr() _ ")"{
-            TypeExpression::FunctionType(LambdaArgs::new(arg_types), Rc::new(return_type))
+            let arg_types = arg_types.into_iter().map(Node::unwrap_or_clone).collect();
+            Node::new(TypeExpression::FunctionType(LambdaArgs::new(arg_types), return_type))
         }
 
-    rule struct_type() -> TypeExpression
+    rule struct_type() -> Node<TypeExpression>
         = "{" _ properties:(struct_prop_type())+ "}" {
-            TypeExpression::StructType(StructTypeProperties::new(properties))
+            Node::new(TypeExpression::StructType(StructTypeProperties::new(properties)))
         }
 
-    rule struct_prop_type() -> (Identifier, Rc<TypeExpression>)
+    rule struct_prop_type() -> (Identifier, Node<TypeExpression>)
         = id:identifier() " "+ "::" _ type_expr:type_expr() " "* "," _ {
-            (id, Rc::new(type_expr))
+            (id, type_expr)
         }
         / id:identifier() " "+ "::" _ type_expr:type_expr() [^'\n']* "\n"+ _ {
-            (id, Rc::new(type_expr))
+            (id, type_expr)
         }
 
-    pub rule expression() -> Expression
+    pub rule expression() -> Node<Expression>
+        = spanned(<unspanned_expression()>)
+
+    rule unspanned_expression() -> Node<Expression>
         = if_else()
-        / fn_pipe()
+        / case_expr()
         / let_expr()
         / query()
         / lambda()
-        / fn_call()
-        / op_call()
-        / atom()
+        / op_expr()
         / commented_expr()
 
-    rule fn_pipe() -> Expression
-        = a:fn_pipe_arg() calls:(fn_pipe_call())+ {
-            let call = calls.iter().fold(a, |acc, call|{
-                if let Expression::FnCall(fn_call) = call {
-                    let mut args = fn_call.args.clone();
-                    args.prepend_arg(Rc::new(acc));
-                    return Expression::FnCall(FnCall::new(fn_call.id.clone(), args))
-                }
-                panic!("Failed to create fn call pipeline")
-            });
-
-            call
-        }
-
-    rule fn_pipe_arg() -> Expression
-        = lambda()
-        / fn_call()
-        / atom()
-
-    rule fn_pipe_call() -> Expression
-        = _ "|>" _ fc:fn_call() {
-            fc
-        }
-        / _ "|>" _ id:identifier() {
-            Expression::FnCall(FnCall::new(id, FnCallArgs::empty()))
-        }
-
-    rule commented_expr() -> Expression
+    rule commented_expr() -> Node<Expression>
         = c:comment() _ e:expression() {
-            Expression::Commented(c, Rc::new(e))
+            Node::new(Expression::Commented(c, e))
         }
 
-    rule atom() -> Expression
+    rule atom() -> Node<Expression>
+        = spanned(<unspanned_atom()>)
+
+    rule unspanned_atom() -> Node<Expression>
         = if_else()
         / literal_expr()
         / edge_prop()
         / variable()
         / constant_or_type_ref()
+        / fn_ref()
         / quoted_expr()
+        / parenthesized_prop_access()
+        / "(" _ c:case_expr() _ ")" { c }
         / lambda()
-        / "(" _ c:(fn_pipe() / fn_call() / op_call()) _ ")" { c }
+        / "(" _ c:op_expr() _ ")" { c }
 
 
-    rule variable() -> Expression
+    rule variable() -> Node<Expression>
         = id:variable_identifier() {
-            Expression::Var(id.into())
+            prop_access(&id)
         }
         / "." id:variable_identifier() {
-            Expression::PropFnRef(id)
+            Node::new(Expression::PropFnRef(id))
+        }
+
+    rule parenthesized_prop_access() -> Node<Expression>
+        = "(" _ expr:(case_expr() / op_expr()) _ ")" "." props:variable_identifier() {?
+            if props.split('.').any(str::is_empty) {
+                Err("property name")
+            } else {
+                Ok(prop_accesses(expr, &props))
+            }
         }
 
-    rule quoted_expr() -> Expression
+    rule fn_ref() -> Node<Expression>
+        = "&" id:identifier() {
+            Node::new(Expression::FnRef(id))
+        }
+
+    rule quoted_expr() -> Node<Expression>
         = "^" "(" expr:expression() ")" {
-            Expression::Quoted(Rc::new(expr))
+            Node::new(Expression::Quoted(expr))
         }
         / "^" "(" ast:root_def() ")" {
-            Expression::QuotedAST(Rc::new(ast))
+            Node::new(Expression::QuotedAST(Node::new(ast)))
         }
         / symbol()
         / unquoted_expr()
 
-    rule unquoted_expr() -> Expression
+    rule unquoted_expr() -> Node<Expression>
         = "~" "(" expr:expression() ")" {
-            Expression::Unquoted(Rc::new(expr))
+            Node::new(Expression::Unquoted(expr))
         }
         / "~" "(" ast:root_def() ")" {
-            Expression::UnquotedAST(Rc::new(ast))
+            Node::new(Expression::UnquotedAST(Node::new(ast)))
         }
         / "~" var:variable() {
-            Expression::Unquoted(Rc::new(var))
+            Node::new(Expression::Unquoted(var))
         }
 
-    rule symbol() -> Expression
+    rule symbol() -> Node<Expression>
         = "^" id:symbol_identifier() {
-            Expression::Symbol(id)
+            Node::new(Expression::Symbol(id))
         }
 
-    rule query() -> Expression
+    rule query() -> Node<Expression>
         = bindings:query_binding()+ guards:query_guard()* _ prod:query_production() {
-            Expression::Query(
+            Node::new(Expression::Query(
                 Query::new(
                     QueryBindings::new(bindings),
                     QueryGuards::new(guards),
-                    Rc::new(prod)
+                    prod
                 )
-            )
+            ))
         }
 
     rule query_binding() -> QueryBinding
         = _ "?" _ vars:query_binding_vars() _ "<!-" _ expr:query_expr() _ {
-            QueryBinding::new_negated(vars, Rc::new(expr))
+            QueryBinding::new_negated(vars, expr)
         }
         / _ "?" _ vars:query_binding_vars() _ "<-" _ expr:query_expr() _ {
-            QueryBinding::new(vars, Rc::new(expr))
+            QueryBinding::new(vars, expr)
         }
 
     rule query_binding_vars() -> Vec<VarIdentifier>
@@ -322,94 +475,113 @@ grammar parser(context: &ParserContext) for str {
             VarIdentifier::new(var)
         }
 
-    rule query_expr() -> Expression
+    rule query_expr() -> Node<Expression>
+        = spanned(<unspanned_query_expr()>)
+
+    rule unspanned_query_expr() -> Node<Expression>
         = edge_prop()
         / "(" _ l:lambda() _ ")" { l }
         / "(" _ q:query() _ ")" { q }
-        / "(" _ c:(fn_pipe() / fn_call() / op_call()) _ ")" { c }
-        / fn_pipe()
+        / "(" _ c:op_expr() _ ")" { c }
+        / op_call()
         / fn_call()
         / constant_or_type_ref()
         / lambda()
         / variable()
         / quoted_expr()
-        / op_call()
         / literal_expr()
 
-    rule edge_prop() -> Expression
+    rule edge_prop() -> Node<Expression>
         = expr:edge_prop_expr() "#" edge:struct_identifier() {
-            Expression::EdgeProp(Rc::new(expr), edge)
+            Node::new(Expression::EdgeProp(expr, edge))
         }
 
-    rule edge_prop_expr() -> Expression
+    rule edge_prop_expr() -> Node<Expression>
         = variable()
         / "(" _ q:query() _ ")" { q }
-        / "(" _ c:(fn_pipe() / fn_call() / op_call()) _ ")" { c }
+        / "(" _ c:op_expr() _ ")" { c }
 
-    rule query_guard() -> Rc<Expression>
+    rule query_guard() -> Node<Expression>
         = _ c:comment() _ g:query_guard() {
-            Rc::new(Expression::Commented(c, g))
+            Node::new(Expression::Commented(c, g))
         }
         / _ "! " _ expr:query_expr() {
-            Rc::new(expr)
+            expr
         }
 
-    rule query_production() -> Expression
+    rule query_production() -> Node<Expression>
         = c:comment() _ qp:query_production() {
-            Expression::Commented(c, Rc::new(qp))
+            Node::new(Expression::Commented(c, qp))
         }
         / "!> " _ expr:query_expr() _ {
             expr
         }
 
 
-    rule fn_call() -> Expression
-        = _ ids:(identifier() ** ".") args:(fn_arg())+ _ {
-            let args = FnCallArgs::from_owned(args);
-            Expression::FnCall(FnCall::new(ids.join(".").into(), args))
+    rule fn_call() -> Node<Expression>
+        = _ c:spanned(<unspanned_fn_call()>) _ { c }
+
+    rule unspanned_fn_call() -> Node<Expression>
+        = ids:(identifier() ** ".") args:(fn_arg())+ {
+            Node::new(Expression::FnCall(FnCall::new(ids.join(".").into(), FnCallArgs::new(args))))
         }
-        / _ id:identifier() args:(fn_arg())+ _ {
-            let args = FnCallArgs::from_owned(args);
-            Expression::FnCall(FnCall::new(id, args))
+        / id:identifier() args:(fn_arg())+ {
+            Node::new(Expression::FnCall(FnCall::new(id, FnCallArgs::new(args))))
         }
 
-    #[cache_left_rec]
-    rule op_call() -> Expression
-        = left:op_call() " "+ id:operator() ws() right:op_arg() {
-            Expression::OpCall(id, Rc::new(left), Rc::new(right))
+    // An operand followed by any number of infix operator applications,
+    // grouped according to the operators' fixity.
+    rule op_expr() -> Node<Expression>
+        = first:op_arg() rest:(op_call_tail())* {?
+            op_calls(context, first, rest)
         }
-        / left:op_arg() " "+ id:operator() ws() right:op_arg() {
-            Expression::OpCall(id, Rc::new(left), Rc::new(right))
+
+    rule op_call() -> Node<Expression>
+        = first:op_arg() rest:(op_call_tail())+ {?
+            op_calls(context, first, rest)
         }
-        / left:op_arg() ws() id:operator() " "+ right:op_arg() {
-            Expression::OpCall(id, Rc::new(left), Rc::new(right))
+
+    rule op_call_tail() -> (Identifier, Node<Expression>)
+        = " "+ id:infix_operator() ws() right:op_arg() {
+            (id, right)
+        }
+        / ws() id:infix_operator() " "+ right:op_arg() {
+            (id, right)
         }
 
+    rule infix_operator() -> Identifier
+        = !"//" id:operator() {?
+            if is_reserved_operator(&id) {
+                Err("infix operator")
+            } else {
+                Ok(id)
+            }
+        }
 
-    rule fn_arg() -> Expression
-        = " "+ e:atom()  { e }
+    rule fn_arg() -> Node<Expression>
+        = " "+ !closing_keyword() !"->" e:atom()  { e }
 
-    rule op_arg() -> Expression
-        = "(" _ expr:atom() _ ")" {
-            expr
-        }
+    #[cache]
+    rule op_arg() -> Node<Expression>
+        = spanned(<if_else()>)
+        / spanned(<unspanned_fn_call()>)
         / atom()
 
-    rule let_expr() -> Expression
+    rule let_expr() -> Node<Expression>
         = "let" _ bindings:let_bindings() _ "in" _ body:let_body() {
-            Expression::Let(
-                LetExpression::new(bindings, Rc::new(body))
-            )
+            Node::new(Expression::Let(
+                LetExpression::new(bindings, body)
+            ))
         }
 
     rule let_bindings() -> LetBindings
         = binding:let_binding() more_bindings:(additional_let_binding())* {
             let mut bindings = more_bindings;
             bindings.insert(0, binding);
-            LetBindings::from_owned(bindings)
+            LetBindings::new(bindings)
         }
 
-    rule additional_let_binding() -> (VarIdentifier, Expression)
+    rule additional_let_binding() -> (VarIdentifier, Node<Expression>)
         = let_binding_sep()* binding:let_binding() {
             binding
         }
@@ -418,40 +590,57 @@ grammar parser(context: &ParserContext) for str {
         = " "* "\n"+
         / ","
 
-    rule let_binding() -> (VarIdentifier, Expression)
+    rule let_binding() -> (VarIdentifier, Node<Expression>)
         = _ id:identifier() _ "=" _ val:let_body() {
             (VarIdentifier::new(id.clone()), val)
         }
-        / _ id:identifier() _ args:(pattern() ** s()) _ "=" _ body:let_body() {
-            (VarIdentifier::new(id.clone()), Expression::InlineFnDef(FnDef::new_inline(id, FnDefArgs::new(args), Rc::new(FnDefBody::rogato(Rc::new(body))))))
+        / _ id:identifier() _ args:(fn_def_arg() ** s()) _ guard:fn_guard()? "=" _ body:let_body() {
+            let variant = FnDefVariant(FnDefArgs::new(args), guard, Rc::new(FnDefBody::rogato(body)));
+            (VarIdentifier::new(id.clone()), Node::new(Expression::InlineFnDef(FnDef::new_inline_with_variants(id, FnDefVariants::new([variant])))))
         }
 
-    rule let_body() -> Expression
-        = lambda()
+    rule let_body() -> Node<Expression>
+        = spanned(<unspanned_let_body()>)
+
+    rule unspanned_let_body() -> Node<Expression>
+        = case_expr()
+        / lambda()
         / if_else()
         / query()
-        / fn_pipe()
-        / fn_call()
-        / op_call()
-        / atom()
+        / op_expr()
         / commented_let_body()
 
-    rule commented_let_body() -> Expression
+    rule commented_let_body() -> Node<Expression>
         = c:comment() _ body:let_body() {
-            Expression::Commented(c, Rc::new(body))
+            Node::new(Expression::Commented(c, body))
         }
 
-    rule if_else() -> Expression
+    rule if_else() -> Node<Expression>
         = "if" " "+ cond:if_else_condition() " "+ "then" _ then_expr:atom() _ "else" _ else_expr:atom() {
-            Expression::IfElse(IfElse::new(Rc::new(cond), Rc::new(then_expr), Rc::new(else_expr)))
+            Node::new(Expression::IfElse(IfElse::new(cond, then_expr, else_expr)))
+        }
+
+    rule case_expr() -> Node<Expression>
+        = "case" " "+ scrutinee:op_expr() " "+ "of" _ arms:(case_arm() ++ case_arm_sep()) {
+            Node::new(Expression::Case(Case::new(scrutinee, arms)))
         }
 
-    rule if_else_condition() -> Expression
+    rule case_arm() -> CaseArm
+        = pattern:or_pattern() s() "->" _ body:let_body() {
+            CaseArm::new(pattern, body)
+        }
+
+    rule case_arm_sep()
+        = " "* "," _
+        / (" "* "\n")+ _
+
+    rule if_else_condition() -> Node<Expression>
         = variable()
         / tuple_item()
 
-    rule literal_expr() -> Expression
+    rule literal_expr() -> Node<Expression>
         = number_lit_expr()
+        / struct_update_expr()
         / map_lit_expr()
         / bool_lit_expr()
         / string_lit_expr()
@@ -459,134 +648,151 @@ grammar parser(context: &ParserContext) for str {
         / tuple_lit_expr()
         / list_lit_expr()
 
-    rule number_lit_expr() -> Expression
+    rule number_lit_expr() -> Node<Expression>
         = n:number_lit() {
-            Expression::Lit(Literal::Number(n))
+            Node::new(Expression::Lit(Literal::Number(n)))
         }
 
-    rule bool_lit_expr() -> Expression
+    rule bool_lit_expr() -> Node<Expression>
         = b:bool_lit() {
-            Expression::Lit(Literal::Bool(b))
+            Node::new(Expression::Lit(Literal::Bool(b)))
         }
 
-    rule string_lit_expr() -> Expression
-        = s:string_lit() {
-            Expression::Lit(Literal::String(s))
+    rule string_lit_expr() -> Node<Expression>
+        = parts:string_parts() {
+            Node::new(match plain_string(&parts) {
+                Some(string) => Expression::Lit(Literal::String(string)),
+                None => Expression::Lit(Literal::InterpolatedString(InterpolatedString::new(parts))),
+            })
         }
 
-    rule tuple_lit_expr() -> Expression
+    rule tuple_lit_expr() -> Node<Expression>
         = "{" _ first:tuple_item() rest:(additional_tuple_item())+ _ ("," _)? "}" {
-            Expression::Lit(Literal::Tuple(TupleItems::new(first, rest)))
+            let mut items = rest;
+            items.insert(0, first);
+            Node::new(Expression::Lit(Literal::Tuple(TupleItems::from(items))))
         }
 
-    rule list_lit_expr() -> Expression
+    rule list_lit_expr() -> Node<Expression>
         = "[" _ first:tuple_item() rest:(additional_tuple_item())+ _ ("," _)? "]" {
-            Expression::Lit(Literal::List(TupleItems::new(first, rest)))
+            let mut items = rest;
+            items.insert(0, first);
+            Node::new(Expression::Lit(Literal::List(TupleItems::from(items))))
         }
         / "[" _ item:tuple_item() _ "]" {
-            Expression::Lit(Literal::List(TupleItems::new(item, vec![])))
+            Node::new(Expression::Lit(Literal::List(TupleItems::from(vec![item]))))
         }
         / "[" _ first:tuple_item() _ "::" _ rest:tuple_item() "]" {
-            Expression::Lit(Literal::ListCons(Rc::new(first), Rc::new(rest)))
+            Node::new(Expression::Lit(Literal::ListCons(first, rest)))
         }
         / "[" _ "]" {
-            Expression::Lit(Literal::List(TupleItems::from(vec![])))
+            Node::new(Expression::Lit(Literal::List(TupleItems::from(vec![]))))
         }
         / "[" _ comment() _ "]" {
-            Expression::Lit(Literal::List(TupleItems::from(vec![])))
+            Node::new(Expression::Lit(Literal::List(TupleItems::from(vec![]))))
         }
 
-    rule map_lit_expr() -> Expression
+    rule map_lit_expr() -> Node<Expression>
         = "{" _ kv_pairs:(kv_pair() ** (_ "," _)) _ "}" {
-            Expression::Lit(Literal::Map(TupleItems::from(kv_pairs)))
+            Node::new(Expression::Lit(Literal::Map(TupleItems::from(kv_pairs))))
         }
         / "{" _ rest:tuple_item() _ "::" _ kv_pairs:(kv_pair() ** (_ "," _)) _ "}" {
-            Expression::Lit(Literal::MapCons(TupleItems::from(kv_pairs), Rc::new(rest)))
+            Node::new(Expression::Lit(Literal::MapCons(TupleItems::from(kv_pairs), rest)))
         }
 
-    rule kv_pair() -> Rc<MapKVPair<Expression>>
+    rule kv_pair() -> Node<MapKVPair<Expression>>
         = key:tuple_item() _ ":" _ value:tuple_item() {
-            Rc::new(MapKVPair::new(Rc::new(key), Rc::new(value)))
+            Node::new(MapKVPair::new(key, value))
         }
 
-    rule tuple_item() -> Expression
-        = fn_call()
-        / fn_pipe()
-        / op_call()
-        / atom()
+    rule tuple_item() -> Node<Expression>
+        = spanned(<unspanned_tuple_item()>)
+
+    rule unspanned_tuple_item() -> Node<Expression>
+        = op_expr()
         / commented_tuple_item()
 
-    rule commented_tuple_item() -> Expression
+    rule commented_tuple_item() -> Node<Expression>
         = c:comment() _ item:tuple_item() {
-            Expression::Commented(c, Rc::new(item))
+            Node::new(Expression::Commented(c, item))
         }
 
-    rule additional_tuple_item() -> Expression
+    rule additional_tuple_item() -> Node<Expression>
         = _ "," _ item:tuple_item() {
             item
         }
 
-    rule struct_lit_expr() -> Expression
+    rule struct_lit_expr() -> Node<Expression>
         = id:struct_identifier() "{" _ first:struct_prop() rest:(additional_struct_prop())*  _ ("," _)? "}" {
-            Expression::Lit(Literal::Struct(id, Rc::new(StructProps::new(first, rest))))
+            Node::new(Expression::Lit(Literal::Struct(id, Rc::new(StructProps::new(first, rest)))))
+        }
+
+    rule struct_update_expr() -> Node<Expression>
+        = "{" _ base:struct_update_base() _ "|" _ first:struct_prop() rest:(additional_struct_prop())* _ ("," _)? "}" {
+            Node::new(Expression::Lit(Literal::StructUpdate(base, Rc::new(StructProps::new(first, rest)))))
         }
 
-    rule additional_struct_prop() -> (Identifier, Rc<Expression>)
+    rule struct_update_base() -> Node<Expression>
+        = spanned(<variable()>)
+        / spanned(<struct_lit_expr()>)
+        / "(" _ e:expression() _ ")" { e }
+
+    rule additional_struct_prop() -> (Identifier, Node<Expression>)
         = _ "," _ prop:struct_prop() {
             prop
         }
 
-    rule struct_prop() -> (Identifier, Rc<Expression>)
+    rule struct_prop() -> (Identifier, Node<Expression>)
         = id:identifier() _ ":" _ expr:(tuple_item()) {
-            (id, Rc::new(expr))
+            (id, expr)
         }
 
-    rule lambda() -> Expression
+    rule lambda() -> Node<Expression>
         = "(" _ variants:(lambda_variant() ** (_ "," _)) _ ")" {
-            Expression::Lambda(Rc::new(Lambda::new(variants)))
+            Node::new(Expression::Lambda(Rc::new(Lambda::new(variants))))
         }
         / variant:lambda_variant() {
-            Expression::Lambda(Rc::new(Lambda::new(vec![variant])))
+            Node::new(Expression::Lambda(Rc::new(Lambda::new(vec![variant]))))
         }
 
     rule lambda_variant() -> Rc<LambdaVariant>
-        = args:lambda_args() s() "->" _ body:let_body() {
-            Rc::new(LambdaVariant::new(LambdaArgs::new(args), Rc::new(body)))
+        = args:lambda_args() s() guard:fn_guard()? "->" _ body:let_body() {
+            Rc::new(LambdaVariant::guarded(LambdaArgs::new(args), guard, body))
         }
         / "->" _ body:let_body() {
-            Rc::new(LambdaVariant::new(LambdaArgs::empty(), Rc::new(body)))
+            Rc::new(LambdaVariant::new(LambdaArgs::empty(), body))
         }
         / "(" _ v:lambda_variant() _ ")" {
             v
         }
 
-    rule lambda_args() -> Vec<Rc<Pattern>>
+    rule lambda_args() -> Vec<Node<Pattern>>
         = arg:lambda_arg() rest:(additional_lambda_arg())* {
             let mut args = rest;
             args.insert(0, arg);
             args
         }
 
-    rule additional_lambda_arg() -> Rc<Pattern>
+    rule additional_lambda_arg() -> Node<Pattern>
         = " "+ arg:lambda_arg() {
             arg
         }
 
-    rule lambda_arg() -> Rc<Pattern>
-        = p:pattern() {
+    rule lambda_arg() -> Node<Pattern>
+        = !keyword() p:pattern() {
             p
         }
 
-    rule constant_or_type_ref() -> Expression
+    rule constant_or_type_ref() -> Node<Expression>
         = id:struct_identifier() {
-            if is_qualified_fn_call(&id) {
+            Node::new(if is_qualified_fn_call(&id) {
                 Expression::FnCall(FnCall::new(id, FnCallArgs::empty()))
             }else{
                 Expression::ConstOrTypeRef(id)
-            }
+            })
         }
         / "@" id:struct_identifier() {
-            Expression::DBTypeRef(id)
+            Node::new(Expression::DBTypeRef(id))
         }
 
     rule struct_identifier() -> Identifier
@@ -619,10 +825,10 @@ grammar parser(context: &ParserContext) for str {
         }
 
     rule operator() -> Identifier
-        = "!" id:$(['+' | '-' | '*' | '/' | '>' | '<' | '=' | '!' | '^' | '=' | '|' | '%'])+ {
+        = "!" id:$(['+' | '-' | '*' | '/' | '>' | '<' | '=' | '!' | '^' | '=' | '|' | '%' | '&'])+ {
             join_string("!", id)
         }
-        / id:$(['+' | '-' | '*' | '/' | '>' | '<' | '=' | '^' | '=' | '|' | '%'])+ id2:$(['+' | '-' | '*' | '/' | '>' | '<' | '=' | '!' | '^' | '=' | '|' | '%'])* {
+        / id:$(['+' | '-' | '*' | '/' | '>' | '<' | '=' | '^' | '=' | '|' | '%' | '&'])+ id2:$(['+' | '-' | '*' | '/' | '>' | '<' | '=' | '!' | '^' | '=' | '|' | '%' | '&'])* {
             join_string(SmolStr::from_iter(id).as_str(), id2)
         }
 
@@ -643,8 +849,43 @@ grammar parser(context: &ParserContext) for str {
         }
 
     rule string_lit() -> String
-        = "\"" s:([^ '"']*) "\"" {
-            String::from_iter(s)
+        = parts:string_parts() {?
+            plain_string(&parts).ok_or("string without interpolation")
+        }
+
+    rule string_parts() -> Vec<StringPart>
+        = "\"\"\"" parts:(string_part(<"\"\"\"">))* "\"\"\"" {
+            merge_string_parts(parts)
+        }
+        / "\"" parts:(string_part(<"\"">))* "\"" {
+            merge_string_parts(parts)
+        }
+
+    rule string_part(delimiter: rule<()>) -> StringPart
+        = "${" _ expr:expression() _ "}" {
+            StringPart::Expr(expr)
+        }
+        / !"${" !delimiter() c:string_char() {
+            StringPart::Lit(c.to_string())
+        }
+
+    rule string_char() -> char
+        = "\\" c:escape_sequence() { c }
+        / [^ '\\']
+
+    rule escape_sequence() -> char
+        = "n" { '\n' }
+        / "t" { '\t' }
+        / "r" { '\r' }
+        / "0" { '\0' }
+        / "\\" { '\\' }
+        / "\"" { '"' }
+        / "$" { '$' }
+        / "u{" code:$(['0'..='9' | 'a'..='f' | 'A'..='F']*<1,6>) "}" {?
+            u32::from_str_radix(code, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .ok_or("unicode scalar value")
         }
 
     rule _
@@ -653,6 +894,17 @@ grammar parser(context: &ParserContext) for str {
     rule ws()
         = ([' ' | '\t' | '\n'])+
 
+    rule keyword()
+        = ("case" / "in" / "when") !identifier_char()
+        / closing_keyword()
+
+    // Keywords that end the preceding expression and thus can't be passed as fn args.
+    rule closing_keyword()
+        = ("of" / "then" / "else") !identifier_char()
+
+    rule identifier_char()
+        = ['a'..='z' | 'A'..='Z' | '-' | '_' | '0'..='9' | '.' | '@' | '$']
+
     rule s()
         = ([' ' | '\t'])+
 
@@ -665,29 +917,119 @@ grammar parser(context: &ParserContext) for str {
 
 pub type ParseResult = Result<Program, ParseError<LineCol>>;
 
-#[cfg(not(feature = "trace"))]
 pub fn parse(str: &str, context: &ParserContext) -> ParseResult {
-    parser::program(str, context)
+    parse_source(str, None, context)
+}
+
+/// Parses a program, recording `file_path` as the source of all spans within it.
+pub fn parse_file(str: &str, file_path: &str, context: &ParserContext) -> ParseResult {
+    parse_source(str, Some(file_path), context)
+}
+
+/// Parses the fixity definitions of a program, which apply to all of its code,
+/// even before the definition itself.
+pub fn parse_fixity_defs(str: &str, context: &ParserContext) -> Vec<FixityDef> {
+    line_start_defs(str, context)
+        .filter_map(|def| match def {
+            AST::FixityDef(fixity_def) => Some(fixity_def),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Parses the `use` definitions of a program, e.g. to load the imported modules
+/// (and their fixities) before parsing the rest of the program.
+pub fn parse_use_defs(str: &str, context: &ParserContext) -> Vec<UseDef> {
+    line_start_defs(str, context)
+        .filter_map(|def| match def {
+            AST::Use(use_def) => Some(use_def),
+            _ => None,
+        })
+        .collect()
+}
+
+fn line_start_defs(str: &str, context: &ParserContext) -> impl Iterator<Item = AST> {
+    parser::line_start_defs(str, context, &SourceMap::new(str, None))
+        .unwrap_or_default()
+        .into_iter()
+}
+
+#[cfg(not(feature = "trace"))]
+fn parse_source(str: &str, source: Option<&str>, context: &ParserContext) -> ParseResult {
+    context.with_fixities(parse_fixity_defs(str, context), || {
+        parser::program(str, context, &SourceMap::new(str, source))
+    })
 }
 
 #[cfg(feature = "trace")]
-pub fn parse(str: &str, context: &ParserContext) -> ParseResult {
-    parser::traced_program(str, context)
+fn parse_source(str: &str, source: Option<&str>, context: &ParserContext) -> ParseResult {
+    context.with_fixities(parse_fixity_defs(str, context), || {
+        parser::traced_program(str, context, &SourceMap::new(str, source))
+    })
 }
 
-pub type ParseASTResult = Result<Rc<AST>, ParseError<LineCol>>;
+/// Parses a program, recovering from parse errors by skipping to the next line starting
+/// a root-level definition.
+/// Returns all definitions that could be parsed along with a diagnostic for every error.
+pub fn parse_with_recovery(
+    str: &str,
+    source: Option<&str>,
+    context: &ParserContext,
+) -> (Program, Vec<ParseDiagnostic>) {
+    context.with_fixities(parse_fixity_defs(str, context), || {
+        recover_program(str, source, context)
+    })
+}
+
+fn recover_program(
+    str: &str,
+    source: Option<&str>,
+    context: &ParserContext,
+) -> (Program, Vec<ParseDiagnostic>) {
+    let source_map = SourceMap::new(str, source);
+    if let Ok(program) = parser::program(str, context, &source_map) {
+        return (program, vec![]);
+    }
+
+    let mut defs = vec![];
+    let mut diagnostics = vec![];
+    let recovered = parser::recovered_program(str, context, &source_map).unwrap_or_default();
+    for def in recovered {
+        let (start, end) = match def {
+            Ok(def) => {
+                defs.push(def);
+                continue;
+            }
+            Err(skipped) => skipped,
+        };
+        // parsed on its own to find where exactly the skipped definition fails,
+        // which is reported at the end of its code if the definition is incomplete
+        let code = &str[start..end];
+        match parser::program(code, context, &source_map.at_offset(start)) {
+            Ok(program) => defs.extend(program.iter().cloned()),
+            Err(error) => {
+                let offset = start + error.location.offset.min(code.trim_end().len());
+                diagnostics.push(ParseDiagnostic::new(
+                    source_map.span(offset, offset),
+                    error.expected,
+                ));
+            }
+        }
+    }
+
+    (Program::new(defs), diagnostics)
+}
+
+pub type ParseASTResult = Result<Node<AST>, ParseError<LineCol>>;
 
 pub fn parse_ast(str: &str, context: &ParserContext) -> ParseASTResult {
-    parser::program_root_def(str, context)
+    parser::program_root_def(str, context, &SourceMap::new(str, None))
 }
 
-pub type ParseExprResult = Result<Rc<Expression>, ParseError<LineCol>>;
+pub type ParseExprResult = Result<Node<Expression>, ParseError<LineCol>>;
 
 pub fn parse_expr(str: &str, context: &ParserContext) -> ParseExprResult {
-    match parser::expression(str, context) {
-        Ok(expr) => Ok(Rc::new(expr)),
-        Err(err) => Err(err),
-    }
+    parser::expression(str, context, &SourceMap::new(str, None))
 }
 
 fn join_string(first: &str, rest: Vec<&str>) -> SmolStr {
@@ -698,6 +1040,135 @@ fn join_string(first: &str, rest: Vec<&str>) -> SmolStr {
     SmolStr::from_iter(parts)
 }
 
+/// Groups a chain of infix operator applications into nested calls, based on the fixity
+/// of each operator known to the parser context at the time of parsing.
+fn op_calls(
+    context: &ParserContext,
+    first: Node<Expression>,
+    rest: Vec<(Identifier, Node<Expression>)>,
+) -> Result<Node<Expression>, &'static str> {
+    let mut rest = rest.into_iter().peekable();
+    fold_op_calls(context, first, &mut rest, 0)
+}
+
+fn fold_op_calls(
+    context: &ParserContext,
+    mut left: Node<Expression>,
+    rest: &mut Peekable<IntoIter<(Identifier, Node<Expression>)>>,
+    min_precedence: u8,
+) -> Result<Node<Expression>, &'static str> {
+    while let Some(fixity) = rest.peek().map(|(id, _)| context.fixity(id)) {
+        if fixity.precedence() < min_precedence {
+            break;
+        }
+        let (id, mut right) = rest.next().unwrap();
+
+        while let Some(next_fixity) = rest.peek().map(|(id, _)| context.fixity(id)) {
+            if next_fixity.precedence() > fixity.precedence() {
+                right = fold_op_calls(context, right, rest, fixity.precedence() + 1)?;
+                continue;
+            }
+            if next_fixity.precedence() < fixity.precedence() {
+                break;
+            }
+            match (fixity.associativity(), next_fixity.associativity()) {
+                (Associativity::Left, Associativity::Left) => break,
+                (Associativity::Right, Associativity::Right) => {
+                    right = fold_op_calls(context, right, rest, fixity.precedence())?;
+                }
+                _ => return Err("parenthesized operator expression of equal precedence"),
+            }
+        }
+
+        left = op_call(id, left, right)?;
+    }
+    Ok(left)
+}
+
+fn op_call(
+    id: Identifier,
+    left: Node<Expression>,
+    right: Node<Expression>,
+) -> Result<Node<Expression>, &'static str> {
+    let span = match (left.span(), right.span()) {
+        (Some(left_span), Some(right_span)) => Some(left_span.merge(right_span)),
+        _ => None,
+    };
+    let op_call = if id == "|>" {
+        Node::new(fn_pipe(left, &right)?)
+    } else {
+        Node::new(Expression::OpCall(id, left, right))
+    };
+    Ok(op_call.with_span(span.as_ref()))
+}
+
+/// Desugars `left |> right` into a call of `right` with `left` prepended to its arguments.
+fn fn_pipe(left: Node<Expression>, right: &Expression) -> Result<Expression, &'static str> {
+    match right {
+        Expression::FnCall(fn_call) => {
+            let mut args = fn_call.args.clone();
+            args.prepend_arg(left);
+            Ok(Expression::FnCall(FnCall::new(fn_call.id.clone(), args)))
+        }
+        Expression::Var(id) => Ok(Expression::FnCall(FnCall::new(
+            id.into(),
+            FnCallArgs::new([left]),
+        ))),
+        Expression::ConstOrTypeRef(id) => Ok(Expression::FnCall(FnCall::new(
+            id.clone(),
+            FnCallArgs::new([left]),
+        ))),
+        _ => Err("function call after |>"),
+    }
+}
+
+/// Joins adjacent literal parts of a string literal.
+fn merge_string_parts(parts: Vec<StringPart>) -> Vec<StringPart> {
+    let mut merged: Vec<StringPart> = Vec::with_capacity(parts.len());
+    for part in parts {
+        match (merged.last_mut(), part) {
+            (Some(StringPart::Lit(string)), StringPart::Lit(next)) => string.push_str(&next),
+            (_, part) => merged.push(part),
+        }
+    }
+    merged
+}
+
+/// Returns the contents of a string literal without any interpolated expressions.
+fn plain_string(parts: &[StringPart]) -> Option<String> {
+    match parts {
+        [] => Some(String::new()),
+        [StringPart::Lit(string)] => Some(string.clone()),
+        _ => None,
+    }
+}
+
+/// Turns a dotted variable identifier like `c.location.lat` into a chain of property accesses
+/// on the variable `c`. Identifiers without (or with empty) dotted parts stay plain variables.
+fn prop_access(id: &Identifier) -> Node<Expression> {
+    let mut parts = id.split('.');
+    match parts.next() {
+        Some(var_id) if id.contains('.') && !id.split('.').any(str::is_empty) => parts
+            .fold(Node::new(Expression::Var(var_id.into())), |expr, prop| {
+                Node::new(Expression::PropAccess(expr, prop.into()))
+            }),
+        _ => Node::new(Expression::Var(id.into())),
+    }
+}
+
+/// Turns the dotted property names following a parenthesized expression, like `name.first` in
+/// `(f x).name.first`, into a chain of property accesses on that expression.
+fn prop_accesses(expr: Node<Expression>, props: &Identifier) -> Node<Expression> {
+    props.split('.').fold(expr, |expr, prop| {
+        Node::new(Expression::PropAccess(expr, prop.into()))
+    })
+}
+
+/// Operator-like tokens that are part of the language's syntax and can't be used as infix operators.
+fn is_reserved_operator(id: &Identifier) -> bool {
+    matches!(id.as_str(), "=" | "->" | "!>")
+}
+
 fn is_qualified_fn_call(id: &Identifier) -> bool {
     let id_parts: Vec<&str> = id.split('.').collect();
     if let Some(last) = id_parts.last() {
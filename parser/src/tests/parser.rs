@@ -1,15 +1,29 @@
 #[cfg(test)]
-use crate::{assert_parse, assert_parse_ast, assert_parse_expr, parse_expr, ParserContext};
+use crate::{
+    assert_parse, assert_parse_ast, assert_parse_expr, parse, parse_ast, parse_expr, parse_file,
+    parse_fixity_defs, parse_with_recovery, ParserContext,
+};
 
 #[cfg(test)]
 use rogato_common::ast::helpers::inline_fn_def;
 use rogato_common::ast::helpers::{
-    any_p, bool_lit, bool_p, commented, const_or_type_ref, db_type_ref, edge_prop, empty_list_p,
-    fn_call, fn_def, if_else, int_type, lambda, lambda_p, let_expr, list_cons, list_cons_p,
-    list_lit, list_lit_p, list_type, map_cons_lit, map_cons_lit_p, map_lit, map_lit_p, module_def,
-    number_lit, number_p, op_call, program, prop_fn_ref, query, quoted, quoted_ast, root_comment,
-    string_lit, string_p, string_type, struct_lit, struct_type, symbol, symbol_p, tuple_lit,
-    tuple_lit_p, tuple_type, type_def, type_ref, unquoted, unquoted_ast, var, var_p, vars,
+    any_p, as_p, bool_lit, bool_p, case_of, commented, const_or_type_ref, constructor_p,
+    db_type_ref, edge_prop, empty_list_p, fixity_def, fn_call, fn_def, fn_ref, guarded_fn_def,
+    guarded_lambda_p, if_else, import_fn, import_type, int_type, interpolated_string_lit, lambda,
+    lambda_p, let_expr, list_cons, list_cons_p, list_lit, list_lit_p, list_type, macro_def,
+    map_cons_lit, map_cons_lit_p, map_lit, map_lit_p, module_def, number_lit, number_p, op_call,
+    or_p, program, prop_access, prop_fn_ref, query, quoted, quoted_ast, root_comment, string_lit,
+    string_p, string_type, struct_lit, struct_p, struct_type, struct_update_lit, symbol, symbol_p,
+    tuple_lit, tuple_lit_p, tuple_type, type_def, type_ref, union_type, unquoted, unquoted_ast,
+    use_def, use_imports, var, var_p, vars,
+};
+#[cfg(test)]
+use rogato_common::ast::{
+    expression::StringPart,
+    fixity::{Fixity, FixityDef},
+    fn_def::FnDefBody,
+    use_def::UseImports,
+    AST,
 };
 #[cfg(test)]
 use rust_decimal_macros::dec;
@@ -169,6 +183,126 @@ fn patterns() {
     );
 }
 
+#[test]
+fn rich_patterns() {
+    assert_parse_ast!(
+        "let f ^foo = ^bar",
+        fn_def("f", [symbol_p("foo")], symbol("bar"))
+    );
+
+    assert_parse_ast!(
+        "let name Person{name: n, age: _} = n",
+        fn_def(
+            "name",
+            [struct_p(
+                Some("Person"),
+                [("name", var_p("n")), ("age", any_p())]
+            )],
+            var("n")
+        )
+    );
+
+    assert_parse_ast!(
+        "let name p@Person{name: n} = {p, n}",
+        fn_def(
+            "name",
+            [as_p("p", struct_p(Some("Person"), [("name", var_p("n"))]))],
+            tuple_lit([var("p"), var("n")])
+        )
+    );
+
+    // without a struct id, braces with key value pairs are map patterns
+    assert_parse_ast!(
+        "let single {k: v} = {k, v}",
+        fn_def(
+            "single",
+            [map_lit_p([(var_p("k"), var_p("v"))])],
+            tuple_lit([var("k"), var("v")])
+        )
+    );
+
+    assert_parse_ast!(
+        "let name _{name: n} = n",
+        fn_def("name", [struct_p(None, [("name", var_p("n"))])], var("n"))
+    );
+
+    assert_parse_ast!(
+        "let pick ({x, 0} | {0, x}) = x",
+        fn_def(
+            "pick",
+            [or_p([
+                tuple_lit_p([var_p("x"), number_p(0)]),
+                tuple_lit_p([number_p(0), var_p("x")])
+            ])],
+            var("x")
+        )
+    );
+
+    // alternatives need to bind the same variables
+    let parser_ctx = ParserContext::new();
+    assert!(parse_ast("let pick ({x, 0} | {0, y}) = x", &parser_ctx).is_err());
+    assert!(parse_ast("let pick (x | 0) = x", &parser_ctx).is_err());
+
+    assert_parse_ast!(
+        "let f all@[x :: _] = x",
+        fn_def(
+            "f",
+            [as_p("all", list_cons_p(var_p("x"), any_p()))],
+            var("x")
+        )
+    );
+
+    assert_parse_ast!(
+        "let small (1 | 2 | 3) = true",
+        fn_def(
+            "small",
+            [or_p([number_p(1), number_p(2), number_p(3)])],
+            bool_lit(true)
+        )
+    );
+
+    assert_parse_ast!(
+        "let f (Some x) None = x",
+        fn_def(
+            "f",
+            [
+                constructor_p("Some", [var_p("x")]),
+                constructor_p("None", [])
+            ],
+            var("x")
+        )
+    );
+
+    assert_parse_expr!(
+        "case x of
+            ^a | ^b -> 1
+            n@(Just (1 | 2)) -> n",
+        case_of(
+            var("x"),
+            [
+                (or_p([symbol_p("a"), symbol_p("b")]), number_lit(1)),
+                (
+                    as_p(
+                        "n",
+                        constructor_p("Just", [or_p([number_p(1), number_p(2)])])
+                    ),
+                    var("n")
+                ),
+            ]
+        )
+    );
+
+    for code in [
+        "let name p@Person{name: n, age: _} = n",
+        "let small (1 | 2 | 3) = true",
+        "let f x@(^a | ^b) y@{k: [z :: _]} = {x, y, z}",
+    ] {
+        let ctx = ParserContext::new();
+        let program = crate::parse(code, &ctx).unwrap();
+        assert_eq!(crate::parse(&program.to_string(), &ctx), Ok(program));
+    }
+}
+
 #[test]
 fn module_defs() {
     assert_parse_ast!("module MyModule", module_def("MyModule", []));
@@ -195,6 +329,59 @@ fn module_defs() {
     );
 }
 
+#[test]
+fn use_defs() {
+    assert_parse_ast!("use Std.List", use_def("Std.List", None, use_imports([])));
+
+    assert_parse_ast!(
+        "use Std.List { map, filter }",
+        use_def(
+            "Std.List",
+            None,
+            use_imports([import_fn("map", None), import_fn("filter", None)])
+        )
+    );
+
+    assert_parse_ast!(
+        "use Std.List as L",
+        use_def("Std.List", Some("L"), use_imports([]))
+    );
+
+    assert_parse_ast!(
+        "use Std.List {*}",
+        use_def("Std.List", None, UseImports::All)
+    );
+
+    assert_parse_ast!(
+        "use Std.Result as R { * }",
+        use_def("Std.Result", Some("R"), UseImports::All)
+    );
+
+    assert_parse_ast!(
+        "use Std.List { map as lmap, Type Foo, Type Bar as Baz, reverse }",
+        use_def(
+            "Std.List",
+            None,
+            use_imports([
+                import_fn("map", Some("lmap")),
+                import_type("Foo", None),
+                import_type("Bar", Some("Baz")),
+                import_fn("reverse", None),
+            ])
+        )
+    );
+
+    for code in [
+        "use Std.List",
+        "use Std.List as L",
+        "use Std.List { * }",
+        "use Std.List as L { map as lmap, Type Foo as F }",
+    ] {
+        let ast = parse_ast(code, &ParserContext::new()).unwrap();
+        assert_eq!(ast.to_string(), code);
+    }
+}
+
 #[test]
 fn arithmetic_expressions() {
     assert_parse_expr!("1 + 1", op_call("+", number_lit(1), number_lit(1)));
@@ -302,6 +489,30 @@ fn literals() {
         )
     );
 
+    assert_parse_expr!(
+        "{ p | age: p.age + 1 }",
+        struct_update_lit(
+            var("p"),
+            [(
+                "age",
+                op_call("+", prop_access(var("p"), "age"), number_lit(1))
+            )]
+        )
+    );
+
+    assert_parse_expr!(
+        "{Point{x: 1, y: 2} | x: 3, y: 4,}",
+        struct_update_lit(
+            struct_lit("Point", [("x", number_lit(1)), ("y", number_lit(2))]),
+            [("x", number_lit(3)), ("y", number_lit(4))]
+        )
+    );
+
+    assert_parse_expr!(
+        "{(origin 1) | x: 0}",
+        struct_update_lit(fn_call("origin", [number_lit(1)]), [("x", number_lit(0))])
+    );
+
     assert_parse_expr!("[]", list_lit([]));
 
     assert_parse_expr!(
@@ -420,6 +631,62 @@ fn literals() {
     );
 }
 
+#[test]
+fn string_literals() {
+    assert_parse_expr!(r#""""#, string_lit(""));
+    assert_parse_expr!(r#""say \"hi\"\n""#, string_lit("say \"hi\"\n"));
+    assert_parse_expr!(r#""a\tb\\c\r\0""#, string_lit("a\tb\\c\r\0"));
+    assert_parse_expr!(r#""\u{1F600} \u{e9}""#, string_lit("\u{1F600} \u{e9}"));
+    assert_parse_expr!(r#""costs \${price}""#, string_lit("costs ${price}"));
+    assert_parse_expr!(
+        "\"\"\"first line\n\"quoted\" second line\"\"\"",
+        string_lit("first line\n\"quoted\" second line")
+    );
+
+    assert!(parse_expr(r#""\q""#, &ParserContext::new()).is_err());
+    assert!(parse_expr(r#""\u{110000}""#, &ParserContext::new()).is_err());
+
+    assert_parse_expr!(
+        r#""Hello ${name}!""#,
+        interpolated_string_lit([
+            StringPart::Lit("Hello ".into()),
+            StringPart::Expr(var("name")),
+            StringPart::Lit("!".into()),
+        ])
+    );
+    assert_parse_expr!(
+        r#""${x} + ${ y } = ${x + y}""#,
+        interpolated_string_lit([
+            StringPart::Expr(var("x")),
+            StringPart::Lit(" + ".into()),
+            StringPart::Expr(var("y")),
+            StringPart::Lit(" = ".into()),
+            StringPart::Expr(op_call("+", var("x"), var("y"))),
+        ])
+    );
+    assert_parse_expr!(
+        r#""Name: ${"\"${name}\""}""#,
+        interpolated_string_lit([
+            StringPart::Lit("Name: ".into()),
+            StringPart::Expr(interpolated_string_lit([
+                StringPart::Lit("\"".into()),
+                StringPart::Expr(var("name")),
+                StringPart::Lit("\"".into()),
+            ])),
+        ])
+    );
+
+    for code in [
+        r#""say \"hi\"\n\ttwice\\""#,
+        "\"\"\"multi\nline \"string\" here\"\"\"",
+        r#""Hello ${name}, you are ${age + 1} \${not} interpolated\n""#,
+    ] {
+        let ctx = ParserContext::new();
+        let parsed = parse_expr(code, &ctx).unwrap();
+        assert_eq!(parse_expr(&parsed.to_string(), &ctx), Ok(parsed));
+    }
+}
+
 #[test]
 fn fn_calls() {
     assert_parse_expr!("add 1 2", fn_call("add", [number_lit(1), number_lit(2)]));
@@ -537,6 +804,201 @@ fn op_calls() {
     );
 }
 
+#[test]
+fn op_precedence() {
+    assert_parse_expr!(
+        "1 + 2 * 3",
+        op_call(
+            "+",
+            number_lit(1),
+            op_call("*", number_lit(2), number_lit(3))
+        )
+    );
+
+    assert_parse_expr!(
+        "1 * 2 + 3 * 4 - 5",
+        op_call(
+            "-",
+            op_call(
+                "+",
+                op_call("*", number_lit(1), number_lit(2)),
+                op_call("*", number_lit(3), number_lit(4))
+            ),
+            number_lit(5)
+        )
+    );
+
+    assert_parse_expr!(
+        "2 ^ 3 ^ 2",
+        op_call(
+            "^",
+            number_lit(2),
+            op_call("^", number_lit(3), number_lit(2))
+        )
+    );
+
+    assert_parse_expr!(
+        "a + 1 == b * 2 && c || d",
+        op_call(
+            "||",
+            op_call(
+                "&&",
+                op_call(
+                    "==",
+                    op_call("+", var("a"), number_lit(1)),
+                    op_call("*", var("b"), number_lit(2))
+                ),
+                var("c")
+            ),
+            var("d")
+        )
+    );
+
+    assert_parse_expr!(
+        "foo x + bar y",
+        op_call("+", fn_call("foo", [var("x")]), fn_call("bar", [var("y")]))
+    );
+
+    assert_parse_expr!(
+        "1 + 2 |> toString |> String.length",
+        fn_call(
+            "String.length",
+            [fn_call(
+                "toString",
+                [op_call("+", number_lit(1), number_lit(2))]
+            )]
+        )
+    );
+
+    assert_parse_expr!(
+        "xs\n  |> List.map f\n  |> List.sum",
+        fn_call("List.sum", [fn_call("List.map", [var("xs"), var("f")])])
+    );
+
+    let parser_ctx = ParserContext::new();
+    assert!(parse_expr("1 == 2 == 3", &parser_ctx).is_err());
+    assert!(parse_expr("1 < 2 > 3", &parser_ctx).is_err());
+    assert!(parse_expr("x |> 1", &parser_ctx).is_err());
+    assert!(parse_expr("(1 == 2) == (2 == 3)", &parser_ctx).is_ok());
+
+    for code in [
+        "1 + 2 * 3",
+        "(1 + 2) * 3",
+        "2 ^ 3 ^ 2",
+        "(2 ^ 3) ^ 2",
+        "1 - (2 - 3)",
+        "a + 1 == b * 2 && c || d",
+        "foo x + bar (y * 2)",
+    ] {
+        let expr = parse_expr(code, &parser_ctx).unwrap();
+        let printed = expr.to_string();
+        assert_eq!(
+            parse_expr(printed.as_str(), &parser_ctx),
+            Ok(expr),
+            "Expected {code:?} printed as {printed:?} to round-trip"
+        );
+    }
+}
+
+#[test]
+fn fixity_defs() {
+    assert_parse_ast!("infixl 6 <+>", fixity_def("<+>", Fixity::left(6)));
+    assert_parse_ast!("infixr 0 <|", fixity_def("<|", Fixity::right(0)));
+    assert_parse_ast!("infix 4 <=>", fixity_def("<=>", Fixity::non_assoc(4)));
+
+    assert_parse!(
+        "infixr 5 <+>\nlet join a b c = a <+> b <+> c",
+        program([
+            fixity_def("<+>", Fixity::right(5)),
+            fn_def(
+                "join",
+                vars(&["a", "b", "c"]),
+                op_call("<+>", var("a"), op_call("<+>", var("b"), var("c")))
+            )
+        ])
+    );
+
+    let parser_ctx = ParserContext::new();
+    let expr = parse_expr("a <+> b * c", &parser_ctx).unwrap();
+    assert_eq!(
+        expr,
+        op_call("*", op_call("<+>", var("a"), var("b")), var("c"))
+    );
+
+    parser_ctx.declare_fixity("<+>", Fixity::left(6));
+    let expr = parse_expr("a <+> b * c", &parser_ctx).unwrap();
+    assert_eq!(
+        expr,
+        op_call("<+>", var("a"), op_call("*", var("b"), var("c")))
+    );
+
+    // a program's fixity definitions apply to all of its code, incl. before the definition
+    assert_parse!(
+        "let join a b c = a <+> b <+> c\ninfixr 5 <+>",
+        program([
+            fn_def(
+                "join",
+                vars(&["a", "b", "c"]),
+                op_call("<+>", var("a"), op_call("<+>", var("b"), var("c")))
+            ),
+            fixity_def("<+>", Fixity::right(5)),
+        ])
+    );
+
+    // but not to any other code parsed afterwards
+    let parser_ctx = ParserContext::new();
+    parse("infixr 5 <|>", &parser_ctx).unwrap();
+    let expr = parse_expr("a <|> b <|> c", &parser_ctx).unwrap();
+    assert_eq!(
+        expr,
+        op_call("<|>", op_call("<|>", var("a"), var("b")), var("c"))
+    );
+
+    let code = "infixr 5 <+>\nlet s = \"\"\"\ninfixl 6 <|>\n\"\"\"\n// infix 4 <=>\n  infixl 0 |||";
+    assert_eq!(
+        parse_fixity_defs(code, &parser_ctx),
+        vec![
+            FixityDef::new("<+>".into(), Fixity::right(5)),
+            FixityDef::new("|||".into(), Fixity::left(0)),
+        ]
+    );
+}
+
+#[test]
+fn macro_defs() {
+    assert_parse_ast!(
+        "macro unless cond body = ^(if ~cond then ^none else ~body)",
+        macro_def(
+            "unless",
+            vars(&["cond", "body"]),
+            quoted(if_else(
+                unquoted(var("cond")),
+                symbol("none"),
+                unquoted(var("body"))
+            ))
+        )
+    );
+
+    assert_parse!(
+        "macro twice expr = ^({~expr, ~expr})\nlet main = twice 1",
+        program([
+            macro_def(
+                "twice",
+                vars(&["expr"]),
+                quoted(tuple_lit([unquoted(var("expr")), unquoted(var("expr"))]))
+            ),
+            fn_def("main", vars(&[]), fn_call("twice", [number_lit(1)]))
+        ])
+    );
+
+    let parser_ctx = ParserContext::new();
+    assert!(parse_ast("macro nothing = 1", &parser_ctx).is_err());
+
+    let code = "macro twice expr =\n    ^({ ~(expr), ~(expr) })";
+    let ast = parse_ast(code, &parser_ctx).unwrap();
+    assert_eq!(ast.to_string(), code);
+}
+
 #[test]
 fn comments() {
     assert_parse!("// a comment", program([root_comment(" a comment")]));
@@ -621,6 +1083,68 @@ fn type_defs() {
     );
 }
 
+#[test]
+fn union_type_defs() {
+    let result_type_def = type_def(
+        "Result",
+        union_type([
+            ("Ok", vec![type_ref("val")]),
+            ("Err", vec![type_ref("err")]),
+        ]),
+    );
+
+    assert_parse_ast!("type Result = Ok val | Err err", result_type_def.to_owned());
+
+    assert_parse_ast!(
+        "type Result
+           = Ok val
+           | Err err",
+        result_type_def
+    );
+
+    assert_parse_ast!(
+        "type Shape = Circle Int | Rect Int Int | Empty",
+        type_def(
+            "Shape",
+            union_type([
+                ("Circle", vec![int_type()]),
+                ("Rect", vec![int_type(), int_type()]),
+                ("Empty", vec![]),
+            ])
+        )
+    );
+
+    assert_parse_ast!(
+        "let isOk (Ok val) = true",
+        fn_def(
+            "isOk",
+            [constructor_p("Ok", [var_p("val")])],
+            bool_lit(true)
+        )
+    );
+
+    assert_parse_ast!(
+        "let area (Rect w h) = w * h",
+        fn_def(
+            "area",
+            [constructor_p("Rect", [var_p("w"), var_p("h")])],
+            op_call("*", var("w"), var("h"))
+        )
+    );
+
+    assert_parse_ast!(
+        "let withDefault Nothing (Just (Just x)) = x",
+        fn_def(
+            "withDefault",
+            [
+                constructor_p("Nothing", []),
+                constructor_p("Just", [constructor_p("Just", [var_p("x")])])
+            ],
+            var("x")
+        )
+    );
+}
+
 #[test]
 fn let_expressions() {
     assert_parse_expr!(
@@ -941,6 +1465,44 @@ fn variables() {
 
     assert_parse_expr!(".foo", prop_fn_ref("foo"));
 
+    assert_parse_expr!("p.age", prop_access(var("p"), "age"));
+
+    assert_parse_expr!(
+        "c.location.lat",
+        prop_access(prop_access(var("c"), "location"), "lat")
+    );
+
+    assert_parse_expr!(
+        "p.age >= 10",
+        op_call(">=", prop_access(var("p"), "age"), number_lit(10))
+    );
+
+    assert_parse_expr!(
+        "(f x).name.first",
+        prop_access(prop_access(fn_call("f", [var("x")]), "name"), "first")
+    );
+
+    // property access on anything but variables prints with its base in parentheses,
+    // e.g. to tell it apart from qualified names
+    let ctx = ParserContext::new();
+    for expr in [
+        prop_access(fn_call("f", [var("x")]), "name"),
+        prop_access(prop_access(var("c"), "location"), "lat"),
+        prop_access(op_call("+", var("a"), var("b")), "x"),
+        prop_access(prop_access(const_or_type_ref("Origin"), "pos"), "x"),
+        prop_access(list_lit([number_lit(1)]), "x"),
+    ] {
+        assert_eq!(parse_expr(&expr.to_string(), &ctx), Ok(expr));
+    }
+    assert_eq!(
+        prop_access(fn_call("f", [var("x")]), "name").to_string(),
+        "(f x).name"
+    );
+    assert_eq!(
+        prop_access(prop_access(const_or_type_ref("Origin"), "pos"), "x").to_string(),
+        "(Origin).pos.x"
+    );
+
     assert_parse_expr!("Foo", const_or_type_ref("Foo"));
 
     assert_parse_expr!(
@@ -960,6 +1522,21 @@ fn variables() {
     .unwrap();
 }
 
+#[test]
+fn fn_refs() {
+    assert_parse_expr!("&add", fn_ref("add"));
+    assert_parse_expr!("&Std.List.map", fn_ref("Std.List.map"));
+
+    assert_parse_expr!(
+        "Std.List.map xs &inc",
+        fn_call("Std.List.map", [var("xs"), fn_ref("inc")])
+    );
+
+    assert_parse_expr!("{&inc, &dec}", tuple_lit([fn_ref("inc"), fn_ref("dec")]));
+
+    assert_parse_expr!("a && b", op_call("&&", var("a"), var("b")));
+}
+
 #[test]
 fn lambdas() {
     assert_parse_expr!("-> ^ok", lambda([], symbol("ok")));
@@ -1230,3 +1807,228 @@ fn if_else_expr() {
         )
     );
 }
+
+#[test]
+fn case_expressions() {
+    assert_parse_expr!(
+        "case x of 0 -> ^zero, _ -> ^other",
+        case_of(
+            var("x"),
+            [(number_p(0), symbol("zero")), (any_p(), symbol("other"))]
+        )
+    );
+
+    assert_parse_expr!(
+        "case (f x) of
+            (Ok val) -> val
+            (Err e) -> default e",
+        case_of(
+            fn_call("f", [var("x")]),
+            [
+                (constructor_p("Ok", [var_p("val")]), var("val")),
+                (
+                    constructor_p("Err", [var_p("e")]),
+                    fn_call("default", [var("e")])
+                ),
+            ]
+        )
+    );
+
+    assert_parse_ast!(
+        "let sum xs =
+            case xs of
+                [] -> 0
+                [x :: rest] -> x + (sum rest)",
+        fn_def(
+            "sum",
+            vars(&["xs"]),
+            case_of(
+                var("xs"),
+                [
+                    (empty_list_p(), number_lit(0)),
+                    (
+                        list_cons_p(var_p("x"), var_p("rest")),
+                        op_call("+", var("x"), fn_call("sum", [var("rest")]))
+                    ),
+                ]
+            )
+        )
+    );
+
+    assert_parse_expr!(
+        "case g x of
+            None -> (case y of 1 -> ^one, _ -> ^many)
+            (Just z) -> z",
+        case_of(
+            fn_call("g", [var("x")]),
+            [
+                (
+                    constructor_p("None", []),
+                    case_of(
+                        var("y"),
+                        [(number_p(1), symbol("one")), (any_p(), symbol("many"))]
+                    )
+                ),
+                (constructor_p("Just", [var_p("z")]), var("z")),
+            ]
+        )
+    );
+
+    let ctx = ParserContext::new();
+    let parsed = parse_expr(
+        "case g x of\n  None -> (case y of 1 -> ^one, _ -> ^many)\n  (Just z) -> z + 1",
+        &ctx,
+    )
+    .unwrap();
+    assert_eq!(parse_expr(&parsed.to_string(), &ctx), Ok(parsed));
+
+    assert!(parse_expr("case x of", &ctx).is_err());
+}
+
+#[test]
+fn guards() {
+    assert_parse_ast!(
+        "let classify n when n < 0 = ^negative",
+        guarded_fn_def(
+            "classify",
+            vars(&["n"]),
+            op_call("<", var("n"), number_lit(0)),
+            symbol("negative")
+        )
+    );
+
+    assert_parse_ast!(
+        "let max x y when (x > y) && (x != 0) =\n    x",
+        guarded_fn_def(
+            "max",
+            vars(&["x", "y"]),
+            op_call(
+                "&&",
+                op_call(">", var("x"), var("y")),
+                op_call("!=", var("x"), number_lit(0))
+            ),
+            var("x")
+        )
+    );
+
+    assert_parse_ast!(
+        "let f whenever = whenever",
+        fn_def("f", vars(&["whenever"]), var("whenever"))
+    );
+
+    assert_parse_expr!(
+        "x when x > 0 -> ^positive",
+        guarded_lambda_p([(
+            [var_p("x")],
+            Some(op_call(">", var("x"), number_lit(0))),
+            symbol("positive")
+        )])
+    );
+
+    assert_parse_expr!(
+        "(
+            [x :: _] when isEven x -> x,
+            _ -> 0
+        )",
+        guarded_lambda_p([
+            (
+                vec![list_cons_p(var_p("x"), any_p())],
+                Some(fn_call("isEven", [var("x")])),
+                var("x")
+            ),
+            (vec![any_p()], None, number_lit(0)),
+        ])
+    );
+
+    for code in [
+        "let classify n when n < 0 = ^negative",
+        "x when x > 0 -> ^positive",
+        "([x :: _] when isEven x -> x, _ -> 0)",
+    ] {
+        let ctx = ParserContext::new();
+        match crate::parse(code, &ctx) {
+            Ok(program) => assert_eq!(crate::parse(&program.to_string(), &ctx), Ok(program)),
+            Err(_) => {
+                let expr = parse_expr(code, &ctx).unwrap();
+                assert_eq!(parse_expr(&expr.to_string(), &ctx), Ok(expr));
+            }
+        }
+    }
+}
+
+#[test]
+fn spans() {
+    let program = parse_file("// f\nlet f x =\n  g x", "f.roga", &ParserContext::new()).unwrap();
+    let fn_def = program.iter().nth(1).unwrap();
+    let span = fn_def.span().unwrap();
+    assert_eq!(span.to_string(), "f.roga:2:1");
+    assert_eq!((span.start(), span.end()), (5, 20));
+
+    match &**fn_def {
+        AST::FnDef(fn_def) => {
+            let fn_def = fn_def.borrow();
+            let variant = fn_def.get_variant(0).unwrap();
+            assert_eq!(
+                variant.0.iter().next().unwrap().span().unwrap().to_string(),
+                "f.roga:2:7"
+            );
+            match &*variant.2 {
+                FnDefBody::RogatoFn(body) => {
+                    assert_eq!(body.span().unwrap().to_string(), "f.roga:3:3")
+                }
+                _ => panic!("Expected RogatoFn body"),
+            }
+        }
+        ast => panic!("Expected FnDef but got: {ast}"),
+    }
+
+    let expr = parse_expr("a +\n  b * c", &ParserContext::new()).unwrap();
+    assert_eq!(expr.span().unwrap().to_string(), "1:1");
+    assert_eq!(expr.span().unwrap().end(), 11);
+}
+
+#[test]
+fn error_recovery() {
+    let code = "let f x = x +\n\nlet g x = x\n\ntype Foo ::\n\nlet h = 1";
+    let (parsed, diagnostics) = parse_with_recovery(code, Some("f.roga"), &ParserContext::new());
+
+    assert_eq!(
+        parsed,
+        program([
+            fn_def("g", vars(&["x"]), var("x")),
+            fn_def("h", vars(&[]), number_lit(1))
+        ])
+    );
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|d| d.span().to_string())
+            .collect::<Vec<_>>(),
+        vec!["f.roga:1:14", "f.roga:5:12"]
+    );
+    assert!(diagnostics[1].expected().any(|token| token == "\"Bool\""));
+
+    // definitions are only skipped up to the next line starting a root definition outside of
+    // string literals, and each error is reported where it occurs
+    let code =
+        "let a = (\nlet b = \"\"\"\nlet c = )\n\"\"\"\n  let d = )\n\nlet e x = x + \nlet f = 1";
+    let (parsed, diagnostics) = parse_with_recovery(code, None, &ParserContext::new());
+    assert_eq!(
+        parsed,
+        program([
+            fn_def("b", vars(&[]), string_lit("\nlet c = )\n")),
+            fn_def("f", vars(&[]), number_lit(1))
+        ])
+    );
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|d| d.span().to_string())
+            .collect::<Vec<_>>(),
+        vec!["1:10", "5:11", "7:14"]
+    );
+
+    let (parsed, diagnostics) = parse_with_recovery("let f x = x", None, &ParserContext::new());
+    assert_eq!(parsed, program([fn_def("f", vars(&["x"]), var("x"))]));
+    assert!(diagnostics.is_empty());
+}
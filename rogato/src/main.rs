@@ -1,10 +1,12 @@
-#[allow(unused_imports)]
-use rogato_interpreter::{EvalContext, Evaluate};
-use rogato_parser::{parse, ParserContext};
+use rogato_common::{
+    ast::Identifier,
+    val::{self, ValueRef},
+};
+use rogato_interpreter::EvalContext;
+use rogato_parser::{parse_file, parse_with_recovery, ParserContext};
 
 use clap::Parser;
 use indent_write::indentable::Indentable;
-use std::collections::HashSet;
 use std::fmt::Display;
 use std::fs::File;
 use std::io::Read;
@@ -40,12 +42,26 @@ enum Command {
 
     #[command(name = "compile", about = "Compiles the given source file")]
     CompileFile(FileInfo),
+
+    #[command(
+        name = "check",
+        about = "Checks the given source files for parse errors"
+    )]
+    CheckFile(FileInfo),
 }
 
 #[derive(Parser, PartialEq, Eq, Debug)]
 struct FileInfo {
     #[arg(long, short)]
     files: Vec<String>,
+
+    // Directories to search for modules imported via `use`
+    #[arg(long = "search-path", short = 'I')]
+    search_paths: Vec<String>,
+
+    // Arguments passed on to the program's main function (after `--`)
+    #[arg(last = true)]
+    args: Vec<String>,
 }
 
 #[derive(Parser, PartialEq, Eq, Debug)]
@@ -53,14 +69,10 @@ struct ReplInfo {
     // Files to parse & load before running REPL
     #[arg(alias = "load", long, short = 'l')]
     preload: Vec<String>,
-}
 
-fn std_lib_preloads() -> HashSet<String> {
-    HashSet::from([
-        "lib/Std.roga".into(),
-        "lib/Std/List.roga".into(),
-        "lib/Std/Map.roga".into(),
-    ])
+    // Directories to search for modules imported via `use`
+    #[arg(long = "search-path", short = 'I')]
+    search_paths: Vec<String>,
 }
 
 #[cfg_attr(feature = "flame_it", flame)]
@@ -70,24 +82,22 @@ fn main() -> anyhow::Result<()> {
 
     match args.command {
         Command::RunRepl(repl_info) => {
-            let mut preloads: HashSet<String> = std_lib_preloads();
-            preloads.extend(repl_info.preload);
-
-            let unique_preloads: Vec<String> = preloads.into_iter().collect();
-            repl::run_repl(&unique_preloads)?;
+            repl::run_repl(&repl_info.preload, &repl_info.search_paths)?;
         }
         Command::EvaluateFile(file_info) => {
-            for file in file_info.files.iter() {
-                println!("Attempting file parse: {file}");
-                let file_path = Path::new(file);
-                if file_path.exists() {
-                    read_parse_file(file_path, &parser_ctx);
-                } else {
-                    eprintln!("File not found: {file:?}. Aborting.");
-                }
+            if let Err(error) = eval_files(&file_info) {
+                eprintln!("❌ {error}");
+                std::process::exit(1);
             }
         }
         Command::CompileFile(_file_info) => todo!(),
+        Command::CheckFile(file_info) => {
+            let error_count = check_files(&file_info, &parser_ctx)?;
+            if error_count > 0 {
+                eprintln!("❌ {error_count} error(s) found");
+                std::process::exit(1);
+            }
+        }
     }
 
     #[cfg(feature = "flame_it")]
@@ -96,19 +106,129 @@ fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-fn read_parse_file(file_path: &Path, parser_ctx: &ParserContext) {
-    match File::open(file_path) {
-        Ok(mut file) => {
-            let mut buf = String::new();
-            file.read_to_string(&mut buf).unwrap();
-            println!("\n📂\t{}", file_path.display());
-            let parse_result = parse(buf.as_str(), parser_ctx);
-            print_parse_result(buf.as_str(), &parse_result);
+fn eval_files(file_info: &FileInfo) -> anyhow::Result<()> {
+    let mut eval_ctx = EvalContext::try_new()
+        .map_err(|e| anyhow::anyhow!("Failed to load std lib: {}", e.report()))?;
+    for search_path in file_info.search_paths.iter() {
+        eval_ctx.add_search_path(search_path);
+    }
+
+    // the modules the files' definitions went into, i.e. the ones they declared, if any
+    let mut file_modules = Vec::with_capacity(file_info.files.len());
+    for file in file_info.files.iter() {
+        let file_path = Path::new(file);
+        if !file_path.exists() {
+            anyhow::bail!("File not found: {file:?}. Aborting.");
+        }
+        if let Some(dir) = file_path.parent() {
+            eval_ctx.add_search_path(dir);
+        }
+        eval_file(file_path, &mut eval_ctx)?;
+        file_modules.push(eval_ctx.current_module().id());
+    }
+
+    if let Some(result) = call_main(&mut eval_ctx, &file_modules, &file_info.args)? {
+        println!("{result}");
+    }
+
+    Ok(())
+}
+
+fn eval_file(file_path: &Path, eval_ctx: &mut EvalContext) -> anyhow::Result<ValueRef> {
+    let mut buf = String::new();
+    File::open(file_path)
+        .and_then(|mut file| file.read_to_string(&mut buf))
+        .map_err(|e| anyhow::anyhow!("Could not open source file {}: {e}", file_path.display()))?;
+
+    // each file only gets the fixities of the modules it imports itself
+    let parser_ctx = ParserContext::new();
+    eval_ctx
+        .import_fixities(buf.as_str(), &parser_ctx)
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "{}\n\t\tFailed to load imported modules: {}",
+                file_path.display(),
+                e.report()
+            )
+        })?;
+    let parse_result = parse_file(
+        buf.as_str(),
+        file_path.to_string_lossy().as_ref(),
+        &parser_ctx,
+    );
+    if parse_result.is_err() {
+        print_parse_result(buf.as_str(), &parse_result);
+    }
+    let program = parse_result
+        .map_err(|e| anyhow::anyhow!("{}\n\t\tFailed to parse file: {e}", file_path.display()))?;
+
+    eval_ctx.evaluate_program(&program).map_err(|e| {
+        anyhow::anyhow!(
+            "{}\n\t\tFailed to evaluate file: {}",
+            file_path.display(),
+            e.report()
+        )
+    })
+}
+
+/// Calls the program's `main` function, if defined.
+/// `main` is looked up in the modules of the given files, starting with the last one,
+/// so it doesn't need to be exported from a file's declared module.
+/// If `main` takes any arguments, the command-line arguments are passed to it as a list of strings.
+fn call_main(
+    eval_ctx: &mut EvalContext,
+    file_modules: &[Identifier],
+    args: &[String],
+) -> anyhow::Result<Option<ValueRef>> {
+    let main_id = "main".into();
+    let main_fn = file_modules
+        .iter()
+        .rev()
+        .filter_map(|module_id| eval_ctx.lookup_module(module_id))
+        .find_map(|module| module.lookup_fn(&main_id))
+        .or_else(|| eval_ctx.lookup_fn(&main_id));
+    let main_fn = match main_fn {
+        Some(main_fn) => main_fn,
+        None => return Ok(None),
+    };
+
+    let takes_args = main_fn
+        .borrow()
+        .variants_iter()
+        .any(|variant| !variant.0.is_empty());
+
+    let main_args = if takes_args {
+        vec![val::list(args.iter().map(val::string).collect::<Vec<_>>())]
+    } else {
+        vec![]
+    };
+
+    eval_ctx
+        .call_function_direct(main_fn, &main_args)
+        .map(Some)
+        .map_err(|e| anyhow::anyhow!("main\n\t\tFailed to evaluate: {}", e.report()))
+}
+
+/// Parses the given files in recovery mode, printing all diagnostics.
+/// Returns the total number of errors found.
+fn check_files(file_info: &FileInfo, parser_ctx: &ParserContext) -> anyhow::Result<usize> {
+    let mut error_count = 0;
+    for file in file_info.files.iter() {
+        let mut buf = String::new();
+        File::open(file)
+            .and_then(|mut f| f.read_to_string(&mut buf))
+            .map_err(|e| anyhow::anyhow!("Could not open source file {file}: {e}"))?;
+
+        let (program, diagnostics) = parse_with_recovery(buf.as_str(), Some(file), parser_ctx);
+        if diagnostics.is_empty() {
+            println!("✅ {file} ({} definitions)", program.len());
         }
-        Err(error) => {
-            println!("Could not open source file: {error:?}");
+        for diagnostic in diagnostics.iter() {
+            eprintln!("❌ {diagnostic}");
         }
+        error_count += diagnostics.len();
     }
+    Ok(error_count)
 }
 
 fn print_parse_result<T: Display, E: Display>(code: &str, result: &Result<T, E>) {
@@ -13,8 +13,8 @@ use rustyline_derive::{Completer, Helper, Highlighter, Hinter, Validator};
 
 use rogato_common::ast::ASTDepth;
 use rogato_compiler::Codegen;
-use rogato_interpreter::{EvalContext, EvalError, Evaluate};
-use rogato_parser::{parse, parse_expr, ParseError, ParserContext};
+use rogato_interpreter::{macro_expander::MacroExpander, EvalContext, EvalError};
+use rogato_parser::{parse, parse_expr, parse_file, parse_fixity_defs, ParseError, ParserContext};
 use thiserror::Error;
 
 #[derive(Completer, Helper, Highlighter, Hinter, Validator)]
@@ -46,13 +46,19 @@ fn validated_editor() -> Result<Editor<InputValidator, FileHistory>, ReadlineErr
 }
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
+const EXPAND_COMMAND: &str = ":expand";
 
-pub fn run_repl(files_to_load: &[String]) -> anyhow::Result<()> {
+pub fn run_repl(files_to_load: &[String], search_paths: &[String]) -> anyhow::Result<()> {
     println!("👾 rogātō ⌘ 🏷 ");
     print!("🖥  Interactive Shell ");
     println!("{VERSION} 🦀 \n");
-    println!("Enter rogātō expressions below. You can add new lines via SHIFT-DOWN.\n");
-    let mut eval_ctx = EvalContext::new();
+    println!("Enter rogātō expressions below. You can add new lines via SHIFT-DOWN.");
+    println!("Prefix code with {EXPAND_COMMAND} to show it with all macro calls expanded.\n");
+    let mut eval_ctx = EvalContext::try_new()
+        .map_err(|e| anyhow::anyhow!("Failed to load std lib: {}", e.report()))?;
+    for search_path in search_paths {
+        eval_ctx.add_search_path(search_path);
+    }
     let parser_ctx = ParserContext::new();
 
     let mut counter = 0usize;
@@ -74,13 +80,23 @@ pub fn run_repl(files_to_load: &[String]) -> anyhow::Result<()> {
             Ok(mut file) => {
                 let mut buf = String::new();
                 file.read_to_string(&mut buf).unwrap();
-                match parse(buf.as_str(), &parser_ctx) {
-                    Ok(program) => match program.evaluate(&mut eval_ctx) {
+                if let Err(e) = declare_fixities(&parser_ctx, &mut eval_ctx, buf.as_str()) {
+                    eprintln!(
+                        "❌ {file_path_string}\n\t\tFailed to load imported modules: {}",
+                        e.report()
+                    );
+                    continue;
+                }
+                match parse_file(buf.as_str(), file_path_string, &parser_ctx) {
+                    Ok(program) => match eval_ctx.evaluate_program(&program) {
                         Ok(_) => {
                             println!("✅ {}", file_path.display());
                         }
                         Err(e) => {
-                            eprintln!("❌ {file_path_string}\n\t\tFailed to evaluate file: {e}")
+                            eprintln!(
+                                "❌ {file_path_string}\n\t\tFailed to evaluate file: {}",
+                                e.report()
+                            )
                         }
                     },
                     Err(e) => {
@@ -107,6 +123,10 @@ pub fn run_repl(files_to_load: &[String]) -> anyhow::Result<()> {
         match readline {
             Ok(line) => {
                 rl.add_history_entry(line.as_str())?;
+                if let Some(code) = line.trim_start().strip_prefix(EXPAND_COMMAND) {
+                    parse_expand_print(&parser_ctx, &eval_ctx, counter, code);
+                    continue;
+                }
                 match parse_eval_print(&parser_ctx, &mut eval_ctx, &mut compiler, counter, &line) {
                     Ok(_) => {
                         continue;
@@ -175,6 +195,11 @@ fn parse_eval_print(
     counter: usize,
     code: &str,
 ) -> anyhow::Result<()> {
+    if let Err(e) = declare_fixities(parse_ctx, eval_ctx, code) {
+        eprintln!("{counter:03} ❌ {}\n", e.report());
+        return Ok(());
+    }
+
     match parse(code, parse_ctx) {
         Ok(ast) => {
             if rogato_common::util::is_debug_enabled() {
@@ -197,7 +222,7 @@ fn parse_eval_print(
                 }
             }
 
-            match ast.evaluate(eval_ctx) {
+            match eval_ctx.evaluate_program(&ast) {
                 Ok(val) => {
                     if val.ast_depth() > 5 {
                         println!("{counter:03} ✅\n{val}\n");
@@ -207,7 +232,7 @@ fn parse_eval_print(
                     Ok(())
                 }
                 Err(e) => {
-                    eprintln!("{counter:03} ❌ {e}\n");
+                    eprintln!("{counter:03} ❌ {}\n", e.report());
                     Ok(())
                 }
             }
@@ -248,7 +273,7 @@ fn parse_eval_print(
                         println!("{counter:03} 🌳 {ast:?}\n\n{ast}\n");
                     }
 
-                    match ast.evaluate(eval_ctx) {
+                    match eval_ctx.evaluate_expression(&ast) {
                         Ok(val) => {
                             if val.ast_depth() > 5 {
                                 println!("{counter:03} ✅\n{val}\n");
@@ -258,7 +283,7 @@ fn parse_eval_print(
                             Ok(())
                         }
                         Err(e) => {
-                            eprintln!("{counter:03} ❌ {e}\n");
+                            eprintln!("{counter:03} ❌ {}\n", e.report());
                             Ok(())
                         }
                     }
@@ -271,3 +296,46 @@ fn parse_eval_print(
         }
     }
 }
+
+/// Declares the fixities of all modules imported by the given code, as well as its own ones,
+/// for all code entered in the REPL from now on.
+fn declare_fixities(
+    parse_ctx: &ParserContext,
+    eval_ctx: &mut EvalContext,
+    code: &str,
+) -> Result<(), EvalError> {
+    eval_ctx.import_fixities(code, parse_ctx)?;
+    for fixity_def in parse_fixity_defs(code, parse_ctx) {
+        parse_ctx.declare_fixity(fixity_def.id().clone(), fixity_def.fixity());
+    }
+    Ok(())
+}
+
+/// Prints the given code with all macro calls expanded. Its definitions get evaluated while
+/// expanding, just like when evaluating the code, so macros can call the functions it defines.
+/// They're evaluated in an isolated copy of the session, which stays unchanged.
+fn parse_expand_print(
+    parse_ctx: &ParserContext,
+    eval_ctx: &EvalContext,
+    counter: usize,
+    code: &str,
+) {
+    let mut expander = MacroExpander::isolated(eval_ctx);
+    let expanded = match parse(code, parse_ctx) {
+        Ok(program) => expander
+            .expand_program(&program)
+            .map(|program| program.to_string()),
+        Err(_) => match parse_expr(code.trim(), parse_ctx) {
+            Ok(expr) => expander.expand_expr(&expr).map(|expr| expr.to_string()),
+            Err(e) => {
+                eprintln!("{counter:03} ❌ {e:?}\n");
+                return;
+            }
+        },
+    };
+
+    match expanded {
+        Ok(code) => println!("{counter:03} 🔎\n{code}\n"),
+        Err(e) => eprintln!("{counter:03} ❌ {}\n", e.report()),
+    }
+}
@@ -0,0 +1,97 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+/// Writes the given source files into a fresh temporary directory, returning its path.
+fn source_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rogato-eval-{name}-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for (file_name, code) in files.iter() {
+        fs::write(dir.join(file_name), code).unwrap();
+    }
+    dir
+}
+
+/// Runs `rogato eval` on the given files within `dir`, passing on the given program arguments.
+fn rogato_eval(dir: &Path, files: &[&str], args: &[&str]) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_rogato"));
+    command.arg("eval");
+    for file in files.iter() {
+        command.arg("-f").arg(dir.join(file));
+    }
+    command.arg("--").args(args).output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn files_are_evaluated_in_one_context_and_main_gets_called() {
+    let dir = source_dir(
+        "main",
+        &[
+            ("helpers.roga", "let double x = x * 2\n"),
+            ("main.roga", "let main args = {double 21, args}\n"),
+        ],
+    );
+
+    let output = rogato_eval(&dir, &["helpers.roga", "main.roga"], &["hello", "world"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(stdout(&output), "{ 42, [ hello, world ] }\n");
+
+    // without a `main` function, files just get evaluated
+    let output = rogato_eval(&dir, &["helpers.roga"], &[]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(stdout(&output), "");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn main_gets_called_within_the_declared_module() {
+    let dir = source_dir(
+        "module",
+        &[
+            (
+                "app.roga",
+                "module App {run}\nlet run x = x + 1\nlet main args = run 41\n",
+            ),
+            ("other.roga", "module Other\nlet helper x = x\n"),
+        ],
+    );
+
+    for files in [&["app.roga"][..], &["app.roga", "other.roga"]] {
+        let output = rogato_eval(&dir, files, &[]);
+        assert!(output.status.success(), "{files:?}: {output:?}");
+        assert_eq!(stdout(&output), "42\n", "{files:?}");
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn errors_exit_with_failure() {
+    let dir = source_dir(
+        "errors",
+        &[
+            ("parse_error.roga", "let broken x = (x +\n"),
+            ("eval_error.roga", "use Missing.Module\n"),
+            ("main_error.roga", "let main args = undefinedFn args\n"),
+        ],
+    );
+
+    for file in [
+        "parse_error.roga",
+        "eval_error.roga",
+        "main_error.roga",
+        "missing.roga",
+    ] {
+        let output = rogato_eval(&dir, &[file], &[]);
+        assert_eq!(output.status.code(), Some(1), "{file}: {output:?}");
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}